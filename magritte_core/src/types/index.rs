@@ -30,19 +30,21 @@ impl From<&String> for IndexSpecifics {
         match value.as_str() {
             "" => IndexSpecifics::None,
             s if s.starts_with("SEARCH") => {
-                let mut parts = s.split_whitespace();
-                parts.next(); // Skip "SEARCH"
-                let analyzer = parts
-                    .find(|&part| part.starts_with("ANALYZER"))
-                    .map(|s| s[9..].to_string());
-                let bm25 = parts.find(|&part| part.starts_with("BM25")).map(|s| {
-                    let nums: Vec<f32> = s[5..s.len() - 1]
+                let tokens: Vec<&str> = s.split_whitespace().collect();
+                let analyzer = tokens
+                    .iter()
+                    .position(|&part| part == "ANALYZER")
+                    .and_then(|i| tokens.get(i + 1))
+                    .map(|s| s.to_string());
+                let bm25 = s.find("BM25(").and_then(|start| {
+                    let rest = &s[start + 5..];
+                    let nums: Vec<f32> = rest[..rest.find(')')?]
                         .split(',')
-                        .map(|n| n.trim().parse().unwrap())
-                        .collect();
-                    (nums[0], nums[1])
+                        .map(|n| n.trim().parse().ok())
+                        .collect::<Option<_>>()?;
+                    (nums.len() == 2).then(|| (nums[0], nums[1]))
                 });
-                let highlights = parts.any(|part| part == "HIGHLIGHTS");
+                let highlights = tokens.contains(&"HIGHLIGHTS");
                 IndexSpecifics::Search {
                     analyzer,
                     bm25,
@@ -160,3 +162,28 @@ impl Display for IndexSpecifics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_specifics_round_trip() {
+        let specifics = IndexSpecifics::Search {
+            analyzer: Some("ascii".to_string()),
+            bm25: Some((1.2, 0.75)),
+            highlights: true,
+        };
+        assert_eq!(IndexSpecifics::from(&specifics.to_string()), specifics);
+
+        let parsed = IndexSpecifics::from(&"SEARCH ANALYZER english  ".to_string());
+        assert_eq!(
+            parsed,
+            IndexSpecifics::Search {
+                analyzer: Some("english".to_string()),
+                bm25: None,
+                highlights: false,
+            }
+        );
+    }
+}
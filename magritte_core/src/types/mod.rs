@@ -28,6 +28,7 @@ pub use projection::*;
 pub use range::*;
 pub use record::RecordRef;
 pub use record::SurrealId;
+pub use record::{IdGenerator, IdKey};
 pub use return_type::*;
 pub use schema::SchemaType;
 
@@ -68,6 +69,9 @@ pub trait TableType: RecordType + HasId {
 }
 
 pub trait ColumnType: ColumnTypeLite {
+    /// Table the column belongs to
+    type Table: NamedType;
+
     fn table_name() -> &'static str;
     fn column_name(&self) -> &str;
     fn column_type(&self) -> &str;
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Copy, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum Operator {
     Eq,
     Gt,
@@ -14,6 +15,10 @@ pub enum Operator {
     Contains,    // For array/set membership
     ContainsAll, // For checking if all elements exist
     ContainsAny, // For checking if any elements exist
+    Intersects,  // For geometries sharing any point
+    /// Full-text match `@@`, or `@N@` with a predicate reference for
+    /// `search::score(N)` and `search::highlight`
+    Matches(Option<u8>),
     Raw,
 }
 
@@ -40,6 +45,9 @@ impl From<Operator> for String {
             Operator::Contains => "CONTAINS".into(),
             Operator::ContainsAll => "CONTAINSALL".into(),
             Operator::ContainsAny => "CONTAINSANY".into(),
+            Operator::Intersects => "INTERSECTS".into(),
+            Operator::Matches(None) => "@@".into(),
+            Operator::Matches(Some(reference)) => format!("@{}@", reference),
             Operator::Raw => "".into(),
         }
     }
@@ -54,5 +62,7 @@ mod tests {
         assert_eq!(String::from(Operator::Eq), "=");
         assert_eq!(String::from(Operator::NotEq), "!=");
         assert_eq!(String::from(Operator::Contains), "CONTAINS");
+        assert_eq!(String::from(Operator::Matches(None)), "@@");
+        assert_eq!(String::from(Operator::Matches(Some(1))), "@1@");
     }
 }
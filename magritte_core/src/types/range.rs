@@ -1,3 +1,4 @@
+use crate::types::{RecordType, SurrealId};
 use std::fmt;
 use std::fmt::{Debug, Display};
 
@@ -10,6 +11,36 @@ pub enum RangeTarget {
     To(String),            // person:..1000
     ToInclusive(String),   // person:..=1000
     Range(String, String), // person:1..5000
+    RangeInclusive(String, String), // person:1..=5000
+}
+
+impl RangeTarget {
+    /// Range over array keys, e.g. `['dev', d'2024-01-01']..['dev', d'2024-02-01']`.
+    /// Parts are SurrealQL values.
+    pub fn array(start: &[&str], end: &[&str], inclusive: bool) -> Self {
+        let start = format!("[{}]", start.join(", "));
+        let end = format!("[{}]", end.join(", "));
+        if inclusive {
+            Self::RangeInclusive(start, end)
+        } else {
+            Self::Range(start, end)
+        }
+    }
+
+    /// Range between the keys of two record ids
+    pub fn between<T: RecordType>(
+        start: &SurrealId<T>,
+        end: &SurrealId<T>,
+        inclusive: bool,
+    ) -> Self {
+        let start = start.id().to_string();
+        let end = end.id().to_string();
+        if inclusive {
+            Self::RangeInclusive(start, end)
+        } else {
+            Self::Range(start, end)
+        }
+    }
 }
 
 impl Display for RangeTarget {
@@ -18,10 +49,29 @@ impl Display for RangeTarget {
             Self::Count(count) => write!(f, "{}", count),
             Self::Full => write!(f, ".."),
             Self::FullInclusive => write!(f, "..="),
-            Self::From(start) => write!(f, "{}...", start),
+            Self::From(start) => write!(f, "{}..", start),
             Self::To(end) => write!(f, "..{}", end),
             Self::ToInclusive(end) => write!(f, "..={}", end),
             Self::Range(start, end) => write!(f, "{}..{}", start, end),
+            Self::RangeInclusive(start, end) => write!(f, "{}..={}", start, end),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_range() {
+        let range = RangeTarget::array(
+            &["'sensor-1'", "d'2024-01-01T00:00:00Z'"],
+            &["'sensor-1'", "time::now()"],
+            true,
+        );
+        assert_eq!(
+            range.to_string(),
+            "['sensor-1', d'2024-01-01T00:00:00Z']..=['sensor-1', time::now()]"
+        );
+    }
+}
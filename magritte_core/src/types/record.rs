@@ -1,5 +1,8 @@
 use crate::types::RecordType;
+use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display};
 use std::marker::PhantomData;
 use std::str::FromStr;
 use surrealdb::{sql, RecordId, RecordIdKey};
@@ -56,6 +59,139 @@ where
     pub fn id(&self) -> &RecordIdKey {
         self.0.key()
     }
+
+    fn from_key(key: sql::Id) -> Result<Self> {
+        let thing = sql::Thing::from((T::table_name().to_string(), key));
+        let record_id = thing
+            .to_string()
+            .parse::<RecordId>()
+            .map_err(|e| anyhow!("Invalid record id {}: {}", thing, e))?;
+        Ok(Self(record_id, PhantomData))
+    }
+
+    /// Integer key, `table:42`
+    pub fn number(id: i64) -> Self {
+        Self::new(id)
+    }
+
+    /// ULID string key, as generated by `ulid()`
+    pub fn ulid(ulid: &str) -> Result<Self> {
+        const CROCKFORD: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+        let ulid = ulid.to_ascii_uppercase();
+        if ulid.len() != 26 || !ulid.chars().all(|c| CROCKFORD.contains(c)) {
+            bail!("Invalid ULID {}", ulid);
+        }
+        Ok(Self::new(ulid))
+    }
+
+    /// Array key, e.g. `table:['device', 42]`
+    pub fn array<V: Serialize>(parts: V) -> Result<Self> {
+        match serde_json::to_value(parts)? {
+            value @ Value::Array(_) => Self::from_json_key(value),
+            other => bail!("Array key must serialize to an array, got {}", other),
+        }
+    }
+
+    /// Object key, e.g. `table:{ device: 'a', at: 42 }`
+    pub fn object<V: Serialize>(fields: V) -> Result<Self> {
+        match serde_json::to_value(fields)? {
+            value @ Value::Object(_) => Self::from_json_key(value),
+            other => bail!("Object key must serialize to an object, got {}", other),
+        }
+    }
+
+    /// Parses a key written in SurrealQL, for keys holding values JSON cannot
+    /// express, e.g. `['device', d'2024-01-01T00:00:00Z']`
+    pub fn parse_key(key: &str) -> Result<Self> {
+        let thing = sql::thing(&format!("{}:{}", T::table_name(), key))
+            .map_err(|e| anyhow!("Invalid record key {}: {}", key, e))?;
+        Self::from_key(thing.id)
+    }
+
+    fn from_json_key(value: Value) -> Result<Self> {
+        let key = match sql::json(&value.to_string())? {
+            sql::Value::Array(array) => sql::Id::Array(array),
+            sql::Value::Object(object) => sql::Id::Object(object),
+            other => bail!("Unsupported record key {}", other),
+        };
+        Self::from_key(key)
+    }
+
+    /// The key of the record id
+    pub fn key(&self) -> IdKey {
+        let parsed = sql::thing(&self.to_string()).map(|thing| thing.id);
+        match parsed {
+            Ok(sql::Id::Number(n)) => IdKey::Number(n),
+            Ok(sql::Id::String(s)) => IdKey::String(s),
+            Ok(sql::Id::Uuid(u)) => IdKey::Uuid(u.to_raw()),
+            Ok(sql::Id::Array(a)) => match sql::Value::Array(a).into_json() {
+                Value::Array(parts) => IdKey::Array(parts),
+                other => IdKey::String(other.to_string()),
+            },
+            Ok(sql::Id::Object(o)) => match sql::Value::Object(o).into_json() {
+                Value::Object(fields) => IdKey::Object(fields),
+                other => IdKey::String(other.to_string()),
+            },
+            _ => IdKey::String(self.0.key().to_string()),
+        }
+    }
+
+    pub fn as_number(&self) -> Option<i64> {
+        match self.key() {
+            IdKey::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<String> {
+        match self.key() {
+            IdKey::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<Vec<Value>> {
+        match self.key() {
+            IdKey::Array(parts) => Some(parts),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<Map<String, Value>> {
+        match self.key() {
+            IdKey::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+/// Key of a record id, as returned by [`SurrealId::key`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdKey {
+    Number(i64),
+    String(String),
+    /// Native UUID key, `table:u'...'`
+    Uuid(String),
+    Array(Vec<Value>),
+    Object(Map<String, Value>),
+}
+
+/// Server-side id generation for CREATE, `table:rand()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdGenerator {
+    Rand,
+    Ulid,
+    Uuid,
+}
+
+impl Display for IdGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rand => write!(f, "rand()"),
+            Self::Ulid => write!(f, "ulid()"),
+            Self::Uuid => write!(f, "uuid()"),
+        }
+    }
 }
 impl<T> From<String> for SurrealId<T>
 where
@@ -67,11 +203,11 @@ where
     }
 }
 
-impl<T> std::fmt::Display for SurrealId<T>
+impl<T> Display for SurrealId<T>
 where
     T: RecordType,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let table = self.0.table();
         let id = self.0.key().to_string();
         write!(f, "{}:{}", table, id)
@@ -138,7 +274,13 @@ where
     T: RecordType,
 {
     fn from(value: serde_json::Value) -> Self {
-        SurrealId::new(value.to_string())
+        match value {
+            Value::String(s) => SurrealId::new(s),
+            Value::Number(n) if n.is_i64() => SurrealId::number(n.as_i64().unwrap_or_default()),
+            value @ (Value::Array(_) | Value::Object(_)) => SurrealId::from_json_key(value.clone())
+                .unwrap_or_else(|_| SurrealId::new(value.to_string())),
+            other => SurrealId::new(other.to_string()),
+        }
     }
 }
 impl<T> From<&str> for SurrealId<T>
@@ -158,3 +300,53 @@ where
         Ok(SurrealId::new(s.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Reading;
+
+    impl crate::NamedType for Reading {
+        fn table_name() -> &'static str {
+            "readings"
+        }
+    }
+    impl Display for Reading {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("reading")
+        }
+    }
+    impl AsRef<str> for Reading {
+        fn as_ref(&self) -> &str {
+            "reading"
+        }
+    }
+    impl RecordType for Reading {}
+
+    #[test]
+    fn test_json_keys() {
+        let id = SurrealId::<Reading>::from(json!("alice"));
+        assert_eq!(id.as_string().as_deref(), Some("alice"));
+        assert_eq!(SurrealId::<Reading>::from(json!(7)).as_number(), Some(7));
+        let id = SurrealId::<Reading>::from(json!(["sensor", 1]));
+        assert_eq!(id.as_array(), Some(vec![json!("sensor"), json!(1)]));
+        let id = SurrealId::<Reading>::object(json!({"sensor": "a"})).unwrap();
+        assert_eq!(id.key(), IdKey::Object(json!({"sensor": "a"}).as_object().unwrap().clone()));
+    }
+
+    #[test]
+    fn test_array_key() {
+        let id = SurrealId::<Reading>::array(("sensor-1", 42)).unwrap();
+        assert_eq!(id.to_string(), "readings:['sensor-1', 42]");
+        assert_eq!(id.as_array(), Some(vec![json!("sensor-1"), json!(42)]));
+    }
+
+    #[test]
+    fn test_ulid_key() {
+        assert!(SurrealId::<Reading>::ulid("01ARZ3NDEKTSV4RRFFQ69G5FAV").is_ok());
+        assert!(SurrealId::<Reading>::ulid("not-a-ulid").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::{RecordType, SurrealId};
+use crate::types::{IdKey, RecordType, SurrealId};
 use rand::{thread_rng, Rng};
 use uuid::{Bytes, Uuid};
 
@@ -27,7 +27,17 @@ impl<T: RecordType> SurrealId<T> {
         Self::from(Uuid::now_v6(&node_id))
     }
 
+    /// Native UUID key, `table:u'...'`. `From<Uuid>` stores the UUID as a
+    /// string key instead.
+    pub fn uuid(uuid: Uuid) -> Self {
+        Self::parse_key(&format!("u'{}'", uuid)).unwrap_or_else(|_| Self::from(uuid))
+    }
+
+    /// Reads a native UUID key or a string key holding a UUID
     pub fn as_uuid(&self) -> Option<Uuid> {
-        Uuid::parse_str(self.id().to_string().as_str()).ok()
+        match self.key() {
+            IdKey::Uuid(raw) | IdKey::String(raw) => Uuid::parse_str(&raw).ok(),
+            _ => None,
+        }
     }
 }
@@ -97,6 +97,32 @@ impl From<String> for VectorDistance {
     }
 }
 
+/// How the KNN operator `<|k, ...|>` finds neighbours
+#[derive(Clone, Copy, Default, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum KnnSearch {
+    /// `<|k|>`, through the MTREE or HNSW index on the field
+    #[default]
+    Index,
+    /// `<|k, ef|>`, through an HNSW index with a candidate list of size `ef`
+    Ef(usize),
+    /// `<|k, DISTANCE|>`, brute force without an index
+    Distance(VectorDistance),
+}
+
+impl KnnSearch {
+    /// Renders the operator for `k` neighbours
+    pub fn operator(&self, k: usize) -> String {
+        match self {
+            Self::Index => format!("<|{}|>", k),
+            Self::Ef(ef) => format!("<|{},{}|>", k, ef),
+            Self::Distance(VectorDistance::Minkowski(p)) => format!("<|{},MINKOWSKI {}|>", k, p),
+            Self::Distance(distance) => {
+                format!("<|{},{}|>", k, distance.to_string().to_uppercase())
+            }
+        }
+    }
+}
+
 /// Vector search conditions
 #[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum VectorCondition {
@@ -107,19 +133,30 @@ pub enum VectorCondition {
         distance: VectorDistance,
         threshold: Option<f32>,
     },
-    /// K-nearest neighbors search
-    Nearest {
+    /// Native KNN search with the `<|k, ...|>` operator
+    Knn {
         field: String,
         vector: Vec<f32>,
         k: usize,
-        distance: VectorDistance,
-    },
-    /// Batch similarity search
-    BatchSimilarity {
-        conditions: Vec<(String, Vec<f32>, VectorDistance, Option<f32>)>,
-    },
-    /// Batch nearest neighbors search
-    BatchNearest {
-        conditions: Vec<(String, Vec<f32>, VectorDistance, usize)>,
+        search: KnnSearch,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knn_operator() {
+        assert_eq!(KnnSearch::Index.operator(5), "<|5|>");
+        assert_eq!(KnnSearch::Ef(40).operator(5), "<|5,40|>");
+        assert_eq!(
+            KnnSearch::Distance(VectorDistance::Euclidean).operator(3),
+            "<|3,EUCLIDEAN|>"
+        );
+        assert_eq!(
+            KnnSearch::Distance(VectorDistance::Minkowski(3.0)).operator(3),
+            "<|3,MINKOWSKI 3|>"
+        );
+    }
+}
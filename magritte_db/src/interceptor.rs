@@ -0,0 +1,402 @@
+//! Interceptors run on every statement built by the query builders
+//!
+//! Before a builder executes, on its own or as a transaction step, it hands a
+//! [`StatementContext`] to the interceptors registered globally with
+//! [`register_interceptor`] and then to the ones added to the handle with
+//! [`SurrealDB::add_interceptor`](crate::SurrealDB::add_interceptor). An
+//! interceptor can add conditions with bound values, tag the statement, or
+//! reject it by returning an error. CREATE, INSERT and RELATE have no WHERE
+//! clause, so adding a condition to them fails. Raw queries sent through
+//! [`SurrealDB::query`](crate::SurrealDB::query) are not intercepted.
+//!
+//! ```rust,ignore
+//! register_interceptor(BlockFullTableDelete);
+//! db().add_interceptor(TenantFilter::new("tenant", "acme")?);
+//! db().add_interceptor(SoftDeleteFilter::new("deleted_at"));
+//! db().add_interceptor(QueryTag::new("billing-service"));
+//!
+//! Query::select::<Order>().execute().await?;
+//! // /* billing-service */ SELECT * FROM orders
+//! //     WHERE tenant = $p0 AND deleted_at IS NONE;
+//! ```
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Kind of statement being intercepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementKind {
+    Select,
+    Create,
+    Update,
+    Upsert,
+    Delete,
+    Insert,
+    Relate,
+}
+
+impl Display for StatementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            StatementKind::Select => "SELECT",
+            StatementKind::Create => "CREATE",
+            StatementKind::Update => "UPDATE",
+            StatementKind::Upsert => "UPSERT",
+            StatementKind::Delete => "DELETE",
+            StatementKind::Insert => "INSERT",
+            StatementKind::Relate => "RELATE",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Structured view of a statement about to execute
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementContext {
+    kind: StatementKind,
+    table: String,
+    targets_records: bool,
+    conditions: Vec<String>,
+    /// Index of the first condition added by an interceptor
+    added_from: usize,
+    params: Vec<(String, Value)>,
+    tags: Vec<String>,
+}
+
+impl StatementContext {
+    /// `targets_records` is set when the statement names record ids or a
+    /// range instead of the whole table
+    pub fn new(
+        kind: StatementKind,
+        table: &str,
+        targets_records: bool,
+        conditions: Vec<String>,
+        params: Vec<(String, Value)>,
+    ) -> Self {
+        Self {
+            kind,
+            table: table.to_string(),
+            targets_records,
+            added_from: conditions.len(),
+            conditions,
+            params,
+            tags: vec![],
+        }
+    }
+
+    pub fn kind(&self) -> StatementKind {
+        self.kind
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn targets_records(&self) -> bool {
+        self.targets_records
+    }
+
+    /// The WHERE conditions, including the ones added by interceptors
+    pub fn conditions(&self) -> &[String] {
+        &self.conditions
+    }
+
+    pub fn params(&self) -> &[(String, Value)] {
+        &self.params
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// True when the statement touches every record of the table
+    pub fn is_whole_table(&self) -> bool {
+        !self.targets_records && self.conditions.is_empty()
+    }
+
+    /// Binds a value as a new parameter and returns its `$name`
+    pub fn bind<V: Serialize>(&mut self, value: V) -> Result<String> {
+        let name = format!("p{}", self.params.len());
+        self.params
+            .push((name.clone(), serde_json::to_value(value)?));
+        Ok(format!("${}", name))
+    }
+
+    /// Adds a raw condition, joined to the others with AND
+    pub fn and_where(&mut self, condition: impl Into<String>) {
+        self.conditions.push(condition.into());
+    }
+
+    /// Tags the statement with a comment, e.g. for tracing it in server logs
+    pub fn tag(&mut self, tag: impl Into<String>) {
+        self.tags.push(tag.into().replace("*/", ""));
+    }
+
+    /// The conditions added by interceptors
+    pub fn added_conditions(&self) -> &[String] {
+        &self.conditions[self.added_from..]
+    }
+
+    /// The tags as a comment to put in front of the statement, or an empty
+    /// string when there are none
+    pub fn comment(&self) -> String {
+        if self.tags.is_empty() {
+            String::new()
+        } else {
+            format!("/* {} */ ", self.tags.join(", "))
+        }
+    }
+
+    /// Consumes the context, returning the added conditions, the parameters
+    /// and the comment
+    pub fn into_parts(mut self) -> (Vec<String>, Vec<(String, Value)>, String) {
+        let comment = self.comment();
+        let added = self.conditions.split_off(self.added_from);
+        (added, self.params, comment)
+    }
+}
+
+/// Rewrites or rejects statements before they execute
+pub trait Interceptor: Send + Sync {
+    /// Returning an error rejects the statement
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()>;
+}
+
+impl<F> Interceptor for F
+where
+    F: Fn(&mut StatementContext) -> Result<()> + Send + Sync,
+{
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        self(ctx)
+    }
+}
+
+/// An ordered list of interceptors
+#[derive(Clone, Default)]
+pub(crate) struct InterceptorChain {
+    interceptors: Arc<RwLock<Vec<Arc<dyn Interceptor>>>>,
+}
+
+impl InterceptorChain {
+    pub(crate) fn push(&self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(interceptor);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.interceptors
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+
+    pub(crate) fn run(&self, ctx: &mut StatementContext) -> Result<()> {
+        // Cloned so an interceptor can register another without deadlocking
+        let interceptors = self
+            .interceptors
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for interceptor in interceptors {
+            interceptor.intercept(ctx)?;
+        }
+        Ok(())
+    }
+}
+
+static GLOBAL: RwLock<Vec<Arc<dyn Interceptor>>> = RwLock::new(Vec::new());
+
+/// Registers an interceptor for every `SurrealDB` handle. Global interceptors
+/// run before the ones added to a handle.
+pub fn register_interceptor(interceptor: impl Interceptor + 'static) {
+    GLOBAL
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::new(interceptor));
+}
+
+pub(crate) fn has_global_interceptors() -> bool {
+    !GLOBAL.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+}
+
+pub(crate) fn run_global(ctx: &mut StatementContext) -> Result<()> {
+    let interceptors = GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone();
+    for interceptor in interceptors {
+        interceptor.intercept(ctx)?;
+    }
+    Ok(())
+}
+
+/// Rejects DELETE statements with no WHERE clause and no record target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockFullTableDelete;
+
+impl Interceptor for BlockFullTableDelete {
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        if ctx.kind() == StatementKind::Delete && ctx.is_whole_table() {
+            bail!(
+                "DELETE on {} has no conditions and would remove every record",
+                ctx.table()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Restricts SELECT, UPDATE, UPSERT and DELETE to the records of one tenant
+#[derive(Debug, Clone)]
+pub struct TenantFilter {
+    field: String,
+    value: Value,
+    tables: Option<Vec<String>>,
+}
+
+impl TenantFilter {
+    pub fn new<V: Serialize>(field: &str, value: V) -> Result<Self> {
+        Ok(Self {
+            field: field.to_string(),
+            value: serde_json::to_value(value)?,
+            tables: None,
+        })
+    }
+
+    /// Only filters these tables; by default every table is filtered
+    pub fn tables(mut self, tables: &[&str]) -> Self {
+        self.tables = Some(tables.iter().map(|t| t.to_string()).collect());
+        self
+    }
+}
+
+impl Interceptor for TenantFilter {
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        let filtered = matches!(
+            ctx.kind(),
+            StatementKind::Select
+                | StatementKind::Update
+                | StatementKind::Upsert
+                | StatementKind::Delete
+        );
+        let table_matches = self
+            .tables
+            .as_ref()
+            .map_or(true, |tables| tables.iter().any(|t| t == ctx.table()));
+        if filtered && table_matches {
+            let param = ctx.bind(&self.value)?;
+            ctx.and_where(format!("{} = {}", self.field, param));
+        }
+        Ok(())
+    }
+}
+
+/// Hides soft-deleted records from SELECT and UPDATE
+#[derive(Debug, Clone)]
+pub struct SoftDeleteFilter {
+    field: String,
+    tables: Option<Vec<String>>,
+}
+
+impl SoftDeleteFilter {
+    /// `field` is NONE on live records, e.g. a `deleted_at` timestamp
+    pub fn new(field: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            tables: None,
+        }
+    }
+
+    /// Only filters these tables; by default every table is filtered
+    pub fn tables(mut self, tables: &[&str]) -> Self {
+        self.tables = Some(tables.iter().map(|t| t.to_string()).collect());
+        self
+    }
+}
+
+impl Interceptor for SoftDeleteFilter {
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        let filtered = matches!(ctx.kind(), StatementKind::Select | StatementKind::Update);
+        let table_matches = self
+            .tables
+            .as_ref()
+            .map_or(true, |tables| tables.iter().any(|t| t == ctx.table()));
+        if filtered && table_matches {
+            ctx.and_where(format!("{} IS NONE", self.field));
+        }
+        Ok(())
+    }
+}
+
+/// Tags every statement with a fixed comment
+#[derive(Debug, Clone)]
+pub struct QueryTag(String);
+
+impl QueryTag {
+    pub fn new(tag: &str) -> Self {
+        Self(tag.to_string())
+    }
+}
+
+impl Interceptor for QueryTag {
+    fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        ctx.tag(self.0.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn context(kind: StatementKind, conditions: &[&str]) -> StatementContext {
+        StatementContext::new(
+            kind,
+            "orders",
+            false,
+            conditions.iter().map(|c| c.to_string()).collect(),
+            vec![("p0".to_string(), json!(10))],
+        )
+    }
+
+    #[test]
+    fn test_chain_rewrites_context() {
+        let chain = InterceptorChain::default();
+        chain.push(Arc::new(TenantFilter::new("tenant", "acme").unwrap()));
+        chain.push(Arc::new(SoftDeleteFilter::new("deleted_at")));
+        chain.push(Arc::new(QueryTag::new("billing")));
+
+        let mut ctx = context(StatementKind::Select, &["total > $p0"]);
+        chain.run(&mut ctx).unwrap();
+        assert_eq!(
+            ctx.added_conditions(),
+            ["tenant = $p1".to_string(), "deleted_at IS NONE".to_string()]
+        );
+        let (added, params, comment) = ctx.into_parts();
+        assert_eq!(added.len(), 2);
+        assert_eq!(params[1], ("p1".to_string(), json!("acme")));
+        assert_eq!(comment, "/* billing */ ");
+    }
+
+    #[test]
+    fn test_block_full_table_delete() {
+        let mut ctx = context(StatementKind::Delete, &[]);
+        assert!(BlockFullTableDelete.intercept(&mut ctx).is_err());
+        let mut ctx = context(StatementKind::Delete, &["total > $p0"]);
+        assert!(BlockFullTableDelete.intercept(&mut ctx).is_ok());
+        let mut ctx = StatementContext::new(StatementKind::Delete, "orders", true, vec![], vec![]);
+        assert!(BlockFullTableDelete.intercept(&mut ctx).is_ok());
+    }
+
+    #[test]
+    fn test_soft_delete_skips_other_tables() {
+        let filter = SoftDeleteFilter::new("deleted_at").tables(&["users"]);
+        let mut ctx = context(StatementKind::Select, &[]);
+        filter.intercept(&mut ctx).unwrap();
+        assert!(ctx.conditions().is_empty());
+    }
+}
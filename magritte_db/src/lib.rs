@@ -1,12 +1,16 @@
 pub mod executor;
+pub mod interceptor;
 pub use crate::executor::core::types::QueryType;
+pub use crate::interceptor::*;
 use crate::executor::utils::metrics::ExecutorMetrics;
+use crate::interceptor::{has_global_interceptors, run_global, InterceptorChain};
 use anyhow::Result;
 pub(crate) use deadpool_surrealdb::Config as DbConfig;
 use deadpool_surrealdb::Runtime;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::sync::{Arc, OnceLock};
+use surrealdb::Response;
 
 /// Main database interface that handles connection management and query execution.
 /// Users should not interact with this directly, but through Query builders.
@@ -14,6 +18,7 @@ use std::sync::{Arc, OnceLock};
 pub struct SurrealDB {
     pool: deadpool_surrealdb::Pool,
     metrics: Arc<ExecutorMetrics>,
+    interceptors: InterceptorChain,
 }
 
 impl SurrealDB {
@@ -24,19 +29,63 @@ impl SurrealDB {
             .create_pool(Some(Runtime::Tokio1))
             .map_err(anyhow::Error::from)?;
         let metrics = Arc::new(ExecutorMetrics::new());
-        Ok(Self { pool, metrics })
+        Ok(Self {
+            pool,
+            metrics,
+            interceptors: InterceptorChain::default(),
+        })
     }
 
-    /// Internal method to execute queries from Query builders.
-    /// This is not public API - users should use Query builders instead.
-    pub async fn execute<T>(
+    /// Adds an interceptor to this handle and its clones. It runs after the
+    /// global ones, in the order interceptors were added.
+    pub fn add_interceptor(&self, interceptor: impl Interceptor + 'static) {
+        self.interceptors.push(Arc::new(interceptor));
+    }
+
+    /// Builder form of [`add_interceptor`](Self::add_interceptor)
+    pub fn with_interceptor(self, interceptor: impl Interceptor + 'static) -> Self {
+        self.add_interceptor(interceptor);
+        self
+    }
+
+    pub fn has_interceptors(&self) -> bool {
+        has_global_interceptors() || !self.interceptors.is_empty()
+    }
+
+    /// Runs the global interceptors, then the ones of this handle. Fails as
+    /// soon as one rejects the statement.
+    pub fn intercept(&self, ctx: &mut StatementContext) -> Result<()> {
+        run_global(ctx)?;
+        self.interceptors.run(ctx)
+    }
+
+    /// Runs a query and returns the raw response with the results of every
+    /// statement. Fails if any statement failed.
+    pub async fn query(
         &self,
         query: impl ToString,
         params: Vec<(String, Value)>,
-    ) -> Result<Vec<T>>
-    where
-        T: DeserializeOwned + Send + 'static,
-    {
+    ) -> Result<Response> {
+        self.run(query, params, true).await
+    }
+
+    /// Runs a query and returns the raw response without checking the
+    /// statement results, e.g. for a `CANCEL TRANSACTION` where every
+    /// statement reports the cancellation as an error.
+    pub async fn query_unchecked(
+        &self,
+        query: impl ToString,
+        params: Vec<(String, Value)>,
+    ) -> Result<Response> {
+        self.run(query, params, false).await
+    }
+
+    async fn run(
+        &self,
+        query: impl ToString,
+        params: Vec<(String, Value)>,
+        check: bool,
+    ) -> Result<Response> {
         let conn = self.pool.get().await?;
         let metrics = self.metrics.clone();
         let start = std::time::Instant::now();
@@ -46,15 +95,13 @@ impl SurrealDB {
             if !params.is_empty() {
                 q = q.bind(params)
             }
-            q.await
+            q.await.and_then(|response| if check { response.check() } else { Ok(response) })
         };
 
         match result {
-            Ok(mut response) => {
+            Ok(response) => {
                 metrics.update_success(start.elapsed().as_micros() as usize);
-                response.take(0).map_err(|e| {
-                    anyhow::anyhow!("Failed to process response: {}", e)
-                })
+                Ok(response)
             }
             Err(e) => {
                 metrics.update_failure();
@@ -62,12 +109,44 @@ impl SurrealDB {
             }
         }
     }
+
+    /// Internal method to execute queries from Query builders.
+    /// This is not public API - users should use Query builders instead.
+    pub async fn execute<T>(
+        &self,
+        query: impl ToString,
+        params: Vec<(String, Value)>,
+    ) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let mut response = self.query(query, params).await?;
+        response
+            .take(0)
+            .map_err(|e| anyhow::anyhow!("Failed to process response: {}", e))
+    }
+
+    /// Executes a query whose first statement yields at most one row, such as
+    /// `SELECT ... FROM ONLY`. Fails if the statement returns several rows.
+    pub async fn execute_optional<T>(
+        &self,
+        query: impl ToString,
+        params: Vec<(String, Value)>,
+    ) -> Result<Option<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let mut response = self.query(query, params).await?;
+        response
+            .take(0)
+            .map_err(|e| anyhow::anyhow!("Failed to process response: {}", e))
+    }
 }
 
 // SurrealDB can be safely shared between threads because:
 // 1. The underlying pool from deadpool_surrealdb is already Send + Sync
 // 2. The metrics are protected by Arc which is thread-safe
-// 3. No interior mutability is used without proper synchronization
+// 3. The interceptor chain is behind an Arc<RwLock>
 unsafe impl Send for SurrealDB {}
 
 // Same reasoning applies for Sync trait implementation
@@ -92,3 +171,19 @@ pub fn db() -> &'static SurrealDB {
     DB.get()
         .expect("Database not initialized. Call init_db() first")
 }
+
+/// Whether a statement built now would be intercepted, by a global
+/// interceptor or one added to the initialized handle
+pub fn has_interceptors() -> bool {
+    DB.get()
+        .map_or_else(has_global_interceptors, |db| db.has_interceptors())
+}
+
+/// Runs the global interceptors, then the ones of the initialized handle if
+/// there is one
+pub fn intercept(ctx: &mut StatementContext) -> Result<()> {
+    match DB.get() {
+        Some(db) => db.intercept(ctx),
+        None => run_global(ctx),
+    }
+}
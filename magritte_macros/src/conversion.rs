@@ -49,6 +49,28 @@ fn handle_enum_type(data: &DataEnum) -> FieldType {
     FieldType::Either(variants)
 }
 
+/// `geo` types and the SurrealDB geometry kind they are stored as. Only
+/// matched on a `geo::` or `geo_types::` path, so that unrelated types named
+/// e.g. `Polygon` keep their own mapping.
+const GEO_TYPES: &[(&str, &str)] = &[
+    ("Point", "point"),
+    ("LineString", "line"),
+    ("Polygon", "polygon"),
+    ("MultiPoint", "multipoint"),
+    ("MultiLineString", "multiline"),
+    ("MultiPolygon", "multipolygon"),
+    ("GeometryCollection", "collection"),
+    ("Geometry", "feature"),
+];
+
+fn geometry_type(name: &str) -> FieldType {
+    let kind = GEO_TYPES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map_or("feature", |(_, kind)| *kind);
+    FieldType::Geometry(kind.to_string())
+}
+
 pub fn type_to_surrealdb_type(ty: &Type) -> FieldType {
     match ty {
         Type::Path(TypePath { path, .. }) => {
@@ -129,23 +151,11 @@ pub fn type_to_surrealdb_type(ty: &Type) -> FieldType {
                 [ns, seg] if ns.ident == "rust_decimal" && seg.ident == "Decimal" => {
                     FieldType::Decimal
                 }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "Point" => {
-                    FieldType::Geometry("point".to_string())
-                }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "LineString" => {
-                    FieldType::Geometry("linestring".to_string())
-                }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "Polygon" => {
-                    FieldType::Geometry("polygon".to_string())
-                }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "MultiPoint" => {
-                    FieldType::Geometry("multipoint".to_string())
-                }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "MultiLineString" => {
-                    FieldType::Geometry("multilinestring".to_string())
-                }
-                [ns, seg] if ns.ident == "geo" && seg.ident == "MultiPolygon" => {
-                    FieldType::Geometry("multipolygon".to_string())
+                [ns, seg]
+                    if (ns.ident == "geo" || ns.ident == "geo_types")
+                        && GEO_TYPES.iter().any(|(name, _)| seg.ident == name) =>
+                {
+                    geometry_type(&seg.ident.to_string())
                 }
                 [ns, seg] if ns.ident == "uuid" && seg.ident == "Uuid" => FieldType::Uuid,
                 [seg] if seg.ident == "Datetime" => FieldType::Datetime,
@@ -222,3 +232,24 @@ pub fn type_to_surrealdb_type(ty: &Type) -> FieldType {
         _ => FieldType::Any,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_geo_types_need_geo_path() {
+        let geo: Type = parse_quote!(geo::Polygon);
+        let geo_types: Type = parse_quote!(geo_types::Point);
+        let local: Type = parse_quote!(Polygon);
+        let point: Type = parse_quote!(Point);
+        assert_eq!(type_to_surrealdb_type(&geo), FieldType::Geometry("polygon".to_string()));
+        assert_eq!(
+            type_to_surrealdb_type(&geo_types),
+            FieldType::Geometry("point".to_string())
+        );
+        assert_eq!(type_to_surrealdb_type(&local), FieldType::Any);
+        assert_eq!(type_to_surrealdb_type(&point), FieldType::Point);
+    }
+}
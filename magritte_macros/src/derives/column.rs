@@ -178,6 +178,8 @@ pub fn expand_derive_column(mut input: DeriveInput) -> syn::Result<TokenStream>
 
         #[automatically_derived]
         impl #impl_generics #crate_name::ColumnType for #column_enum_name #type_generics #where_clause {
+            type Table = #entity_type;
+
             fn column_name(&self) -> & str {
                 match self {
                     #(#column_enum_name::#column_variants => #column_names,)*
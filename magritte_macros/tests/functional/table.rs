@@ -1,6 +1,9 @@
-use crate::User;
+use crate::{Order, OrderIndexes, User};
 use magritte::entity_crud::{BasicCrud, SurrealCrud};
-use magritte::{ColumnTrait, TableTrait};
+use magritte::{
+    ColumnTrait, IndexTrait, Query, QueryPlan, StatementBuilder, TableTrait, Validate, WhereClause,
+};
+use magritte_core::operator::Operator;
 use magritte_query::types::HasId;
 use magritte_query::{SurrealDB, SurrealId};
 use pretty_assertions::assert_eq;
@@ -58,3 +61,34 @@ async fn test_create_and_query_records() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_find_by_id_at() -> anyhow::Result<()> {
+    let at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+    let sql = User::find_by_id_at(SurrealId::from("alice"), at)?.validate()?;
+    assert!(sql.contains("VERSION d'2024-01-01T00:00:00Z'"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_plan_uses_index() -> anyhow::Result<()> {
+    let db: Surreal<Any> = connect("mem://").await?;
+    db.use_ns("test").use_db("test").await?;
+    db.query(OrderIndexes::StatusIdx.to_statement().build()?)
+        .await?
+        .check()?;
+
+    let select = Query::select::<Order>()
+        .where_op("status", Operator::Eq, Some("shipped"))?
+        .explain(true);
+    let rows: Vec<serde_json::Value> = db
+        .query(select.build()?)
+        .bind(select.with_params())
+        .await?
+        .take(0)?;
+    let plan = QueryPlan::from_rows(rows)?;
+    plan.assert_uses_index("status_idx")?;
+    assert!(!plan.is_full_scan());
+    assert!(plan.assert_uses_index("created_idx").is_err());
+    Ok(())
+}
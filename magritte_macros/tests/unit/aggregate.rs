@@ -0,0 +1,32 @@
+use super::{Order, OrderColumns};
+use anyhow::Result;
+use magritte::*;
+use magritte_core::operator::Operator;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn test_aggregate_statement() -> Result<()> {
+    let query = Query::aggregate::<Order>()
+        .key(OrderColumns::Status)
+        .count("orders")
+        .sum(OrderColumns::Total, "revenue")
+        .distinct(OrderColumns::User, "customers")
+        .where_op("total", Operator::Gt, Some(0))?
+        .order_by("revenue", false);
+    assert_eq!(
+        query.validate()?,
+        "SELECT status, count() AS orders, math::sum(total) AS revenue, array::distinct(user) AS customers \
+         FROM orders WHERE total > $p0 GROUP BY status ORDER BY revenue DESC;"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_from_select() -> Result<()> {
+    let query = Query::select::<Order>()
+        .where_op("status", Operator::Eq, Some("paid"))?
+        .aggregate()
+        .mean(OrderColumns::Total, "average");
+    query.validate()?;
+    Ok(())
+}
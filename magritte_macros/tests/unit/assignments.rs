@@ -0,0 +1,42 @@
+use super::{Product, ProductColumns, User};
+use anyhow::Result;
+use magritte::*;
+use magritte_core::operator::Operator;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[test]
+fn test_typed_assignments() -> Result<()> {
+    let query = Query::update::<Product>()
+        .increment(ProductColumns::Quantity, 1)?
+        .set(ProductColumns::Price, 12.5)?
+        .set_path("metadata.tags[0]", SetOp::Assign, "sale")?
+        .unset(ProductColumns::Sku)?
+        .where_op("quantity", Operator::Lt, Some(10))?;
+    assert_eq!(
+        query.validate()?,
+        "UPDATE products SET quantity += $p0, price = $p1, metadata.tags[0] = $p2, sku = NONE \
+         WHERE quantity < $p3;"
+    );
+    Query::upsert::<Product>()
+        .append(ProductColumns::Metadata, "sale")?
+        .decrement(ProductColumns::Price, 1)?
+        .validate()?;
+    Query::update::<Product>()
+        .unset(ProductColumns::Sku)?
+        .validate()?;
+    Ok(())
+}
+
+#[test]
+fn test_invalid_assignments() -> Result<()> {
+    assert!(Query::update::<User>()
+        .set(ProductColumns::Price, 1)
+        .is_err());
+    assert!(Query::update::<Product>()
+        .merge(json!({"name": "x"}))?
+        .set(ProductColumns::Name, "y")?
+        .build()
+        .is_err());
+    Ok(())
+}
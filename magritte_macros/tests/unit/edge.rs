@@ -3,6 +3,7 @@ use super::Product;
 use super::User;
 use anyhow::Result;
 use magritte::*;
+use magritte_core::ReturnType;
 use pretty_assertions::assert_eq;
 use serde::{Deserialize, Serialize};
 
@@ -81,13 +82,13 @@ fn test_edge_statements() -> Result<()> {
         .map_err(anyhow::Error::from)?;
     println!("{}", order_product_edge_stmt);
     assert!(order_product_edge_stmt.contains(
-        "DEFINE TABLE order_product TYPE RELATION SCHEMALESS FROM orders TO products ENFORCED"
+        "DEFINE TABLE order_product TYPE RELATION FROM orders TO products ENFORCED SCHEMALESS"
     ));
 
     // Test UserOrderEdge statement
     let user_order_edge_stmt = UserOrder::new().to_statement_owned().build()?;
     assert!(user_order_edge_stmt.contains(
-        "DEFINE TABLE IF NOT EXISTS user_order TYPE RELATION SCHEMAFULL FROM users TO orders"
+        "DEFINE TABLE IF NOT EXISTS user_order TYPE RELATION FROM users TO orders SCHEMAFULL"
     ));
     Ok(())
 }
@@ -117,6 +118,35 @@ fn test_edge_columns() {
     assert!(!note_def.is_nullable());
 }
 
+#[test]
+fn test_typed_relate() -> Result<()> {
+    let order = SurrealId::<Order>::new("o1");
+    let product = SurrealId::<Product>::new("p1");
+
+    let relate = Relate::<OrderProduct>::between(&order, &product);
+    assert_eq!(relate.build()?, "RELATE orders:o1->order_product->products:p1;");
+
+    let relate = Relate::<OrderProduct>::between(&order, &product)
+        .content(&OrderProduct::new())?
+        .return_(ReturnType::After);
+    assert_eq!(
+        relate.build()?,
+        "RELATE orders:o1->order_product->products:p1 CONTENT $p0 RETURN AFTER;"
+    );
+    assert_eq!(relate.with_params()[0].1["quantity"], "1");
+
+    let relate = Relate::<OrderProduct>::many(
+        &[order.clone(), SurrealId::new("o2")],
+        &[product.clone()],
+    )
+    .set("quantity", 3)?;
+    assert_eq!(
+        relate.build()?,
+        "RELATE [orders:o1, orders:o2]->order_product->[products:p1] SET quantity = $p0;"
+    );
+    Ok(())
+}
+
 impl OrderProduct {
     fn new() -> Self {
         Self {
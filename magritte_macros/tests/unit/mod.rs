@@ -2,11 +2,15 @@ use magritte_macros::Table;
 use magritte_query::{HasId, RecordRef, SurrealId};
 use serde::{Deserialize, Serialize};
 
+mod aggregate;
+mod assignments;
 mod edge;
 mod relations;
+mod strict;
 mod table;
 mod table_events;
 mod table_indexes;
+mod validation;
 
 // Test table with nested columns and relationships
 #[derive(Table, Serialize, Deserialize, Clone)]
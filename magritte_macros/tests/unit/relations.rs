@@ -4,6 +4,7 @@ use super::Order;
 use super::Product;
 use super::User;
 use magritte::*;
+use magritte_core::operator::Operator;
 use pretty_assertions::assert_eq;
 use serde::{Deserialize, Serialize};
 // Test relations for Order and Product
@@ -52,23 +53,46 @@ fn test_user_order_relations_derive() {
 #[test]
 fn test_relation_statements() -> anyhow::Result<()> {
     // Test OrderProductRelations statement
-    let order_product_stmt = OrderRelations::OrderToProduct
+    let order_product = OrderRelations::OrderToProduct
         .relation_def()
-        .relate("id", "id2")?
-        .build()
-        .map_err(anyhow::Error::from)?;
+        .relate(&SurrealId::<Order>::new("id"), &SurrealId::<Product>::new("id2"))?;
+    let order_product_stmt = order_product.build().map_err(anyhow::Error::from)?;
     println!("{}", order_product_stmt);
-    assert!(order_product_stmt.contains(
-        "RELATE orders:id->order_product->products:id2 CONTENT \"order_product_content\""
-    ));
+    assert!(order_product_stmt.contains("RELATE orders:id->order_product->products:id2 CONTENT $p0"));
+    assert_eq!(order_product.with_params()[0].1, "order_product_content");
 
     // Test UserOrderRelations statement
-    let user_order_stmt = UserRelations::UserToOrder
+    let user_order = UserRelations::UserToOrder
         .relation_def()
-        .relate("id", "id2")?
-        .build()?;
-    assert!(user_order_stmt
-        .contains("RELATE users:id->user_order->orders:id2 CONTENT \"user_order_content\""));
+        .relate(&SurrealId::<User>::new("id"), &SurrealId::<Order>::new("id2"))?;
+    let user_order_stmt = user_order.build()?;
+    assert!(user_order_stmt.contains("RELATE users:id->user_order->orders:id2 CONTENT $p0"));
+    assert_eq!(user_order.with_params()[0].1, "user_order_content");
+
+    let swapped = UserRelations::UserToOrder
+        .relation_def()
+        .relate(&SurrealId::<Order>::new("id"), &SurrealId::<User>::new("id2"));
+    assert!(swapped.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_typed_traversal() -> anyhow::Result<()> {
+    let traversal = Traverse::from_id(SurrealId::<User>::new("alice"))
+        .then::<UserRelationsUserToOrderRelation>()
+        .where_op("status", Operator::Eq, "paid")?
+        .then::<OrderRelationsOrderToProductRelation>()
+        .where_edge("quantity", Operator::Gt, 1)?;
+    assert_eq!(
+        traversal.build()?,
+        "SELECT *, ->user_order->(orders WHERE status = $p0)\
+         ->(order_product WHERE quantity > $p1)->products.* AS __related FROM users:alice;"
+    );
+
+    let traversal = Traverse::from_id(SurrealId::<Product>::new("p1"))
+        .back::<OrderRelationsOrderToProductRelation>()
+        .back::<UserRelationsUserToOrderRelation>();
+    assert_eq!(traversal.path(), "<-order_product<-orders<-user_order<-users");
     Ok(())
 }
 
@@ -0,0 +1,44 @@
+//! Strict mode against the columns of the derived tables.
+
+use super::{Product, ProductColumns};
+use anyhow::Result;
+use magritte::*;
+use magritte_core::operator::Operator;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn test_strict_mode_registered_tables() -> Result<()> {
+    with_strict_mode(|| -> Result<()> {
+        let query = Query::select::<Product>()
+            .fields(&["name", "price"])
+            .where_op("metadata.vendor.country", Operator::Eq, Some("FR"))?
+            .order_by_field("quantity", false);
+        assert!(query.build().is_ok());
+
+        let query = Query::update::<Product>()
+            .set(ProductColumns::Price, 12.5)?
+            .set_path("metadata.tags[0]", SetOp::Assign, "sale")?
+            .where_op("sku", Operator::Eq, Some("A-1"))?;
+        assert!(query.build().is_ok());
+
+        let err = Query::select::<Product>()
+            .where_op("prcie", Operator::Gt, Some(10))?
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `prcie` on table products, did you mean `price`?"
+        );
+        let err = Query::update::<Product>()
+            .set_path("quantiy", SetOp::Assign, 1)?
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `quantiy` on table products, did you mean `quantity`?"
+        );
+        Ok(())
+    })?;
+    assert!(Query::select::<Product>().fields(&["prcie"]).build().is_ok());
+    Ok(())
+}
@@ -0,0 +1,210 @@
+//! Round-trips the output of every builder through the SurrealDB parser.
+
+use super::edge::OrderProduct;
+use super::{Order, Product, User};
+use magritte::*;
+use magritte_core::operator::Operator;
+use magritte_core::{RangeTarget, ReturnType};
+use serde_json::json;
+use std::time::Duration;
+
+fn assert_valid<S: StatementBuilder>(stmt: S) -> String {
+    stmt.validate().unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[test]
+fn test_select_round_trip() -> anyhow::Result<()> {
+    assert_valid(Query::select::<User>());
+    assert_valid(
+        Query::select::<User>()
+            .fields(&["name", "email"])
+            .where_op("name", Operator::Eq, Some("Alice"))?
+            .where_op("email", Operator::NotEq, Some("a@b.c"))?
+            .order_by_field("name", true)
+            .limit(10)
+            .start("20"),
+    );
+    assert_valid(
+        Query::select::<User>()
+            .only()
+            .where_id(SurrealId::new("alice"))
+            .limit(1),
+    );
+    assert_valid(
+        Query::select::<Product>()
+            .field("name", None)
+            .count()
+            .group_by("name")
+            .split("tags")
+            .fetch(&["owner"])
+            .timeout(Duration::from_millis(1500))
+            .parallel()
+            .tempfiles(),
+    );
+    assert_valid(Query::select::<User>().group_all().count());
+    assert_valid(Query::select::<User>().omit(vec!["email"]).explain(true));
+    assert_valid(Query::select::<User>().version("2024-01-01T00:00:00Z")?);
+    assert_valid(Query::select::<User>().with_indexes(vec!["email_idx".into()]));
+    assert_valid(Query::select::<User>().with_indexes(vec![]));
+    assert_valid(
+        Query::select::<User>()
+            .let_("min", "18")
+            .where_op("age", Operator::Gte, Some(18))?,
+    );
+    assert_valid(
+        Query::select::<User>()
+            .where_in("id", Query::select::<User>().select_value().field("id", None))?
+            .subquery(Query::select::<Product>().limit(1), Some("first_product"))?,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_vector_round_trip() -> anyhow::Result<()> {
+    // Vector searches used to open a second WHERE after FETCH
+    let sql = assert_valid(
+        Query::select::<Product>()
+            .where_op("quantity", Operator::Gt, Some(0))?
+            .vector_similarity("embedding", vec![1.0, 0.0], VectorDistance::Cosine, Some(0.8))
+            .fetch(&["owner"]),
+    );
+    assert_eq!(sql.matches(" WHERE ").count(), 1);
+    assert!(sql.ends_with(
+        "WHERE quantity > $p0 AND vector::similarity::cosine(embedding, $p1) >= 0.8 FETCH owner;"
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_mutation_round_trip() -> anyhow::Result<()> {
+    let id = SurrealId::<User>::new("alice");
+
+    assert_valid(
+        Query::create::<User>()
+            .with_id("alice")
+            .set("name", "Alice")?
+            .set("email", "alice@example.com")?
+            .return_(ReturnType::None)
+            .timeout(Duration::from_secs(5)),
+    );
+    assert_valid(
+        Query::insert::<Product>()
+            .content(&Product::new("p1", "Widget", 3, 9.5, "W-1", json!({"color": "red"})))?,
+    );
+    assert_valid(
+        Query::update::<User>()
+            .where_id(id.clone())
+            .merge(json!({"name": "Bob"}))?
+            .return_(ReturnType::Diff),
+    );
+    assert_valid(
+        Query::update::<User>()
+            .content(&json!({"name": "Bob"}))?
+            .where_op("name", Operator::Eq, Some("Alice"))?,
+    );
+    assert_valid(
+        Query::upsert::<User>()
+            .where_id(id.clone())
+            .merge(json!({"email": "bob@example.com"}))?,
+    );
+    assert_valid(
+        Query::delete::<User>()
+            .where_id(id.clone())
+            .return_(ReturnType::Before),
+    );
+    assert_valid(Query::delete::<User>().range(RangeTarget::Range("1".into(), "10".into())));
+    assert_valid(
+        Query::delete::<User>()
+            .where_op("email", Operator::Eq, Some("a@b.c"))?
+            .timeout(Duration::from_secs(2)),
+    );
+    assert_valid(
+        Query::relate()
+            .from_record("users:alice")
+            .edge_table("purchased")
+            .to_record("products:p1")
+            .set("quantity", 2)?
+            .timeout(Duration::from_secs(1)),
+    );
+    Ok(())
+}
+
+#[test]
+fn test_schema_round_trip() -> anyhow::Result<()> {
+    assert_valid(
+        Query::alter()
+            .table("users")
+            .schemafull()
+            .comment("Users table"),
+    );
+    assert_valid(
+        Define::table::<User>()
+            .name("users")
+            .schema_type(SchemaType::Schemafull)
+            .changefeed(Duration::from_secs(3600), true)
+            .drop()
+            .comment("Users"),
+    );
+    assert_valid(
+        Define::edge::<OrderProduct>()
+            .name("order_product")
+            .from(<Order as NamedType>::table_name())
+            .to(<Product as NamedType>::table_name())
+            .enforced(),
+    );
+    assert_valid(
+        Define::field()
+            .name("email")
+            .table_name("users")
+            .column_type("string")
+            .assert("string::is::email($value)"),
+    );
+    assert_valid(
+        Define::index()
+            .name("email_idx")
+            .table("users")
+            .columns(vec!["email".to_string()])
+            .unique(),
+    );
+    assert_valid(
+        Define::event()
+            .name("email_change")
+            .table("users")
+            .when("$before.email != $after.email")
+            .then("CREATE log SET user = $this"),
+    );
+    assert_valid(
+        Define::function()
+            .name("greet")
+            .arg(FunctionArg::new("name", "string"))
+            .body(Block::new().then(ReturnStatement::new("\"Hello, \" + $name")))?,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_control_flow_round_trip() -> anyhow::Result<()> {
+    assert_valid(
+        ForStatement::new("user")
+            .in_query(Query::select::<User>())?
+            .then(
+                IfStatement::new("$user.email = NONE")
+                    .then(ContinueStatement)
+                    .else_(ThrowStatement::new("unexpected")),
+            ),
+    );
+    assert_valid(
+        Query::begin()
+            .then(Query::create::<User>().with_id("alice").set("name", "Alice")?)
+            .then(Query::delete::<User>().where_op("name", Operator::Eq, Some("Bob"))?)
+            .commit(),
+    );
+    Ok(())
+}
+
+#[test]
+fn test_validation_reports_clause() {
+    let err = validate_sql("SELECT * FROM users ORDER BY name ASC WHERE name = 'x';").unwrap_err();
+    assert_eq!(err.clause.as_deref(), Some("WHERE"));
+    assert!(err.to_string().contains("WHERE clause"));
+}
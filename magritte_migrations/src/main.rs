@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use magritte::SurrealDB;
+use magritte::{format_sql, SurrealDB};
 use magritte_migrations::manager::MigrationManager;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -70,7 +70,7 @@ async fn main() -> Result<()> {
             if !statements.is_empty() {
                 println!("\nGenerated statements:");
                 for stmt in statements {
-                    println!("{}", stmt);
+                    println!("{}", format_sql(&stmt));
                 }
             }
         }
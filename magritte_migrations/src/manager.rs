@@ -1,6 +1,6 @@
 use super::{introspection, snapshot, Diff, Error, Result, ensure_overwrite};
 use crate::edge::EdgeDiff;
-use crate::snapshot::save_to_file;
+use crate::snapshot::{save_statements, save_to_file};
 use crate::table::TableDiff;
 use crate::types::FlexibleDateTime;
 use magritte::{EdgeRegistration, EventRegistration, IndexRegistration, Query, SchemaSnapshot, Snapshot, SurrealDB, TableRegistration};
@@ -109,7 +109,8 @@ impl MigrationManager {
     /// 
     /// If a database connection is provided, this will also include the current
     /// database schema state in the snapshot. Otherwise, it will only include
-    /// the registered schema.
+    /// the registered schema. The generated statements are written next to the
+    /// snapshot as formatted SurrealQL in a `.surql` file.
     /// 
     /// # Arguments
     /// 
@@ -151,6 +152,9 @@ impl MigrationManager {
             self.migrations_dir.join(format!("{}_schema.json", self.get_name()?))
         };
         save_to_file(&final_schema, &path)?;
+        if !statements.is_empty() {
+            save_statements(&statements, path.with_extension("surql"))?;
+        }
 
         Ok((path, statements))
     }
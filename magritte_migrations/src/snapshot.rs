@@ -1,5 +1,5 @@
 use super::Result;
-use magritte::SchemaSnapshot;
+use magritte::{format_sql, SchemaSnapshot};
 use std::fs;
 use std::path::Path;
 
@@ -14,3 +14,10 @@ pub fn save_to_file<P: AsRef<Path>>(s: &SchemaSnapshot, path: P) -> Result<()> {
     fs::write(path, data)?;
     Ok(())
 }
+
+/// Writes the statements of a migration as formatted SurrealQL, for review
+pub fn save_statements<P: AsRef<Path>>(statements: &[String], path: P) -> Result<()> {
+    let data: Vec<String> = statements.iter().map(|stmt| format_sql(stmt)).collect();
+    fs::write(path, data.join("\n\n") + "\n")?;
+    Ok(())
+}
@@ -1,8 +1,9 @@
 #![allow(unused)]
-use magritte::{RelationDef, Snapshot, TableSnapshot};
+use magritte::{RelationDef, Snapshot, TableDef, TableSnapshot};
 use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use crate::{ensure_overwrite, Diff};
+use tracing::warn;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TableDiff {
@@ -40,9 +41,10 @@ impl TableDiff {
     pub fn generate_statements(&self, table_name: &str) -> anyhow::Result<Vec<String>> {
         let mut statements = Vec::new();
 
-        // Only include table definition if this is a new table (no previous definition)
-        if self.previous.is_none() {
-            statements.push(ensure_overwrite(&self.current));
+        // Define new tables, alter the options of existing ones
+        match &self.previous {
+            None => statements.push(ensure_overwrite(&self.current)),
+            Some(previous) => statements.extend(alter_table(previous, &self.current)?),
         }
 
         // Add new columns
@@ -108,8 +110,8 @@ impl TableDiff {
     pub fn reverse(&self, table_name: &str) -> anyhow::Result<Vec<String>> {
         let mut statements = Vec::new();
 
-        // Table definition (ensure OVERWRITE is present)
-        statements.push(ensure_overwrite(self.previous.as_ref().unwrap()));
+        // Restore the previous table options
+        statements.extend(alter_table(&self.current, self.previous.as_ref().unwrap())?);
 
         for column in self.added_columns.keys() {
             statements.push(format!("REMOVE FIELD {} ON TABLE {};", column, table_name));
@@ -168,6 +170,25 @@ impl TableDiff {
         Ok(statements)
     }
 }
+
+/// The statement changing the table options from `from` to `to`: an ALTER
+/// TABLE when possible, a DEFINE TABLE OVERWRITE for changes ALTER cannot
+/// make, and nothing when the options are the same. Fails if either
+/// definition does not parse.
+fn alter_table(from: &str, to: &str) -> anyhow::Result<Option<String>> {
+    if from.trim() == to.trim() {
+        return Ok(None);
+    }
+    let new = TableDef::from_define(to)?;
+    match TableDef::from_define(from)?.alter_to(&new) {
+        Ok(alter) => alter.map(|alter| alter.build()).transpose(),
+        Err(e) => {
+            warn!("{}, falling back to DEFINE TABLE OVERWRITE", e);
+            Ok(Some(ensure_overwrite(to)))
+        }
+    }
+}
+
 impl Diff<TableSnapshot> for TableDiff {
     fn from_snapshots(
         old_table: &TableSnapshot,
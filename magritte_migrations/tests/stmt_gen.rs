@@ -1,5 +1,5 @@
 use magritte::{
-    table_snapshot, ColumnTrait, Event, HasId, Index, SchemaSnapshot, SurrealId, Table,
+    format_sql, table_snapshot, ColumnTrait, Event, HasId, Index, SchemaSnapshot, SurrealId, Table,
     TableRegistration, TableSnapshot, TableTrait,
 };
 use magritte_migrations::manager::MigrationManager;
@@ -172,6 +172,112 @@ async fn test_reverse_migration() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_table_options_emit_alter() -> anyhow::Result<()> {
+    let old = TableSnapshot {
+        name: "events".into(),
+        define_table_statement: "DEFINE TABLE events TYPE NORMAL SCHEMAFULL;".into(),
+        ..Default::default()
+    };
+    let new = TableSnapshot {
+        name: "events".into(),
+        define_table_statement:
+            "DEFINE TABLE events TYPE NORMAL SCHEMAFULL CHANGEFEED 1h COMMENT 'audit';".into(),
+        ..Default::default()
+    };
+
+    let diff = TableDiff::from_snapshots(&old, &new)?;
+    let up_statements = diff.generate_statements("events")?;
+    assert!(
+        up_statements
+            .iter()
+            .any(|s| s == "ALTER TABLE events CHANGEFEED 3600s COMMENT 'audit';"),
+        "Up migration should alter the table in place: {:?}",
+        up_statements
+    );
+    assert!(!up_statements.iter().any(|s| s.contains("DEFINE TABLE")));
+
+    let down_statements = diff.reverse("events")?;
+    assert!(
+        down_statements
+            .iter()
+            .any(|s| s == "ALTER TABLE events CHANGEFEED NONE COMMENT NONE;"),
+        "Down migration should undo the options: {:?}",
+        down_statements
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_kind_emits_alter() -> anyhow::Result<()> {
+    let old = TableSnapshot {
+        name: "likes".into(),
+        define_table_statement: "DEFINE TABLE likes TYPE NORMAL SCHEMAFULL;".into(),
+        ..Default::default()
+    };
+    let new = TableSnapshot {
+        name: "likes".into(),
+        define_table_statement:
+            "DEFINE TABLE likes TYPE RELATION FROM users TO posts SCHEMAFULL \
+             PERMISSIONS FOR select WHERE published = true;"
+                .into(),
+        ..Default::default()
+    };
+
+    let diff = TableDiff::from_snapshots(&old, &new)?;
+    let up_statements = diff.generate_statements("likes")?;
+    assert!(
+        up_statements.iter().any(|s| s
+            == "ALTER TABLE likes TYPE RELATION FROM users TO posts \
+                PERMISSIONS FOR select WHERE published = true;"),
+        "Up migration should change the table type: {:?}",
+        up_statements
+    );
+
+    let down_statements = diff.reverse("likes")?;
+    assert!(
+        down_statements
+            .iter()
+            .any(|s| s == "ALTER TABLE likes TYPE NORMAL PERMISSIONS NONE;"),
+        "Down migration should restore the table type: {:?}",
+        down_statements
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_table_overwrite_only_when_alter_cannot() -> anyhow::Result<()> {
+    let snapshot = |define: &str| TableSnapshot {
+        name: "totals".into(),
+        define_table_statement: define.into(),
+        ..Default::default()
+    };
+    let old = snapshot(
+        "DEFINE TABLE totals TYPE NORMAL SCHEMALESS AS SELECT count() FROM orders GROUP ALL;",
+    );
+    let new = snapshot(
+        "DEFINE TABLE totals TYPE NORMAL SCHEMALESS \
+         AS SELECT math::sum(total) FROM orders GROUP ALL;",
+    );
+    let up_statements = TableDiff::from_snapshots(&old, &new)?.generate_statements("totals")?;
+    assert!(
+        up_statements
+            .iter()
+            .any(|s| s.starts_with("DEFINE TABLE OVERWRITE totals")),
+        "Changing AS SELECT should redefine the table: {:?}",
+        up_statements
+    );
+
+    let broken = snapshot("DEFINE TABLE totals TYPE NORMAL SCHEMAFUL;");
+    assert!(TableDiff::from_snapshots(&old, &broken)?
+        .generate_statements("totals")
+        .is_err());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_conditional_features() -> anyhow::Result<()> {
     let manager = MigrationManager::new(tempdir()?.path().into());
@@ -300,3 +406,17 @@ async fn test_inventory_collection() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_snapshot_writes_formatted_statements() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let manager = MigrationManager::new(temp_dir.path().into());
+    let (path, statements) = manager.create_snapshot(None, None).await?;
+    assert!(!statements.is_empty());
+
+    let sql = std::fs::read_to_string(path.with_extension("surql"))?;
+    let formatted: Vec<String> = statements.iter().map(|s| format_sql(s)).collect();
+    assert_eq!(sql, formatted.join("\n\n") + "\n");
+    assert!(sql.lines().count() > statements.len(), "{}", sql);
+    Ok(())
+}
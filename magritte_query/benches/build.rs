@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use magritte_core::operator::Operator;
+use magritte_core::{NamedType, RecordType};
+use magritte_query::{build_cached, Fingerprint, Query, SelectStatement, WhereClause};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::hint::black_box;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Order {
+    status: String,
+    total: f64,
+}
+
+impl NamedType for Order {
+    fn table_name() -> &'static str {
+        "orders"
+    }
+}
+
+impl Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::table_name())
+    }
+}
+
+impl AsRef<str> for Order {
+    fn as_ref(&self) -> &str {
+        Self::table_name()
+    }
+}
+
+impl RecordType for Order {}
+
+fn hot_query(status: &str, total: f64) -> SelectStatement<Order> {
+    Query::select::<Order>()
+        .fields(&["status", "total", "created_at"])
+        .where_op("status", Operator::Eq, Some(status))
+        .unwrap()
+        .where_op("total", Operator::Gt, Some(total))
+        .unwrap()
+        .order_by_field("created_at", false)
+        .limit(50)
+}
+
+fn bench_build(c: &mut Criterion) {
+    let query = hot_query("paid", 10.0);
+    let mut group = c.benchmark_group("select");
+    group.bench_function("build", |b| b.iter(|| black_box(&query).build()));
+    group.bench_function("build_cached", |b| b.iter(|| build_cached(black_box(&query))));
+    group.bench_function("fingerprint", |b| b.iter(|| black_box(&query).fingerprint()));
+    group.bench_function("render_key", |b| b.iter(|| black_box(&query).render_key()));
+    group.bench_function("new_builder", |b| {
+        b.iter(|| hot_query(black_box("paid"), black_box(10.0)).build())
+    });
+    group.bench_function("new_builder_cached", |b| {
+        b.iter(|| build_cached(&hot_query(black_box("paid"), black_box(10.0))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_build);
+criterion_main!(benches);
@@ -0,0 +1,221 @@
+//! SET/UNSET data clauses for UPDATE and UPSERT
+//!
+//! [`SetClause`] adds field assignments keyed by the generated column enums,
+//! with every value bound as a parameter. `increment`/`append` render `+=` and
+//! `decrement`/`remove_from` render `-=`, so counters and arrays change
+//! atomically on the server instead of through a read-modify-write cycle.
+//! Nested fields such as `address.city`, `tags[0]` or an [`Idiom`] go through
+//! [`SetClause::set_path`] and [`SetClause::unset_path`].
+//!
+//! [`Idiom`]: crate::Idiom
+//!
+//! ```rust,ignore
+//! Query::update::<Product>()
+//!     .where_id(id)
+//!     .increment(ProductColumns::Stock, -1)?
+//!     .append(ProductColumns::Tags, "sale")?
+//!     .set_path("dimensions.weight", SetOp::Assign, 2.5)?
+//!     .unset(ProductColumns::Discount)?;
+//! // UPDATE products:x SET stock += $p0, tags += $p1, dimensions.weight = $p2,
+//! //     discount = NONE;
+//! ```
+
+use std::fmt::{self, Display};
+
+use anyhow::{bail, Result};
+use magritte_core::ColumnType;
+use serde::Serialize;
+
+use crate::expr::{HasAssignments, HasParams};
+use crate::idiom::IntoIdiom;
+
+/// Assignment operator of a SET clause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOp {
+    /// `=`
+    Assign,
+    /// `+=`, adds to numbers and appends to arrays
+    Add,
+    /// `-=`, subtracts from numbers and removes from arrays
+    Subtract,
+}
+
+impl Display for SetOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetOp::Assign => write!(f, "="),
+            SetOp::Add => write!(f, "+="),
+            SetOp::Subtract => write!(f, "-="),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Assignment {
+    /// `path op $param`
+    Set {
+        path: String,
+        op: SetOp,
+        param: String,
+    },
+    /// Removes the field
+    Unset(String),
+}
+
+impl Assignment {
+    pub fn path(&self) -> &str {
+        match self {
+            Assignment::Set { path, .. } | Assignment::Unset(path) => path,
+        }
+    }
+}
+
+/// Renders the data clause. Unsets alone render as `UNSET a, b`; mixed with
+/// sets they become `a = NONE`, as a statement takes a single data clause.
+pub(crate) fn build_assignments(assignments: &[Assignment]) -> String {
+    if assignments.is_empty() {
+        return String::new();
+    }
+    if assignments.iter().all(|a| matches!(a, Assignment::Unset(_))) {
+        let paths: Vec<&str> = assignments
+            .iter()
+            .filter_map(|a| match a {
+                Assignment::Unset(path) => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+        return format!(" UNSET {}", paths.join(", "));
+    }
+    let sets: Vec<String> = assignments
+        .iter()
+        .map(|a| match a {
+            Assignment::Set { path, op, param } => format!("{} {} ${}", path, op, param),
+            Assignment::Unset(path) => format!("{} = NONE", path),
+        })
+        .collect();
+    format!(" SET {}", sets.join(", "))
+}
+
+fn column_path<C: ColumnType>(column: &C, table: &str) -> Result<String> {
+    if C::table_name() != table {
+        bail!(
+            "Column {} belongs to {}, not {}",
+            column.column_name(),
+            C::table_name(),
+            table
+        );
+    }
+    Ok(column.column_name().to_string())
+}
+
+fn check_path(path: &str) -> Result<()> {
+    if path.trim().is_empty() {
+        bail!("SET path must not be empty");
+    }
+    if path.contains(';') {
+        bail!("Invalid SET path: {}", path);
+    }
+    Ok(())
+}
+
+pub trait SetClause: Sized {
+    /// `path op $param` for a nested path such as `address.city` or `tags[0]`
+    fn set_path<V: Serialize>(self, path: impl IntoIdiom, op: SetOp, value: V) -> Result<Self>;
+    /// Removes a nested field
+    fn unset_path(self, path: impl IntoIdiom) -> Result<Self>;
+
+    /// `column = $param`
+    fn set<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self>;
+    /// `column += $param`
+    fn increment<C: ColumnType, V: Serialize>(self, column: C, by: V) -> Result<Self>;
+    /// `column -= $param`
+    fn decrement<C: ColumnType, V: Serialize>(self, column: C, by: V) -> Result<Self>;
+    /// `column += $param` on an array column
+    fn append<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self>;
+    /// `column -= $param` on an array column, removing every equal element
+    fn remove_from<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self>;
+    /// Removes the column from the record
+    fn unset<C: ColumnType>(self, column: C) -> Result<Self>;
+}
+
+impl<T: HasAssignments + HasParams> SetClause for T {
+    fn set_path<V: Serialize>(mut self, path: impl IntoIdiom, op: SetOp, value: V) -> Result<Self> {
+        let path = path.into_idiom().bind(self.params_mut());
+        check_path(&path)?;
+        let param = format!("p{}", self.params().len());
+        self.params_mut()
+            .push((param.clone(), serde_json::to_value(value)?));
+        self.assignments_mut().push(Assignment::Set {
+            path,
+            op,
+            param,
+        });
+        Ok(self)
+    }
+
+    fn unset_path(mut self, path: impl IntoIdiom) -> Result<Self> {
+        let path = path.into_idiom().bind(self.params_mut());
+        check_path(&path)?;
+        self.assignments_mut().push(Assignment::Unset(path));
+        Ok(self)
+    }
+
+    fn set<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.set_path(&path, SetOp::Assign, value)
+    }
+
+    fn increment<C: ColumnType, V: Serialize>(self, column: C, by: V) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.set_path(&path, SetOp::Add, by)
+    }
+
+    fn decrement<C: ColumnType, V: Serialize>(self, column: C, by: V) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.set_path(&path, SetOp::Subtract, by)
+    }
+
+    fn append<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.set_path(&path, SetOp::Add, value)
+    }
+
+    fn remove_from<C: ColumnType, V: Serialize>(self, column: C, value: V) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.set_path(&path, SetOp::Subtract, value)
+    }
+
+    fn unset<C: ColumnType>(self, column: C) -> Result<Self> {
+        let path = column_path(&column, self.assignment_table())?;
+        self.unset_path(&path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_assignments() {
+        let set = |path: &str, op, param: &str| Assignment::Set {
+            path: path.into(),
+            op,
+            param: param.into(),
+        };
+        assert_eq!(build_assignments(&[]), "");
+        assert_eq!(
+            build_assignments(&[Assignment::Unset("a".into()), Assignment::Unset("b.c".into())]),
+            " UNSET a, b.c"
+        );
+        assert_eq!(
+            build_assignments(&[
+                set("stock", SetOp::Add, "p0"),
+                set("tags[0]", SetOp::Assign, "p1"),
+                Assignment::Unset("discount".into()),
+            ]),
+            " SET stock += $p0, tags[0] = $p1, discount = NONE"
+        );
+        assert!(check_path("a; DELETE users").is_err());
+        assert!(check_path("address.city").is_ok());
+    }
+}
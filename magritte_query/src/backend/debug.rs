@@ -0,0 +1,204 @@
+//! Debug rendering with the parameters inlined
+//!
+//! [`debug_sql`] replaces the `$p0`-style references of a built statement with
+//! their values written as SurrealQL literals, so the result can be pasted
+//! into Surrealist as is. Values bound to sensitive fields, or stored under a
+//! sensitive key of an object, are replaced with `'<redacted>'`. A field is
+//! sensitive when its name contains one of the redacted names, by default
+//! `password`, `secret`, `token` and `api_key`; more can be added with
+//! [`redact_fields`].
+//!
+//! ```rust,ignore
+//! let debug = Query::select::<User>()
+//!     .where_op("name", Operator::Eq, Some("O'Brien"))?
+//!     .where_op("password_hash", Operator::Eq, Some("..."))?
+//!     .to_debug_sql()?;
+//! // SELECT * FROM users WHERE name = 'O\'Brien' AND password_hash = '<redacted>';
+//! ```
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use serde_json::Value;
+
+use crate::backend::params::rewrite_params_after;
+
+/// Literal written in place of a redacted value
+pub const REDACTED: &str = "'<redacted>'";
+
+const DEFAULT_REDACTED: &[&str] = &["password", "secret", "token", "api_key"];
+
+static EXTRA_REDACTED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Adds field names whose values [`debug_sql`] redacts
+pub fn redact_fields(fields: &[&str]) {
+    EXTRA_REDACTED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(fields.iter().map(|f| f.to_ascii_lowercase()));
+}
+
+fn is_redacted(field: &str) -> bool {
+    let field = field.to_ascii_lowercase();
+    DEFAULT_REDACTED.iter().any(|name| field.contains(name))
+        || EXTRA_REDACTED
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|name| field.contains(name.as_str()))
+}
+
+fn is_plain_key(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn quote(s: &str, quote: char) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace(quote, &format!("\\{}", quote));
+    format!("{}{}{}", quote, escaped, quote)
+}
+
+/// Writes a JSON value as a SurrealQL literal
+pub fn surql_literal(value: &Value) -> String {
+    literal(value, false)
+}
+
+fn literal(value: &Value, redact: bool) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote(s, '\''),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(|v| literal(v, redact)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) if map.is_empty() => "{}".to_string(),
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, v)| {
+                    let key_literal = if is_plain_key(key) {
+                        key.clone()
+                    } else {
+                        quote(key, '"')
+                    };
+                    let value = if redact && is_redacted(key) {
+                        REDACTED.to_string()
+                    } else {
+                        literal(v, redact)
+                    };
+                    format!("{}: {}", key_literal, value)
+                })
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+/// The field a parameter is compared to or assigned to, taken from the text
+/// before it: `password = `, `token += ` or `{ secret: `
+fn preceding_field(before: &str) -> Option<&str> {
+    let before = before.trim_end();
+    let rest = before
+        .trim_end_matches(|c: char| "=<>!+-*/?~@:".contains(c))
+        .trim_end();
+    let is_keyword = |w: &str| w.chars().all(|c| c.is_ascii_uppercase());
+    let rest = match rest.rsplit_once(char::is_whitespace) {
+        // A keyword operator such as CONTAINS or INSIDE
+        Some((head, op)) if rest.len() == before.len() && is_keyword(op) => head.trim_end(),
+        _ => rest,
+    };
+    let start = rest
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || "_⟨⟩".contains(*c)))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let field = rest[start..].trim_matches(['⟨', '⟩']);
+    (!field.is_empty()).then_some(field)
+}
+
+/// Inlines `params` into `query` as SurrealQL literals, redacting sensitive
+/// values
+pub fn debug_sql(query: &str, params: &[(String, Value)]) -> String {
+    let values: HashMap<&str, &Value> = params.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    rewrite_params_after(query, |before, name| {
+        let value = values.get(name)?;
+        if preceding_field(before).is_some_and(is_redacted) {
+            Some(REDACTED.to_string())
+        } else {
+            Some(literal(value, true))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{validate_sql, Query, SetClause, SetOp, StatementBuilder, WhereClause};
+    use magritte_core::operator::Operator;
+    use serde_json::json;
+
+    #[test]
+    fn test_surql_literal() {
+        assert_eq!(surql_literal(&json!("O'Brien \\ co")), r"'O\'Brien \\ co'");
+        assert_eq!(surql_literal(&json!(null)), "NULL");
+        assert_eq!(
+            surql_literal(&json!({"first name": [1, true]})),
+            r#"{ "first name": [1, true] }"#
+        );
+    }
+
+    #[test]
+    fn test_debug_sql_redacts_sensitive_fields() {
+        let params = vec![
+            ("p0".to_string(), json!("alice")),
+            ("p1".to_string(), json!("hunter2")),
+            ("p2".to_string(), json!({"api_key": "k"})),
+            ("p3".to_string(), json!(["x"])),
+        ];
+        assert_eq!(
+            debug_sql(
+                "UPDATE users SET password_hash = $p1, profile = $p2 \
+                 WHERE name = $p0 AND tags CONTAINSANY $p3 AND x = $before;",
+                &params
+            ),
+            "UPDATE users SET password_hash = '<redacted>', \
+             profile = { api_key: '<redacted>' } \
+             WHERE name = 'alice' AND tags CONTAINSANY ['x'] AND x = $before;"
+        );
+    }
+
+    #[test]
+    fn test_builder_debug_sql() {
+        let debug = Query::select::<User>()
+            .where_op("name", Operator::Eq, Some("O'Brien"))
+            .unwrap()
+            .where_op("age", Operator::Gte, Some(18))
+            .unwrap()
+            .to_debug_sql()
+            .unwrap();
+        assert_eq!(debug, "SELECT * FROM users WHERE name = 'O\\'Brien' AND age >= 18;");
+        validate_sql(&debug).unwrap_or_else(|e| panic!("{}", e));
+
+        let debug = Query::update::<User>()
+            .set_path("password_hash", SetOp::Assign, "hunter2")
+            .unwrap()
+            .set_path("tags", SetOp::Add, vec!["admin"])
+            .unwrap()
+            .where_op("name", Operator::Eq, Some("alice"))
+            .unwrap()
+            .to_debug_sql()
+            .unwrap();
+        assert_eq!(
+            debug,
+            "UPDATE users SET password_hash = '<redacted>', tags += ['admin'] WHERE name = 'alice';"
+        );
+        validate_sql(&debug).unwrap_or_else(|e| panic!("{}", e));
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Renders a [`Duration`] as a SurrealQL duration literal, e.g. `5s` or `1500ms`.
+pub(crate) fn duration_to_sql(duration: &Duration) -> String {
+    if duration.subsec_nanos() == 0 {
+        format!("{}s", duration.as_secs())
+    } else if duration.subsec_nanos() % 1_000_000 == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{}ns", duration.as_nanos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_to_sql() {
+        assert_eq!(duration_to_sql(&Duration::from_secs(5)), "5s");
+        assert_eq!(duration_to_sql(&Duration::from_millis(1500)), "1500ms");
+        assert_eq!(duration_to_sql(&Duration::from_nanos(1_000_001)), "1000001ns");
+    }
+}
@@ -3,6 +3,8 @@ use magritte_core::value::SqlValue;
 use magritte_core::{Projection, VectorCondition};
 use serde_json::Value;
 
+use crate::backend::assign::Assignment;
+
 pub trait HasVectorConditions {
     fn get_vector_conditions(&self) -> &Vec<VectorCondition>;
     fn get_vector_conditions_mut(&mut self) -> &mut Vec<VectorCondition>;
@@ -19,6 +21,12 @@ pub trait HasParams {
 pub trait HasConditions {
     fn conditions_mut(&mut self) -> &mut Vec<(String, Operator, SqlValue)>;
 }
+pub trait HasAssignments {
+    /// Table the statement writes to, checked against column enums
+    fn assignment_table(&self) -> &'static str;
+    fn assignments(&self) -> &Vec<Assignment>;
+    fn assignments_mut(&mut self) -> &mut Vec<Assignment>;
+}
 
 pub trait HasLetConditions {
     fn get_lets(&self) -> &Vec<(String, String)>;
@@ -0,0 +1,143 @@
+//! Statement fingerprints
+//!
+//! A fingerprint hashes the shape of a statement: its table, clauses and
+//! parameter names, but not the parameter values, record ids or limits. Two
+//! builders that differ only in those literals share a fingerprint.
+//! Fingerprints are stable within a process and are recorded on the `execute`
+//! span to group query metrics.
+//!
+//! A render key hashes everything `build()` renders, literals included, but
+//! still not the parameter values. Executing statements look their SQL up by
+//! render key in a bounded cache, so a hot query only rebinds its parameters.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Debug, Write};
+use std::hash::Hasher;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use moka::sync::Cache;
+
+use crate::backend::strict::is_strict_mode;
+use crate::StatementBuilder;
+
+pub trait Fingerprint {
+    /// Hash of the statement shape, excluding parameter values
+    fn fingerprint(&self) -> u64;
+
+    /// Hash of everything `build()` renders, excluding parameter values.
+    /// Statements with the same render key build the same SQL.
+    fn render_key(&self) -> u64;
+}
+
+/// Number of rendered statements kept by [`build_cached`]
+pub const SQL_CACHE_CAPACITY: u64 = 1024;
+
+fn sql_cache() -> &'static Cache<(u64, bool), String> {
+    static CACHE: OnceLock<Cache<(u64, bool), String>> = OnceLock::new();
+    CACHE.get_or_init(|| Cache::new(SQL_CACHE_CAPACITY))
+}
+
+/// Builds `statement`, reusing the SQL of an earlier statement with the same
+/// render key. Strict mode is part of the key, since it changes what
+/// `build()` accepts. Failed builds are not cached.
+pub fn build_cached<S>(statement: &S) -> Result<String>
+where
+    S: StatementBuilder + Fingerprint,
+{
+    let key = (statement.render_key(), is_strict_mode());
+    if let Some(sql) = sql_cache().get(&key) {
+        return Ok(sql);
+    }
+    let sql = statement.build()?;
+    sql_cache().insert(key, sql.clone());
+    Ok(sql)
+}
+
+/// Feeds the `Debug` output of each shape part into a hasher without
+/// allocating
+pub(crate) struct ShapeHasher(DefaultHasher);
+
+impl ShapeHasher {
+    pub(crate) fn new(table: &str) -> Self {
+        let mut hasher = Self(DefaultHasher::new());
+        hasher.0.write(table.as_bytes());
+        hasher
+    }
+
+    pub(crate) fn part(mut self, part: &dyn Debug) -> Self {
+        // Writing into a hasher cannot fail
+        let _ = write!(self, "{:?}", part);
+        self.0.write_u8(0xff);
+        self
+    }
+
+    pub(crate) fn finish(self) -> u64 {
+        self.0.finish()
+    }
+}
+
+impl Write for ShapeHasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, WhereClause};
+    use magritte_core::operator::Operator;
+    use magritte_core::SurrealId;
+
+    #[test]
+    fn test_shape_hash() {
+        let a = ShapeHasher::new("users").part(&Some(10)).part(&"name").finish();
+        let b = ShapeHasher::new("users").part(&Some(10)).part(&"name").finish();
+        let c = ShapeHasher::new("users").part(&Some(1)).part(&"0name").finish();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_literals_share_a_fingerprint() {
+        let query = |id: &str, limit: usize| {
+            Query::select::<User>()
+                .where_id(SurrealId::new(id))
+                .where_op("age", Operator::Gt, Some(18))
+                .unwrap()
+                .limit(limit)
+        };
+        assert_eq!(query("alice", 10).fingerprint(), query("bob", 20).fingerprint());
+        assert_ne!(
+            query("alice", 10).fingerprint(),
+            Query::select::<User>().limit(10).fingerprint()
+        );
+        assert_ne!(query("alice", 10).render_key(), query("bob", 20).render_key());
+    }
+
+    #[test]
+    fn test_build_cached() {
+        let query = |email: &str, limit: usize| {
+            Query::select::<User>()
+                .where_op("email", Operator::Eq, Some(email))
+                .unwrap()
+                .limit(limit)
+        };
+        assert_eq!(
+            query("a@b.c", 10).render_key(),
+            query("d@e.f", 10).render_key()
+        );
+        for (email, limit) in [("a@b.c", 10), ("d@e.f", 10), ("a@b.c", 20)] {
+            let query = query(email, limit);
+            assert_eq!(build_cached(&query).unwrap(), query.build().unwrap());
+        }
+
+        let alice = Query::delete::<User>().where_id(SurrealId::new("alice"));
+        let bob = Query::delete::<User>().where_id(SurrealId::new("bob"));
+        assert_eq!(build_cached(&alice).unwrap(), "DELETE users:alice;");
+        assert_eq!(build_cached(&bob).unwrap(), "DELETE users:bob;");
+    }
+}
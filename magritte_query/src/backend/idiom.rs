@@ -0,0 +1,375 @@
+//! Typed idioms (field paths)
+//!
+//! [`Idiom`] builds the paths SurrealQL accepts wherever a field goes: nested
+//! fields, array indexes and slices, `[WHERE ...]` filters with bound values,
+//! `.*`, destructuring, optional chaining, method calls and graph steps. Field
+//! names that are not plain identifiers, or that are reserved words, are
+//! escaped as `⟨name⟩`.
+//!
+//! Builder methods that take a field accept anything implementing
+//! [`IntoIdiom`]. A string naming a single field, e.g. `"type"`, is escaped
+//! like [`Idiom::new`]; any other string, such as `"address.city"` or
+//! `"count()"`, is used verbatim. [`Idiom::raw`] always uses its text
+//! verbatim.
+//!
+//! ```rust,ignore
+//! let active_cities = Idiom::new("addresses")
+//!     .filter("active", Operator::Eq, true)?
+//!     .field("city");
+//! Query::select::<User>()
+//!     .field(active_cities, Some("cities"))
+//!     .where_op(Idiom::new("profile").optional().field("type"), Operator::Eq, Some("admin"))?;
+//! // SELECT addresses[WHERE active = $p0].city AS cities FROM users
+//! //     WHERE profile?.⟨type⟩ = $p1;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use anyhow::Result;
+use magritte_core::operator::Operator;
+use magritte_core::RelationDirection;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::backend::params::rename_params;
+
+/// Words escaped when used as field names
+const RESERVED: &[&str] = &[
+    "AND", "AS", "ASC", "BY", "CONTENT", "CONTAINS", "CREATE", "DELETE", "DESC", "FETCH", "FROM",
+    "GROUP", "IN", "INSIDE", "IS", "LIMIT", "MERGE", "NONE", "NOT", "NULL", "OR", "ORDER",
+    "RELATE", "RETURN", "SELECT", "SET", "SPLIT", "START", "TIMEOUT", "TYPE", "UNSET", "UPDATE",
+    "VALUE", "WHERE", "WITH",
+];
+
+fn is_plain(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Escapes a field name unless it is a plain, non-reserved identifier
+pub fn escape_ident(name: &str) -> String {
+    if is_plain(name) && !RESERVED.contains(&name.to_ascii_uppercase().as_str()) {
+        name.to_string()
+    } else {
+        format!("⟨{}⟩", name.replace('⟩', "\\⟩"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Field(String),
+    Index(i64),
+    Last,
+    All,
+    Filter(String),
+    Destructure(Vec<String>),
+    Optional,
+    Method(String, Vec<String>),
+    Graph(RelationDirection, String),
+    Raw(String),
+}
+
+/// A field path with the values bound by its filters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Idiom {
+    parts: Vec<Part>,
+    params: Vec<(String, Value)>,
+}
+
+impl Idiom {
+    /// A path starting at `name`
+    pub fn new(name: &str) -> Self {
+        Self::default().push(Part::Field(name.to_string()))
+    }
+
+    /// A path starting with a graph step from the current record
+    pub fn graph(direction: RelationDirection, edge: &str) -> Self {
+        Self::default().push(Part::Graph(direction, edge.to_string()))
+    }
+
+    /// Raw SurrealQL, used verbatim
+    pub fn raw(path: &str) -> Self {
+        Self::default().push(Part::Raw(path.to_string()))
+    }
+
+    fn push(mut self, part: Part) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// `.name`
+    pub fn field(self, name: &str) -> Self {
+        self.push(Part::Field(name.to_string()))
+    }
+
+    /// `[n]`
+    pub fn index(self, index: i64) -> Self {
+        self.push(Part::Index(index))
+    }
+
+    /// `[$]`, the last element
+    pub fn last(self) -> Self {
+        self.push(Part::Last)
+    }
+
+    /// `.slice(start, len)`
+    pub fn slice(self, start: usize, len: usize) -> Self {
+        self.method("slice", &[&start.to_string(), &len.to_string()])
+    }
+
+    /// `.*`
+    pub fn all(self) -> Self {
+        self.push(Part::All)
+    }
+
+    /// `[WHERE field op $p]`, with `value` bound as a parameter
+    pub fn filter<V: Serialize>(mut self, field: &str, op: Operator, value: V) -> Result<Self> {
+        let param = format!("p{}", self.params.len());
+        self.params
+            .push((param.clone(), serde_json::to_value(value)?));
+        let condition = format!("{} {} ${}", escape_ident(field), String::from(op), param);
+        Ok(self.push(Part::Filter(condition)))
+    }
+
+    /// `[WHERE condition]` with a raw condition
+    pub fn filter_raw(self, condition: &str) -> Self {
+        self.push(Part::Filter(condition.to_string()))
+    }
+
+    /// `.{ a, b }`
+    pub fn destructure(self, fields: &[&str]) -> Self {
+        self.push(Part::Destructure(
+            fields.iter().map(|f| escape_ident(f)).collect(),
+        ))
+    }
+
+    /// `?`, stops the path at NONE instead of failing
+    pub fn optional(self) -> Self {
+        self.push(Part::Optional)
+    }
+
+    /// `.name(args)` with raw arguments
+    pub fn method(self, name: &str, args: &[&str]) -> Self {
+        self.push(Part::Method(
+            name.to_string(),
+            args.iter().map(|a| a.to_string()).collect(),
+        ))
+    }
+
+    /// `->edge`
+    pub fn out(self, edge: &str) -> Self {
+        self.push(Part::Graph(RelationDirection::Out, edge.to_string()))
+    }
+
+    /// `<-edge`
+    pub fn in_(self, edge: &str) -> Self {
+        self.push(Part::Graph(RelationDirection::In, edge.to_string()))
+    }
+
+    /// `<->edge`
+    pub fn both(self, edge: &str) -> Self {
+        self.push(Part::Graph(RelationDirection::Both, edge.to_string()))
+    }
+
+    pub fn params(&self) -> &[(String, Value)] {
+        &self.params
+    }
+
+    /// Renders the path and moves its parameters into `params`, renumbering
+    /// them after the ones already there
+    pub fn bind(self, params: &mut Vec<(String, Value)>) -> String {
+        let path = self.to_string();
+        if self.params.is_empty() {
+            return path;
+        }
+        let mut renames = HashMap::new();
+        for (name, value) in self.params {
+            let new_name = format!("p{}", params.len());
+            renames.insert(name, new_name.clone());
+            params.push((new_name, value));
+        }
+        rename_params(&path, &renames)
+    }
+}
+
+impl Display for Idiom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            match part {
+                Part::Field(name) if i == 0 => write!(f, "{}", escape_ident(name))?,
+                Part::Field(name) => write!(f, ".{}", escape_ident(name))?,
+                Part::Index(n) => write!(f, "[{}]", n)?,
+                Part::Last => write!(f, "[$]")?,
+                Part::All => write!(f, ".*")?,
+                Part::Filter(condition) => write!(f, "[WHERE {}]", condition)?,
+                Part::Destructure(fields) => write!(f, ".{{ {} }}", fields.join(", "))?,
+                Part::Optional => write!(f, "?")?,
+                Part::Method(name, args) => write!(f, ".{}({})", name, args.join(", "))?,
+                Part::Graph(direction, edge) => write!(f, "{}{}", direction, escape_ident(edge))?,
+                Part::Raw(raw) => write!(f, "{}", raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Anything accepted as a field by the builders
+pub trait IntoIdiom {
+    fn into_idiom(self) -> Idiom;
+}
+
+impl IntoIdiom for Idiom {
+    fn into_idiom(self) -> Idiom {
+        self
+    }
+}
+
+impl IntoIdiom for &Idiom {
+    fn into_idiom(self) -> Idiom {
+        self.clone()
+    }
+}
+
+impl IntoIdiom for &str {
+    fn into_idiom(self) -> Idiom {
+        if is_plain(self) {
+            Idiom::new(self)
+        } else {
+            Idiom::raw(self)
+        }
+    }
+}
+
+impl IntoIdiom for String {
+    fn into_idiom(self) -> Idiom {
+        self.as_str().into_idiom()
+    }
+}
+
+impl IntoIdiom for &String {
+    fn into_idiom(self) -> Idiom {
+        self.as_str().into_idiom()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Product, User};
+    use crate::{Query, SetOp, Validate, WhereClause};
+    use serde_json::json;
+
+    #[test]
+    fn test_idiom_display() {
+        assert_eq!(escape_ident("name"), "name");
+        assert_eq!(escape_ident("type"), "⟨type⟩");
+        assert_eq!(escape_ident("first-name"), "⟨first-name⟩");
+        assert_eq!(Idiom::new("tags").index(0).to_string(), "tags[0]");
+        assert_eq!(
+            Idiom::new("profile").optional().field("type").to_string(),
+            "profile?.⟨type⟩"
+        );
+        assert_eq!(
+            Idiom::new("address")
+                .destructure(&["city", "zip code"])
+                .to_string(),
+            "address.{ city, ⟨zip code⟩ }"
+        );
+        assert_eq!(
+            Idiom::graph(RelationDirection::Out, "purchased")
+                .out("products")
+                .all()
+                .to_string(),
+            "->purchased->products.*"
+        );
+        assert_eq!(
+            Idiom::new("items")
+                .slice(1, 2)
+                .last()
+                .method("len", &[])
+                .to_string(),
+            "items.slice(1, 2)[$].len()"
+        );
+    }
+
+    #[test]
+    fn test_idiom_bind() {
+        let idiom = Idiom::new("addresses")
+            .filter("active", Operator::Eq, true)
+            .unwrap()
+            .filter("city", Operator::Eq, "Paris")
+            .unwrap()
+            .field("zip");
+        let mut params = vec![("p0".to_string(), json!(1))];
+        assert_eq!(
+            idiom.bind(&mut params),
+            "addresses[WHERE active = $p1][WHERE city = $p2].zip"
+        );
+        assert_eq!(params[2], ("p2".to_string(), json!("Paris")));
+    }
+
+    #[test]
+    fn test_idioms_in_select() {
+        let sql = Query::select::<User>()
+            .field(
+                Idiom::new("addresses")
+                    .filter("active", Operator::Eq, true)
+                    .unwrap()
+                    .field("city"),
+                Some("cities"),
+            )
+            .where_op(Idiom::new("profile").optional().field("type"), Operator::Eq, Some("admin"))
+            .unwrap()
+            .validate()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT addresses[WHERE active = $p0].city AS cities FROM users \
+             WHERE profile?.⟨type⟩ = $p1;"
+        );
+        Query::select::<User>()
+            .field(Idiom::new("profile").destructure(&["name", "type"]), None)
+            .field(
+                Idiom::graph(RelationDirection::Out, "purchased")
+                    .out("products")
+                    .field("name"),
+                None,
+            )
+            .order_by_field(Idiom::new("tags").index(0), true)
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_idioms_in_update() {
+        Query::update::<Product>()
+            .set_path(Idiom::new("metadata").field("sizes").last(), SetOp::Assign, "XL")
+            .unwrap()
+            .unset_path(Idiom::new("metadata").field("first-seen"))
+            .unwrap()
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_str_fields_escaped() {
+        assert_eq!("type".into_idiom().to_string(), "⟨type⟩");
+        assert_eq!("name".into_idiom().to_string(), "name");
+        assert_eq!("profile.type".into_idiom().to_string(), "profile.type");
+        assert_eq!(Idiom::raw("type").to_string(), "type");
+
+        let sql = Query::select::<User>()
+            .where_op("type", Operator::Eq, Some("admin"))
+            .unwrap()
+            .order_by_field("value", false)
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM users WHERE ⟨type⟩ = $p0 ORDER BY ⟨value⟩ DESC;"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Runs the registered interceptors on a builder before it executes
+//!
+//! The conditions interceptors add are appended to the builder as raw
+//! conditions and their values replace its parameters, so the statement is
+//! built as if the filters had been written by hand. Every builder runs them
+//! from [`StatementBuilder::build_prepared`](crate::StatementBuilder::build_prepared),
+//! which `execute` and transaction steps go through.
+
+use anyhow::{bail, Result};
+use magritte_core::operator::Operator;
+use magritte_core::value::SqlValue;
+use magritte_db::{StatementContext, StatementKind};
+use serde_json::Value;
+
+use crate::expr::{HasConditions, HasParams};
+
+fn render_condition((field, op, value): &(String, Operator, SqlValue)) -> String {
+    format!("{} {} {}", field, String::from(*op), value)
+        .trim()
+        .to_string()
+}
+
+/// Applies the interceptors to `stmt` and returns the comment to put in front
+/// of the statement
+pub(crate) fn apply<S: HasConditions + HasParams>(
+    stmt: &mut S,
+    kind: StatementKind,
+    table: &str,
+    targets_records: bool,
+) -> Result<String> {
+    if !magritte_db::has_interceptors() {
+        return Ok(String::new());
+    }
+    let mut ctx = StatementContext::new(
+        kind,
+        table,
+        targets_records,
+        stmt.conditions_mut().iter().map(render_condition).collect(),
+        stmt.params().clone(),
+    );
+    magritte_db::intercept(&mut ctx)?;
+    let (added, params, comment) = ctx.into_parts();
+    stmt.conditions_mut().extend(
+        added
+            .into_iter()
+            .map(|condition| (condition, Operator::Raw, SqlValue::Null)),
+    );
+    *stmt.params_mut() = params;
+    Ok(comment)
+}
+
+/// Applies the interceptors to a statement without a WHERE clause, such as
+/// CREATE, INSERT or RELATE. Interceptors can tag or reject it, but adding a
+/// condition fails.
+pub(crate) fn apply_unfiltered(
+    params: &mut Vec<(String, Value)>,
+    kind: StatementKind,
+    table: &str,
+    targets_records: bool,
+) -> Result<String> {
+    if !magritte_db::has_interceptors() {
+        return Ok(String::new());
+    }
+    let mut ctx = StatementContext::new(kind, table, targets_records, vec![], params.clone());
+    magritte_db::intercept(&mut ctx)?;
+    let (added, new_params, comment) = ctx.into_parts();
+    if !added.is_empty() {
+        bail!(
+            "{} on {} has no WHERE clause for the conditions added by interceptors: {}",
+            kind,
+            table,
+            added.join(" AND ")
+        );
+    }
+    *params = new_params;
+    Ok(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use magritte_core::operator::Operator;
+    use magritte_db::register_interceptor;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::Invoice;
+    use crate::{Query, StatementBuilder, TransactionStatement, WhereClause};
+
+    /// Registers a global interceptor that only touches `invoices`, so the
+    /// other tests are unaffected
+    fn register_invoice_filter() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            register_interceptor(|ctx: &mut StatementContext| -> Result<()> {
+                if ctx.table() != "invoices" {
+                    return Ok(());
+                }
+                ctx.tag(ctx.kind().to_string());
+                if ctx.kind() != StatementKind::Create {
+                    let param = ctx.bind("acme")?;
+                    ctx.and_where(format!("tenant = {}", param));
+                }
+                Ok(())
+            })
+        });
+    }
+
+    #[test]
+    fn test_select_is_intercepted() {
+        register_invoice_filter();
+        let mut query = Query::select::<Invoice>()
+            .where_op("total", Operator::Gt, Some(10))
+            .unwrap();
+        assert_eq!(
+            StatementBuilder::build_prepared(&mut query).unwrap(),
+            "/* SELECT */ SELECT * FROM invoices WHERE total > $p0 AND tenant = $p1;"
+        );
+        assert_eq!(query.with_params()[1], ("p1".to_string(), json!("acme")));
+    }
+
+    #[test]
+    fn test_create_is_tagged() {
+        register_invoice_filter();
+        let mut query = Query::create::<Invoice>().set("total", 10).unwrap();
+        assert_eq!(
+            StatementBuilder::build_prepared(&mut query).unwrap(),
+            "/* CREATE */ CREATE invoices SET total = 10;"
+        );
+    }
+
+    #[test]
+    fn test_relate_rejects_added_conditions() {
+        register_invoice_filter();
+        let mut query = Query::relate()
+            .from_record("users:alice")
+            .edge_table("invoices")
+            .to_record("orders:1");
+        let error = StatementBuilder::build_prepared(&mut query).unwrap_err();
+        assert!(error.to_string().starts_with("RELATE on invoices has no WHERE clause"));
+    }
+
+    #[test]
+    fn test_transaction_steps_are_intercepted() {
+        register_invoice_filter();
+        let tx = TransactionStatement::new().then(
+            Query::update::<Invoice>()
+                .merge(json!({"paid": true}))
+                .unwrap()
+                .where_op("total", Operator::Gt, Some(10))
+                .unwrap(),
+        );
+        let sql = tx.build().unwrap();
+        assert!(sql.contains("/* UPDATE */ UPDATE invoices MERGE"), "{}", sql);
+        assert!(sql.contains("WHERE total > $p0 AND tenant = $p1"), "{}", sql);
+    }
+}
@@ -1,16 +1,41 @@
 
+pub mod assign;
+pub mod debug;
+pub(crate) mod duration;
 pub mod expr;
+pub mod fingerprint;
 pub mod from;
 pub mod graph;
+pub mod idiom;
+pub(crate) mod intercept;
+pub(crate) mod params;
+pub mod policy;
+pub mod pretty;
 pub mod query_result;
 pub mod returns;
+#[cfg(feature = "with-geo")]
+pub mod spatial;
+pub mod strict;
+pub mod timestamp;
+pub mod validate;
 pub mod vector_search;
 pub mod wheres;
 
+pub use assign::*;
+pub use debug::*;
 pub use expr::*;
+pub use fingerprint::*;
 pub use from::*;
 pub use graph::*;
+pub use idiom::*;
+pub use policy::*;
+pub use pretty::*;
 pub use query_result::*;
 pub use returns::*;
+#[cfg(feature = "with-geo")]
+pub use spatial::*;
+pub use strict::*;
+pub use timestamp::*;
+pub use validate::*;
 pub use vector_search::*;
 pub use wheres::*;
@@ -0,0 +1,162 @@
+//! Helpers for rewriting `$param` references inside rendered SurrealQL.
+//!
+//! Builders name their bound parameters `p0`, `p1`, ... so statements that are
+//! composed together (blocks, transactions, function bodies) need their
+//! parameters renamed or inlined. A naive `str::replace` turns `$p10` into
+//! `$X0` when renaming `$p1`, so references are matched as whole identifiers.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// Rewrites every `$ident` outside of string literals using `f`.
+///
+/// When `f` returns `None` the reference is left untouched.
+pub(crate) fn rewrite_params<F>(query: &str, mut f: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    rewrite_params_after(query, |_, name| f(name))
+}
+
+/// Like [`rewrite_params`], also passing `f` the text rendered before the
+/// reference.
+pub(crate) fn rewrite_params_after<F>(query: &str, mut f: F) -> String
+where
+    F: FnMut(&str, &str) -> Option<String>,
+{
+    let mut out = String::with_capacity(query.len());
+    let mut chars = query.char_indices().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some((i, c)) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == '\\' {
+                if let Some((_, escaped)) = chars.next() {
+                    out.push(escaped);
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '$' => {
+                let start = i + 1;
+                let mut end = start;
+                while let Some(&(j, n)) = chars.peek() {
+                    if n.is_ascii_alphanumeric() || n == '_' {
+                        end = j + n.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let name = &query[start..end];
+                match (!name.is_empty()).then(|| f(&out, name)).flatten() {
+                    Some(replacement) => out.push_str(&replacement),
+                    None => {
+                        out.push('$');
+                        out.push_str(name);
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Renames parameter references according to `renames` (old name -> new name).
+pub(crate) fn rename_params(query: &str, renames: &HashMap<String, String>) -> String {
+    rewrite_params(query, |name| renames.get(name).map(|new| format!("${}", new)))
+}
+
+/// Replaces parameter references with their values rendered as literals.
+///
+/// Used where a statement is stored rather than executed, e.g. a function
+/// body, so the values cannot be bound at call time.
+pub(crate) fn inline_params(query: &str, params: &[(String, Value)]) -> String {
+    let values: HashMap<&str, &Value> = params.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    rewrite_params(query, |name| values.get(name).map(|v| v.to_string()))
+}
+
+/// Renumbers the parameters of several rendered statements so that they can be
+/// concatenated without clashes. Returns the rewritten statements and the
+/// merged parameter list.
+pub(crate) fn merge_params(
+    statements: &[(String, Vec<(String, Value)>)],
+) -> (Vec<String>, Vec<(String, Value)>) {
+    let mut queries = Vec::with_capacity(statements.len());
+    let mut all_params = Vec::new();
+
+    for (query, params) in statements {
+        let mut renames = HashMap::new();
+        for (name, value) in params {
+            let new_name = format!("p{}", all_params.len());
+            renames.insert(name.clone(), new_name.clone());
+            all_params.push((new_name, value.clone()));
+        }
+        queries.push(rename_params(query, &renames));
+    }
+
+    (queries, all_params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rename_does_not_touch_longer_names() {
+        let renames = HashMap::from([
+            ("p1".to_string(), "p2".to_string()),
+            ("p10".to_string(), "p11".to_string()),
+        ]);
+        assert_eq!(
+            rename_params("a = $p1 AND b = $p10", &renames),
+            "a = $p2 AND b = $p11"
+        );
+    }
+
+    #[test]
+    fn test_rename_skips_string_literals() {
+        let renames = HashMap::from([("p0".to_string(), "p5".to_string())]);
+        assert_eq!(
+            rename_params("a = $p0 AND b = '$p0'", &renames),
+            "a = $p5 AND b = '$p0'"
+        );
+    }
+
+    #[test]
+    fn test_inline_params() {
+        let params = vec![("p0".to_string(), json!("it's")), ("p1".to_string(), json!(3))];
+        assert_eq!(
+            inline_params("name = $p0 AND age > $p1 AND x = $before", &params),
+            "name = \"it's\" AND age > 3 AND x = $before"
+        );
+    }
+
+    #[test]
+    fn test_merge_params() {
+        let statements = vec![
+            ("a = $p0;".to_string(), vec![("p0".to_string(), json!(1))]),
+            (
+                "b = $p0 AND c = $p1;".to_string(),
+                vec![("p0".to_string(), json!(2)), ("p1".to_string(), json!(3))],
+            ),
+        ];
+        let (queries, params) = merge_params(&statements);
+        assert_eq!(queries, vec!["a = $p0;", "b = $p1 AND c = $p2;"]);
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[2], ("p2".to_string(), json!(3)));
+    }
+}
@@ -0,0 +1,383 @@
+//! Safety policy against unbounded queries
+//!
+//! A [`SafetyPolicy`] installed with [`set_safety_policy`] is checked when a
+//! SELECT, UPDATE, UPSERT or DELETE builder executes or joins a transaction,
+//! before interceptors run. It can require a LIMIT or a record target on
+//! SELECTs from large tables, reject mutations with neither conditions nor
+//! record targets, and cap FETCH and recursive graph depths. A statement opts
+//! out of the table-wide checks with `.allow_full_table()`.
+//!
+//! **The mutation guard is on by default**: out of the box, an UPDATE,
+//! UPSERT or DELETE (edge deletes included) without a WHERE clause or a
+//! record target fails to execute. Every other check is off until a policy
+//! enables it. [`SafetyPolicy::allow_unguarded_mutations`] turns the guard
+//! off, and [`clear_safety_policy`] removes every check.
+//!
+//! ```rust,ignore
+//! Query::delete::<User>().execute().await; // Err: DELETE on users has no conditions
+//! Query::delete::<User>().allow_full_table().execute().await?;
+//!
+//! set_safety_policy(
+//!     SafetyPolicy::new()
+//!         .large_tables(&["events", "audit_log"])
+//!         .max_fetch_depth(2)
+//!         .max_recursion_depth(8),
+//! );
+//! ```
+
+use std::sync::RwLock;
+
+use anyhow::{bail, Result};
+use magritte_core::{Projection, RecursiveDepth};
+
+/// Checks applied to statements at execution time
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafetyPolicy {
+    large_tables: Vec<String>,
+    all_tables_large: bool,
+    guard_mutations: bool,
+    max_fetch_depth: Option<usize>,
+    max_recursion_depth: Option<usize>,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SafetyPolicy {
+    /// A policy guarding mutations, with every other check disabled
+    pub const fn new() -> Self {
+        Self {
+            large_tables: Vec::new(),
+            all_tables_large: false,
+            guard_mutations: true,
+            max_fetch_depth: None,
+            max_recursion_depth: None,
+        }
+    }
+
+    /// SELECTs from these tables need a LIMIT or a record target
+    pub fn large_tables(mut self, tables: &[&str]) -> Self {
+        self.large_tables
+            .extend(tables.iter().map(|t| t.to_string()));
+        self
+    }
+
+    /// Every SELECT needs a LIMIT or a record target
+    pub fn all_tables_large(mut self) -> Self {
+        self.all_tables_large = true;
+        self
+    }
+
+    /// UPDATE, UPSERT and DELETE need conditions or a record target. This is
+    /// the default.
+    pub fn guard_mutations(mut self) -> Self {
+        self.guard_mutations = true;
+        self
+    }
+
+    /// Lets UPDATE, UPSERT and DELETE run without conditions or a record
+    /// target
+    pub fn allow_unguarded_mutations(mut self) -> Self {
+        self.guard_mutations = false;
+        self
+    }
+
+    /// Caps the number of levels in a FETCH path, `a.b` being two
+    pub fn max_fetch_depth(mut self, depth: usize) -> Self {
+        self.max_fetch_depth = Some(depth);
+        self
+    }
+
+    /// Caps recursive graph traversals and rejects `@.{..}` without a bound
+    pub fn max_recursion_depth(mut self, depth: usize) -> Self {
+        self.max_recursion_depth = Some(depth);
+        self
+    }
+
+    fn is_large(&self, table: &str) -> bool {
+        self.all_tables_large || self.large_tables.iter().any(|t| t == table)
+    }
+
+    /// Checks a SELECT. Selects with GROUP ALL return a single row and are
+    /// never unbounded.
+    pub fn check_select(&self, select: &SelectShape<'_>) -> Result<()> {
+        if self.is_large(select.table)
+            && !select.allow_full_table
+            && !select.targets_records
+            && !select.group_all
+            && select.limit.is_none()
+        {
+            bail!(
+                "SELECT on large table {} needs a LIMIT or a record target, \
+                 or .allow_full_table()",
+                select.table
+            );
+        }
+        if let Some(max) = self.max_fetch_depth {
+            for field in select.fetch_fields {
+                let depth = field.split('.').count();
+                if depth > max {
+                    bail!(
+                        "FETCH {} is {} levels deep, the policy allows {}",
+                        field,
+                        depth,
+                        max
+                    );
+                }
+            }
+        }
+        if let Some(max) = self.max_recursion_depth {
+            for projection in select.projections {
+                let Projection::Relation(relation) = projection else {
+                    continue;
+                };
+                let depth = match &relation.recursive {
+                    None => continue,
+                    Some(RecursiveDepth::Fixed(n)) => Some(*n),
+                    Some(RecursiveDepth::Range(_, n)) => Some(*n),
+                    Some(RecursiveDepth::OpenEnded(n)) => *n,
+                };
+                match depth {
+                    None => bail!(
+                        "Unbounded recursive traversal of {}, the policy allows {} levels",
+                        relation.edge,
+                        max
+                    ),
+                    Some(depth) if depth > max => bail!(
+                        "Recursive traversal of {} is {} levels deep, the policy allows {}",
+                        relation.edge,
+                        depth,
+                        max
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks an UPDATE, UPSERT or DELETE
+    pub fn check_mutation(&self, mutation: &MutationShape<'_>) -> Result<()> {
+        if self.guard_mutations
+            && !mutation.allow_full_table
+            && !mutation.targets_records
+            && !mutation.has_conditions
+        {
+            bail!(
+                "{} on {} has no conditions and no record target, \
+                 use .allow_full_table() to touch every record",
+                mutation.kind,
+                mutation.table
+            );
+        }
+        Ok(())
+    }
+}
+
+/// What the policy looks at in a SELECT
+#[derive(Debug, Clone, Copy)]
+pub struct SelectShape<'a> {
+    pub table: &'a str,
+    pub targets_records: bool,
+    pub limit: Option<usize>,
+    pub group_all: bool,
+    pub fetch_fields: &'a [String],
+    pub projections: &'a [Projection],
+    pub allow_full_table: bool,
+}
+
+/// What the policy looks at in an UPDATE, UPSERT or DELETE
+#[derive(Debug, Clone, Copy)]
+pub struct MutationShape<'a> {
+    /// `UPDATE`, `UPSERT` or `DELETE`
+    pub kind: &'a str,
+    pub table: &'a str,
+    pub targets_records: bool,
+    pub has_conditions: bool,
+    pub allow_full_table: bool,
+}
+
+static POLICY: RwLock<Option<SafetyPolicy>> = RwLock::new(Some(SafetyPolicy::new()));
+
+/// Installs the policy checked by every statement, replacing the previous one
+pub fn set_safety_policy(policy: SafetyPolicy) {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(policy);
+}
+
+/// Removes the installed policy, including the default mutation guard
+pub fn clear_safety_policy() {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+pub fn safety_policy() -> Option<SafetyPolicy> {
+    POLICY.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub(crate) fn enforce_select(select: SelectShape<'_>) -> Result<()> {
+    match POLICY.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(policy) => policy.check_select(&select),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn enforce_mutation(mutation: MutationShape<'_>) -> Result<()> {
+    match POLICY.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(policy) => policy.check_mutation(&mutation),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, StatementBuilder, WhereClause};
+    use magritte_core::operator::Operator;
+    use magritte_core::{Relation, RelationDirection, SurrealId};
+    use serde_json::json;
+
+    fn select<'a>(fetch: &'a [String], projections: &'a [Projection]) -> SelectShape<'a> {
+        SelectShape {
+            table: "events",
+            targets_records: false,
+            limit: None,
+            group_all: false,
+            fetch_fields: fetch,
+            projections,
+            allow_full_table: false,
+        }
+    }
+
+    #[test]
+    fn test_select_on_large_table() {
+        let policy = SafetyPolicy::new().large_tables(&["events"]);
+        assert!(policy.check_select(&select(&[], &[])).is_err());
+        let limited = SelectShape {
+            limit: Some(10),
+            ..select(&[], &[])
+        };
+        assert!(policy.check_select(&limited).is_ok());
+        let opted_in = SelectShape {
+            allow_full_table: true,
+            ..select(&[], &[])
+        };
+        assert!(policy.check_select(&opted_in).is_ok());
+        let other = SelectShape {
+            table: "users",
+            ..select(&[], &[])
+        };
+        assert!(policy.check_select(&other).is_ok());
+    }
+
+    #[test]
+    fn test_explain_full_is_guarded() {
+        let policy = SafetyPolicy::new().large_tables(&["users"]);
+        let plain = Query::select::<User>().explain(false);
+        assert!(policy.check_select(&plain.shape()).is_ok());
+        let full = Query::select::<User>().explain(true);
+        assert!(policy.check_select(&full.shape()).is_err());
+        assert!(policy
+            .check_select(&Query::select::<User>().explain(true).limit(10).shape())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_depth_caps() {
+        let policy = SafetyPolicy::new()
+            .max_fetch_depth(2)
+            .max_recursion_depth(4);
+        let fetch = vec!["author.company.owner".to_string()];
+        assert!(policy.check_select(&select(&fetch, &[])).is_err());
+
+        let mut relation = Relation::new(RelationDirection::Out, "knows", "users");
+        relation.recursive = Some(RecursiveDepth::OpenEnded(None));
+        let projections = vec![Projection::Relation(relation.clone())];
+        let err = policy.check_select(&select(&[], &projections)).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unbounded recursive traversal of knows"));
+        relation.recursive = Some(RecursiveDepth::OpenEnded(Some(3)));
+        let projections = vec![Projection::Relation(relation)];
+        assert!(policy.check_select(&select(&[], &projections)).is_ok());
+    }
+
+    #[test]
+    fn test_guarded_mutation() {
+        let policy = SafetyPolicy::new();
+        let delete = MutationShape {
+            kind: "DELETE",
+            table: "users",
+            targets_records: false,
+            has_conditions: false,
+            allow_full_table: false,
+        };
+        assert!(policy.check_mutation(&delete).is_err());
+        assert!(policy
+            .check_mutation(&MutationShape {
+                has_conditions: true,
+                ..delete
+            })
+            .is_ok());
+        assert!(policy
+            .check_mutation(&MutationShape {
+                allow_full_table: true,
+                ..delete
+            })
+            .is_ok());
+        assert!(SafetyPolicy::new()
+            .allow_unguarded_mutations()
+            .check_mutation(&delete)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unconditioned_mutations_do_not_execute() {
+        let err = Query::delete::<User>().execute().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DELETE on users has no conditions and no record target, \
+             use .allow_full_table() to touch every record"
+        );
+        let err = Query::update::<User>()
+            .merge(json!({"active": false}))
+            .unwrap()
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("UPDATE on users has no conditions"));
+        let err = Query::upsert::<User>()
+            .merge(json!({"active": false}))
+            .unwrap()
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("UPSERT on users has no conditions"));
+        let err = Query::delete::<User>()
+            .edge_of("purchased")
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("DELETE on purchased has no conditions"));
+    }
+
+    #[test]
+    fn test_guard_lets_bounded_mutations_through() {
+        let mut delete = Query::delete::<User>()
+            .where_op("active", Operator::Eq, Some(false))
+            .unwrap();
+        assert!(StatementBuilder::build_prepared(&mut delete).is_ok());
+        let mut update = Query::update::<User>()
+            .where_id(SurrealId::new("alice"))
+            .merge(json!({"active": false}))
+            .unwrap();
+        assert!(StatementBuilder::build_prepared(&mut update).is_ok());
+        let mut purge = Query::delete::<User>().allow_full_table();
+        assert_eq!(
+            StatementBuilder::build_prepared(&mut purge).unwrap(),
+            "DELETE users;"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! SurrealQL pretty printer
+//!
+//! [`format_sql`] puts each clause of a statement on its own line, indents
+//! `AND`/`OR` chains, the steps of a transaction and the statements of a block
+//! such as an event body. Keywords are matched in any case. Strings,
+//! subqueries and object literals are kept as they are.
+//!
+//! ```text
+//! SELECT name, email
+//! FROM users
+//! WHERE age > $p0
+//!     AND active = true
+//! ORDER BY name ASC
+//! LIMIT 10;
+//! ```
+
+const INDENT: &str = "    ";
+
+/// Keywords starting a clause on a new line
+const CLAUSES: &[&str] = &[
+    // SELECT and mutations
+    "OMIT", "FROM", "WITH", "WHERE", "SPLIT", "GROUP", "ORDER", "LIMIT", "START", "FETCH",
+    "CONTENT", "SET", "UNSET", "MERGE", "PATCH", "REPLACE", "RETURN", "VERSION", "TIMEOUT",
+    "PARALLEL", "TEMPFILES", "EXPLAIN",
+    // DEFINE
+    "TYPE", "DEFAULT", "VALUE", "ASSERT", "READONLY", "PERMISSIONS", "CHANGEFEED", "COMMENT",
+    "WHEN", "THEN", "FIELDS", "COLUMNS", "UNIQUE", "SEARCH", "MTREE", "HNSW", "SCHEMAFULL",
+    "SCHEMALESS", "DROP",
+];
+
+/// Keywords after which a clause keyword is part of the same clause, e.g.
+/// `SELECT VALUE` or `FLEXIBLE TYPE`, or is a name or value, e.g.
+/// `DEFINE FIELD type` or `WHERE value = 1`
+const NO_BREAK_AFTER: &[&str] = &[
+    "SELECT", "FLEXIBLE", "FOR", "FIELD", "TABLE", "INDEX", "EVENT", "ON", "FROM", "INTO",
+    "CREATE", "UPDATE", "UPSERT", "DELETE", "BY", "AS", "WHERE", "SET", "AND", "OR",
+];
+
+/// Whether the output so far ends an expression, so that a keyword can start
+/// a new clause. After `,`, `=` or another operator the word is a name or a
+/// value, e.g. `SET a = 1, value = 2`.
+fn ends_expression(out: &str) -> bool {
+    out.trim_end().chars().last().map_or(false, |c| {
+        c.is_alphanumeric() || matches!(c, '_' | ')' | ']' | '}' | '\'' | '"' | '`' | '⟩' | '*')
+    })
+}
+
+/// Formats SurrealQL as indented, multi-line text
+pub fn format_sql(sql: &str) -> String {
+    format_statements(sql, 0)
+}
+
+fn format_statements(sql: &str, level: usize) -> String {
+    let mut lines = Vec::new();
+    let mut level = level;
+    for statement in split_statements(sql) {
+        if starts_with_word(statement, "COMMIT") || starts_with_word(statement, "CANCEL") {
+            level = level.saturating_sub(1);
+        }
+        lines.push(format!("{};", format_statement(statement, level)));
+        if starts_with_word(statement, "BEGIN") {
+            level += 1;
+        }
+    }
+    lines.join("\n")
+}
+
+fn starts_with_word(statement: &str, word: &str) -> bool {
+    statement
+        .split_whitespace()
+        .next()
+        .is_some_and(|first| first.eq_ignore_ascii_case(word))
+}
+
+/// Index just past the quoted string starting at `start`
+fn skip_quoted(chars: &[char], start: usize) -> usize {
+    let close = match chars[start] {
+        '⟨' => '⟩',
+        c => c,
+    };
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == close {
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn is_quote(c: char) -> bool {
+    matches!(c, '\'' | '"' | '`' | '⟨')
+}
+
+/// Index just past the bracket matching the one at `start`
+fn skip_group(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            c if is_quote(c) => {
+                i = skip_quoted(chars, i);
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Splits on top-level `;`, dropping empty statements
+fn split_statements(sql: &str) -> Vec<&str> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut start_byte = 0;
+    let mut byte = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let next = match chars[i] {
+            c if is_quote(c) => skip_quoted(&chars, i),
+            '(' | '[' | '{' => skip_group(&chars, i),
+            ';' => {
+                statements.push(&sql[start_byte..byte]);
+                start_byte = byte + 1;
+                i + 1
+            }
+            _ => i + 1,
+        };
+        byte += chars[i..next].iter().map(|c| c.len_utf8()).sum::<usize>();
+        i = next;
+    }
+    statements.push(&sql[start_byte..]);
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Whether a `{ ... }` group is a block of statements rather than an object
+fn is_block(inner: &str) -> bool {
+    split_statements(inner).len() > 1 || inner.trim_end().ends_with(';')
+}
+
+fn format_statement(statement: &str, level: usize) -> String {
+    let indent = INDENT.repeat(level);
+    let chars: Vec<char> = statement.chars().collect();
+    let mut out = indent.clone();
+    let mut previous_word = String::new();
+    let mut clause = String::new();
+    let mut first = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if is_quote(c) {
+            let end = skip_quoted(&chars, i);
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if c == '{' {
+            let end = skip_group(&chars, i);
+            let inner: String = chars[i + 1..end.saturating_sub(1)].iter().collect();
+            if is_block(&inner) {
+                out.push_str("{\n");
+                out.push_str(&format_statements(&inner, level + 1));
+                out.push('\n');
+                out.push_str(&indent);
+                out.push('}');
+            } else {
+                out.extend(&chars[i..end]);
+            }
+            i = end;
+            continue;
+        }
+        if c == '(' || c == '[' {
+            let end = skip_group(&chars, i);
+            out.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | ':'))
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let keyword = word.to_ascii_uppercase();
+            let at_boundary = start == 0 || chars[start - 1].is_whitespace();
+            if !first && at_boundary && ends_expression(&out) {
+                if CLAUSES.contains(&keyword.as_str())
+                    && !NO_BREAK_AFTER.contains(&previous_word.as_str())
+                {
+                    trim_end(&mut out);
+                    out.push('\n');
+                    out.push_str(&indent);
+                    clause = keyword.clone();
+                } else if (keyword == "AND" || keyword == "OR") && clause == "WHERE" {
+                    trim_end(&mut out);
+                    out.push('\n');
+                    out.push_str(&indent);
+                    out.push_str(INDENT);
+                }
+            }
+            out.push_str(&word);
+            previous_word = keyword;
+            first = false;
+            continue;
+        }
+        if c.is_whitespace() {
+            if !out.ends_with(char::is_whitespace) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    trim_end(&mut out);
+    out
+}
+
+fn trim_end(out: &mut String) {
+    let len = out.trim_end_matches([' ', '\t']).len();
+    out.truncate(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_select() {
+        assert_eq!(
+            format_sql(
+                "SELECT VALUE name FROM users WHERE age > $p0 AND email = 'a; b' \
+                 ORDER BY name ASC LIMIT 10;"
+            ),
+            "SELECT VALUE name\nFROM users\nWHERE age > $p0\n    AND email = 'a; b'\n\
+             ORDER BY name ASC\nLIMIT 10;"
+        );
+    }
+
+    #[test]
+    fn test_format_lowercase() {
+        assert_eq!(
+            format_sql("select name, type from users where age > $p0 and value = 1 limit 10;"),
+            "select name, type\nfrom users\nwhere age > $p0\n    and value = 1\nlimit 10;"
+        );
+        assert_eq!(
+            format_sql("DEFINE FIELD type ON TABLE users TYPE string;"),
+            "DEFINE FIELD type ON TABLE users\nTYPE string;"
+        );
+    }
+
+    #[test]
+    fn test_format_define_event() {
+        assert_eq!(
+            format_sql(
+                "DEFINE EVENT audit ON TABLE users WHEN $event = \"UPDATE\" \
+                 THEN { CREATE log SET user = $value.id; RETURN true; };"
+            ),
+            "DEFINE EVENT audit ON TABLE users\nWHEN $event = \"UPDATE\"\nTHEN {\n    \
+             CREATE log\n    SET user = $value.id;\n    RETURN true;\n};"
+        );
+    }
+
+    #[test]
+    fn test_format_transaction() {
+        assert_eq!(
+            format_sql(
+                "BEGIN TRANSACTION; CREATE users CONTENT { name: 'a' }; COMMIT TRANSACTION;"
+            ),
+            "BEGIN TRANSACTION;\n    CREATE users\n    CONTENT { name: 'a' };\nCOMMIT TRANSACTION;"
+        );
+    }
+}
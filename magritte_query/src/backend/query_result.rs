@@ -62,6 +62,26 @@ where
     Dynamic(String), // e.g., "type::Table($Table)"
 }
 
+impl<T> FromTarget<T>
+where
+    T: RecordType,
+{
+    /// Whether the target names records rather than a whole table
+    pub fn is_records(&self) -> bool {
+        matches!(
+            self,
+            FromTarget::Record(_) | FromTarget::RecordList(_) | FromTarget::Range(..)
+        )
+    }
+}
+
+/// Whether a statement's targets all name records
+pub(crate) fn targets_records<T: RecordType>(targets: &Option<Vec<FromTarget<T>>>) -> bool {
+    targets
+        .as_ref()
+        .is_some_and(|t| !t.is_empty() && t.iter().all(FromTarget::is_records))
+}
+
 impl<T> Display for FromTarget<T>
 where
     T: RecordType,
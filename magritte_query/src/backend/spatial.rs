@@ -0,0 +1,321 @@
+//! Geospatial support, behind the `with-geo` feature
+//!
+//! SurrealDB stores geometries as GeoJSON. [`geojson`] converts `geo` types
+//! to and from that representation and doubles as a serde `with` module for
+//! geometry columns. [`SpatialClause`] adds `INSIDE` and `INTERSECTS`
+//! conditions with the geometry bound as a parameter.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! #[derive(Table, Serialize, Deserialize)]
+//! struct Courier {
+//!     #[serde(with = "magritte_query::geojson")]
+//!     position: geo::Point,
+//! }
+//!
+//! let couriers = Query::select::<Courier>()
+//!     .where_inside("position", &zone)?
+//!     .order_by_distance("position", depot, "distance")
+//!     .execute()
+//!     .await?;
+//! ```
+
+use anyhow::Result;
+use geo::{Geometry, Point, Polygon};
+use magritte_core::operator::Operator;
+use magritte_core::{Projection, RecordType};
+
+use crate::{HasConditions, HasParams, SelectStatement, WhereClause};
+
+/// Conversion between `geo` geometries and SurrealDB's GeoJSON values
+pub mod geojson {
+    use anyhow::{anyhow, bail, Result};
+    use geo::{
+        Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint,
+        MultiPolygon, Point, Polygon,
+    };
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::{json, Value};
+
+    /// Converts a geometry to GeoJSON. `Line`, `Rect` and `Triangle` become a
+    /// `LineString` or `Polygon`, which SurrealDB has no dedicated type for.
+    pub fn to_value(geometry: &Geometry) -> Value {
+        match geometry {
+            Geometry::Point(p) => json!({"type": "Point", "coordinates": coord(&p.0)}),
+            Geometry::Line(l) => to_value(&Geometry::LineString(LineString::from(*l))),
+            Geometry::LineString(l) => {
+                json!({"type": "LineString", "coordinates": line(l)})
+            }
+            Geometry::Polygon(p) => json!({"type": "Polygon", "coordinates": polygon(p)}),
+            Geometry::MultiPoint(m) => json!({
+                "type": "MultiPoint",
+                "coordinates": m.iter().map(|p| coord(&p.0)).collect::<Vec<_>>(),
+            }),
+            Geometry::MultiLineString(m) => json!({
+                "type": "MultiLineString",
+                "coordinates": m.iter().map(line).collect::<Vec<_>>(),
+            }),
+            Geometry::MultiPolygon(m) => json!({
+                "type": "MultiPolygon",
+                "coordinates": m.iter().map(polygon).collect::<Vec<_>>(),
+            }),
+            Geometry::GeometryCollection(c) => json!({
+                "type": "GeometryCollection",
+                "geometries": c.iter().map(to_value).collect::<Vec<_>>(),
+            }),
+            Geometry::Rect(r) => to_value(&Geometry::Polygon(r.to_polygon())),
+            Geometry::Triangle(t) => to_value(&Geometry::Polygon(t.to_polygon())),
+        }
+    }
+
+    /// Reads a GeoJSON value returned by SurrealDB
+    pub fn from_value(value: &Value) -> Result<Geometry> {
+        let kind = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Geometry has no type: {}", value))?;
+        if kind == "GeometryCollection" {
+            let geometries = value
+                .get("geometries")
+                .and_then(Value::as_array)
+                .ok_or_else(|| anyhow!("GeometryCollection has no geometries"))?;
+            return Ok(Geometry::GeometryCollection(GeometryCollection(
+                geometries.iter().map(from_value).collect::<Result<_>>()?,
+            )));
+        }
+        let coordinates = value
+            .get("coordinates")
+            .ok_or_else(|| anyhow!("{} has no coordinates", kind))?;
+        Ok(match kind {
+            "Point" => Geometry::Point(Point(read_coord(coordinates)?)),
+            "LineString" => Geometry::LineString(read_line(coordinates)?),
+            "Polygon" => Geometry::Polygon(read_polygon(coordinates)?),
+            "MultiPoint" => Geometry::MultiPoint(MultiPoint(
+                items(coordinates)?
+                    .iter()
+                    .map(|c| read_coord(c).map(Point))
+                    .collect::<Result<_>>()?,
+            )),
+            "MultiLineString" => Geometry::MultiLineString(MultiLineString(
+                items(coordinates)?
+                    .iter()
+                    .map(read_line)
+                    .collect::<Result<_>>()?,
+            )),
+            "MultiPolygon" => Geometry::MultiPolygon(MultiPolygon(
+                items(coordinates)?
+                    .iter()
+                    .map(read_polygon)
+                    .collect::<Result<_>>()?,
+            )),
+            other => bail!("Unsupported geometry type {}", other),
+        })
+    }
+
+    /// Serializes any `geo` geometry as GeoJSON, for
+    /// `#[serde(with = "magritte_query::geojson")]`
+    pub fn serialize<G, S>(geometry: &G, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        G: Clone + Into<Geometry>,
+        S: Serializer,
+    {
+        to_value(&geometry.clone().into()).serialize(serializer)
+    }
+
+    /// Deserializes GeoJSON into the `geo` type of the field
+    pub fn deserialize<'de, G, D>(deserializer: D) -> Result<G, D::Error>
+    where
+        G: TryFrom<Geometry>,
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        let geometry = from_value(&value).map_err(D::Error::custom)?;
+        G::try_from(geometry).map_err(|_| D::Error::custom("Unexpected geometry type"))
+    }
+
+    /// The same bridge for `Option` fields
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<G, S>(geometry: &Option<G>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            G: Clone + Into<Geometry>,
+            S: Serializer,
+        {
+            match geometry {
+                Some(g) => super::serialize(g, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, G, D>(deserializer: D) -> Result<Option<G>, D::Error>
+        where
+            G: TryFrom<Geometry>,
+            D: Deserializer<'de>,
+        {
+            match Option::<Value>::deserialize(deserializer)? {
+                Some(Value::Null) | None => Ok(None),
+                Some(value) => {
+                    let geometry = from_value(&value).map_err(D::Error::custom)?;
+                    G::try_from(geometry)
+                        .map(Some)
+                        .map_err(|_| D::Error::custom("Unexpected geometry type"))
+                }
+            }
+        }
+    }
+
+    fn coord(c: &Coord) -> Value {
+        json!([c.x, c.y])
+    }
+
+    fn line(l: &LineString) -> Value {
+        Value::Array(l.coords().map(coord).collect())
+    }
+
+    fn polygon(p: &Polygon) -> Value {
+        let mut rings = vec![line(p.exterior())];
+        rings.extend(p.interiors().iter().map(line));
+        Value::Array(rings)
+    }
+
+    fn items(value: &Value) -> Result<&Vec<Value>> {
+        value
+            .as_array()
+            .ok_or_else(|| anyhow!("Expected an array of coordinates, got {}", value))
+    }
+
+    fn read_coord(value: &Value) -> Result<Coord> {
+        match items(value)?.as_slice() {
+            [x, y, ..] => Ok(Coord {
+                x: x.as_f64().ok_or_else(|| anyhow!("Invalid longitude {}", x))?,
+                y: y.as_f64().ok_or_else(|| anyhow!("Invalid latitude {}", y))?,
+            }),
+            _ => bail!("Invalid coordinate {}", value),
+        }
+    }
+
+    fn read_line(value: &Value) -> Result<LineString> {
+        Ok(LineString(
+            items(value)?
+                .iter()
+                .map(read_coord)
+                .collect::<Result<_>>()?,
+        ))
+    }
+
+    fn read_polygon(value: &Value) -> Result<Polygon> {
+        let mut rings = items(value)?.iter().map(read_line);
+        let exterior = rings
+            .next()
+            .ok_or_else(|| anyhow!("Polygon has no exterior ring"))??;
+        Ok(Polygon::new(exterior, rings.collect::<Result<_>>()?))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use geo::polygon;
+
+        #[test]
+        fn test_geojson_round_trip() {
+            let point = Geometry::Point(Point::new(13.4, 52.5));
+            assert_eq!(
+                to_value(&point),
+                json!({"type": "Point", "coordinates": [13.4, 52.5]})
+            );
+            assert_eq!(from_value(&to_value(&point)).unwrap(), point);
+
+            let zone = Geometry::Polygon(polygon![
+                (x: 0.0, y: 0.0),
+                (x: 1.0, y: 0.0),
+                (x: 1.0, y: 1.0),
+            ]);
+            let value = to_value(&zone);
+            assert_eq!(value["coordinates"][0].as_array().unwrap().len(), 4);
+            assert_eq!(from_value(&value).unwrap(), zone);
+            assert!(from_value(&json!({"type": "Circle", "coordinates": []})).is_err());
+        }
+    }
+}
+
+/// Spatial WHERE conditions on geometry fields
+pub trait SpatialClause: Sized {
+    /// `field INSIDE $zone`
+    fn where_inside(self, field: &str, zone: &Polygon) -> Result<Self>;
+    /// `field INTERSECTS $geometry`
+    fn where_intersects<G>(self, field: &str, geometry: &G) -> Result<Self>
+    where
+        G: Clone + Into<Geometry>;
+}
+
+impl<T: HasConditions + HasParams> SpatialClause for T {
+    fn where_inside(self, field: &str, zone: &Polygon) -> Result<Self> {
+        let zone = geojson::to_value(&Geometry::Polygon(zone.clone()));
+        self.where_op(field, Operator::Inside, Some(zone))
+    }
+
+    fn where_intersects<G>(self, field: &str, geometry: &G) -> Result<Self>
+    where
+        G: Clone + Into<Geometry>,
+    {
+        let geometry = geojson::to_value(&geometry.clone().into());
+        self.where_op(field, Operator::Intersects, Some(geometry))
+    }
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Orders by distance in meters from `point`, nearest first. The distance
+    /// is selected as `alias`.
+    pub fn order_by_distance(mut self, field: &str, point: Point, alias: &str) -> Self {
+        let name = format!("p{}", self.parameters.len());
+        self.parameters
+            .push((name.clone(), geojson::to_value(&Geometry::Point(point))));
+        if self.selected_fields.is_empty() {
+            self.selected_fields.push(Projection::All);
+        }
+        self.selected_fields.push(Projection::RawAs(
+            format!("geo::distance({}, ${})", field, name),
+            alias.to_string(),
+        ));
+        self.order_by_field(alias, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, Validate};
+    use geo::polygon;
+
+    #[test]
+    fn test_spatial_select() {
+        let zone = polygon![
+            (x: 13.3, y: 52.4),
+            (x: 13.5, y: 52.4),
+            (x: 13.5, y: 52.6),
+        ];
+        let query = Query::select::<User>()
+            .where_inside("address.location", &zone)
+            .unwrap()
+            .order_by_distance("address.location", Point::new(13.4, 52.5), "distance");
+        assert_eq!(
+            query.validate().unwrap(),
+            "SELECT *, geo::distance(address.location, $p1) AS distance FROM users \
+             WHERE address.location INSIDE $p0 ORDER BY distance ASC;"
+        );
+        Query::select::<User>()
+            .where_intersects("area", &zone)
+            .unwrap()
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+}
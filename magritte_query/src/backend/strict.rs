@@ -0,0 +1,287 @@
+//! Strict mode: field names checked against the registered schema
+//!
+//! When enabled, `SelectStatement`, `UpdateStatement`, `UpsertStatement` and
+//! `DeleteStatement` check the fields they reference (projections, conditions,
+//! ordering, grouping and assignments) against the columns registered for the
+//! table, and fail to build with the closest column as a suggestion. Only the
+//! first segment of a path is checked, so `address.city`, `tags[0]` and fields
+//! inside FLEXIBLE objects pass as long as their root column exists. Raw
+//! expressions, functions, parameters and graph steps are not checked, and
+//! neither are tables the provider does not know.
+//!
+//! The schema comes from a provider installed with [`set_schema_provider`];
+//! `magritte::enable_strict_mode()` installs one backed by the registered
+//! tables and edges. [`with_schema_provider`] turns strict mode on for the
+//! current thread only, leaving the global switch alone, which is what tests
+//! running side by side need.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+
+/// Returns the column names of a table, or `None` for unknown tables
+pub type SchemaProvider = fn(&str) -> Option<Vec<String>>;
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+static PROVIDER: OnceLock<SchemaProvider> = OnceLock::new();
+
+thread_local! {
+    static SCOPED: Cell<Option<SchemaProvider>> = const { Cell::new(None) };
+}
+
+/// Fields every record has
+const BUILTIN_FIELDS: &[&str] = &["id", "in", "out"];
+
+/// Installs the schema provider. Only the first call has an effect.
+pub fn set_schema_provider(provider: SchemaProvider) {
+    let _ = PROVIDER.set(provider);
+}
+
+pub fn set_strict_mode(enabled: bool) {
+    STRICT.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_strict_mode() -> bool {
+    STRICT.load(Ordering::Relaxed) || SCOPED.get().is_some()
+}
+
+/// Runs `f` with strict mode on for the current thread, checking against
+/// `provider` instead of the global one
+pub fn with_schema_provider<R>(provider: SchemaProvider, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<SchemaProvider>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.set(self.0);
+        }
+    }
+    let _restore = Restore(SCOPED.replace(Some(provider)));
+    f()
+}
+
+/// The provider in effect: the scoped one, else the global one when strict
+/// mode is on
+fn active_provider() -> Option<SchemaProvider> {
+    SCOPED.get().or_else(|| {
+        STRICT
+            .load(Ordering::Relaxed)
+            .then(|| PROVIDER.get().copied())
+            .flatten()
+    })
+}
+
+/// The column a field path starts with, if the path is a plain field
+fn root_field(field: &str) -> Option<&str> {
+    let field = field.trim();
+    if let Some(rest) = field.strip_prefix('⟨') {
+        let end = rest.find('⟩')?;
+        return Some(&rest[..end]);
+    }
+    let end = field
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(field.len());
+    let (root, rest) = field.split_at(end);
+    let plain_path = rest.is_empty() || rest.starts_with(['.', '[', '?']);
+    let starts_with_letter = root.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+    (starts_with_letter && plain_path).then_some(root)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// The known column closest to `field`, if any is reasonably close
+fn suggest<'a>(field: &str, columns: &'a [String]) -> Option<&'a str> {
+    columns
+        .iter()
+        .map(|c| (edit_distance(field, c), c))
+        .filter(|(distance, c)| *distance <= (c.len().max(field.len()) / 2).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c.as_str())
+}
+
+fn check_against<'a>(
+    table: &str,
+    columns: &[String],
+    fields: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let roots: Vec<String> = columns
+        .iter()
+        .filter_map(|c| root_field(c).map(String::from))
+        .collect();
+    for field in fields {
+        let Some(root) = root_field(field) else {
+            continue;
+        };
+        if BUILTIN_FIELDS.contains(&root) || roots.iter().any(|c| c == root) {
+            continue;
+        }
+        match suggest(root, &roots) {
+            Some(closest) => bail!(
+                "Unknown field `{}` on table {}, did you mean `{}`?",
+                root,
+                table,
+                closest
+            ),
+            None => bail!("Unknown field `{}` on table {}", root, table),
+        }
+    }
+    Ok(())
+}
+
+/// Checks the fields a statement references when strict mode is on
+pub(crate) fn check_fields<'a>(
+    table: &str,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let Some(columns) = active_provider().and_then(|provider| provider(table)) else {
+        return Ok(());
+    };
+    check_against(table, &columns, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Product, User};
+    use crate::{Query, SetClause, SetOp, WhereClause};
+    use magritte_core::operator::Operator;
+
+    #[test]
+    fn test_root_field() {
+        assert_eq!(root_field("address.city"), Some("address"));
+        assert_eq!(root_field("tags[0]"), Some("tags"));
+        assert_eq!(root_field("profile?.kind"), Some("profile"));
+        assert_eq!(root_field("⟨first-name⟩"), Some("first-name"));
+        assert_eq!(root_field("count()"), None);
+        assert_eq!(root_field("math::sum(total)"), None);
+        assert_eq!(root_field("$param"), None);
+        assert_eq!(root_field("->purchased->products"), None);
+        assert_eq!(root_field("name IN (SELECT 1)"), None);
+    }
+
+    #[test]
+    fn test_check_against() {
+        let columns = vec!["name".to_string(), "email".to_string(), "address".to_string()];
+        assert!(check_against("users", &columns, ["name", "address.city", "id"]).is_ok());
+        let err = check_against("users", &columns, ["nmae"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `nmae` on table users, did you mean `name`?"
+        );
+        let err = check_against("users", &columns, ["zzzzzz"]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown field `zzzzzz` on table users");
+    }
+
+    fn schema(table: &str) -> Option<Vec<String>> {
+        match table {
+            "users" => Some(
+                ["name", "email", "address", "metadata"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_scoped_provider() {
+        assert!(!is_strict_mode());
+        with_schema_provider(schema, || {
+            assert!(is_strict_mode());
+            assert!(check_fields("users", ["nmae"]).is_err());
+            assert!(check_fields("products", ["anything"]).is_ok());
+        });
+        assert!(!is_strict_mode());
+        assert!(check_fields("users", ["nmae"]).is_ok());
+    }
+
+    #[test]
+    fn test_strict_select() {
+        with_schema_provider(schema, || {
+            let query = Query::select::<User>()
+                .field("address.city", None)
+                .where_op("metadata.plan.tier", Operator::Eq, Some("pro"))
+                .unwrap()
+                .order_by_field("name", true);
+            assert!(query.build().is_ok());
+
+            let err = Query::select::<User>()
+                .fields(&["nmae"])
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Unknown field `nmae` on table users, did you mean `name`?"
+            );
+            // Tables without a schema are not checked
+            assert!(Query::select::<Product>().fields(&["nmae"]).build().is_ok());
+        });
+    }
+
+    #[test]
+    fn test_strict_update() {
+        with_schema_provider(schema, || {
+            let query = Query::update::<User>()
+                .set_path("address.city", SetOp::Assign, "Paris")
+                .unwrap()
+                .set_path("metadata.tags[0]", SetOp::Assign, "new")
+                .unwrap()
+                .where_op("email", Operator::Eq, Some("a@b.c"))
+                .unwrap();
+            assert!(query.build().is_ok());
+
+            let err = Query::update::<User>()
+                .set_path("emial", SetOp::Assign, "a@b.c")
+                .unwrap()
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Unknown field `emial` on table users, did you mean `email`?"
+            );
+        });
+    }
+
+    #[test]
+    fn test_strict_where_clause() {
+        with_schema_provider(schema, || {
+            let err = Query::delete::<User>()
+                .where_op("adress.city", Operator::Eq, Some("Paris"))
+                .unwrap()
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Unknown field `adress` on table users, did you mean `address`?"
+            );
+            let err = Query::select::<User>()
+                .where_op("zzzzzz", Operator::Eq, Some(1))
+                .unwrap()
+                .build()
+                .unwrap_err();
+            assert_eq!(err.to_string(), "Unknown field `zzzzzz` on table users");
+            // Raw conditions are left alone
+            let query = Query::select::<User>()
+                .where_op("zzzzzz IS NONE", Operator::Raw, None::<()>)
+                .unwrap();
+            assert!(query.build().is_ok());
+        });
+    }
+}
@@ -0,0 +1,196 @@
+//! Typed datetimes for `VERSION` clauses
+//!
+//! [`IntoTimestamp`] turns `SystemTime`, RFC 3339 strings and, with the
+//! `with-chrono` and `with-time` features, `chrono` and `time` datetimes into
+//! the UTC RFC 3339 text SurrealDB expects inside a `d'...'` literal. Strings
+//! are rejected unless they are RFC 3339 datetimes, so they cannot break out
+//! of the literal.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+
+pub trait IntoTimestamp {
+    /// RFC 3339 representation in UTC
+    fn into_timestamp(self) -> Result<String>;
+}
+
+impl IntoTimestamp for &str {
+    fn into_timestamp(self) -> Result<String> {
+        if !is_rfc3339(self) {
+            bail!("Invalid RFC 3339 datetime: {:?}", self);
+        }
+        Ok(self.to_string())
+    }
+}
+
+impl IntoTimestamp for String {
+    fn into_timestamp(self) -> Result<String> {
+        self.as_str().into_timestamp()
+    }
+}
+
+impl IntoTimestamp for &String {
+    fn into_timestamp(self) -> Result<String> {
+        self.as_str().into_timestamp()
+    }
+}
+
+/// Checks `YYYY-MM-DDTHH:MM:SS[.fraction](Z|+HH:MM|-HH:MM)`
+fn is_rfc3339(value: &str) -> bool {
+    fn number(part: &[u8], max: u32) -> bool {
+        part.iter().all(u8::is_ascii_digit)
+            && part.iter().fold(0, |n, d| n * 10 + u32::from(d - b'0')) <= max
+    }
+
+    let bytes = value.as_bytes();
+    if bytes.len() < 20 {
+        return false;
+    }
+    let date_time = number(&bytes[0..4], 9999)
+        && bytes[4] == b'-'
+        && number(&bytes[5..7], 12)
+        && bytes[7] == b'-'
+        && number(&bytes[8..10], 31)
+        && matches!(bytes[10], b'T' | b't')
+        && number(&bytes[11..13], 23)
+        && bytes[13] == b':'
+        && number(&bytes[14..16], 59)
+        && bytes[16] == b':'
+        && number(&bytes[17..19], 60);
+    if !date_time {
+        return false;
+    }
+    let mut rest = &bytes[19..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let digits = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return false;
+        }
+        rest = &fraction[digits..];
+    }
+    match rest {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => number(&[*h1, *h2], 23) && number(&[*m1, *m2], 59),
+        _ => false,
+    }
+}
+
+impl IntoTimestamp for SystemTime {
+    fn into_timestamp(self) -> Result<String> {
+        let (secs, nanos) = match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                match d.subsec_nanos() {
+                    0 => (-(d.as_secs() as i64), 0),
+                    n => (-(d.as_secs() as i64) - 1, 1_000_000_000 - n),
+                }
+            }
+        };
+        let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+        let (year, month, day) = civil_from_days(days);
+        let mut out = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            rem / 3600,
+            rem % 3600 / 60,
+            rem % 60
+        );
+        if nanos > 0 {
+            let fraction = format!("{:09}", nanos);
+            out.push('.');
+            out.push_str(fraction.trim_end_matches('0'));
+        }
+        out.push('Z');
+        Ok(out)
+    }
+}
+
+/// Days since 1970-01-01 to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(feature = "with-chrono")]
+impl<Tz: chrono::TimeZone> IntoTimestamp for chrono::DateTime<Tz> {
+    fn into_timestamp(self) -> Result<String> {
+        Ok(self
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+    }
+}
+
+#[cfg(feature = "with-chrono")]
+impl<Tz: chrono::TimeZone> IntoTimestamp for &chrono::DateTime<Tz> {
+    fn into_timestamp(self) -> Result<String> {
+        self.clone().into_timestamp()
+    }
+}
+
+#[cfg(feature = "with-time")]
+impl IntoTimestamp for time::OffsetDateTime {
+    fn into_timestamp(self) -> Result<String> {
+        // Fails for years outside 0..=9999, which RFC 3339 cannot represent
+        Ok(self
+            .to_offset(time::UtcOffset::UTC)
+            .format(&time::format_description::well_known::Rfc3339)?)
+    }
+}
+
+/// Renders a timestamp as a SurrealQL datetime literal, e.g.
+/// `d'2024-01-01T00:00:00Z'`
+pub(crate) fn datetime_literal(at: impl IntoTimestamp) -> Result<String> {
+    Ok(format!("d'{}'", at.into_timestamp()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, Validate};
+    use std::time::Duration;
+
+    #[test]
+    fn test_system_time_timestamp() {
+        assert_eq!(UNIX_EPOCH.into_timestamp().unwrap(), "1970-01-01T00:00:00Z");
+        let at = UNIX_EPOCH + Duration::from_millis(1_704_164_645_500);
+        assert_eq!(at.into_timestamp().unwrap(), "2024-01-02T03:04:05.5Z");
+        let before = UNIX_EPOCH - Duration::from_millis(500);
+        assert_eq!(before.into_timestamp().unwrap(), "1969-12-31T23:59:59.5Z");
+        assert_eq!(
+            datetime_literal("2024-01-01T00:00:00Z").unwrap(),
+            "d'2024-01-01T00:00:00Z'"
+        );
+    }
+
+    #[test]
+    fn test_rfc3339_strings() {
+        assert!(is_rfc3339("2024-01-01T00:00:00Z"));
+        assert!(is_rfc3339("2024-01-01t12:30:00.123456z"));
+        assert!(is_rfc3339("2024-01-01T12:30:00+02:00"));
+        assert!(!is_rfc3339("2024-01-01"));
+        assert!(!is_rfc3339("2024-13-01T00:00:00Z"));
+        assert!(!is_rfc3339("2024-01-01T00:00:00."));
+        assert!(!is_rfc3339("2024-01-01T00:00:00Z'; DELETE users; --"));
+        assert!(datetime_literal("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_select_as_of() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+        let sql = Query::select::<User>().as_of(at).unwrap().validate().unwrap();
+        assert_eq!(sql, "SELECT * FROM users VERSION d'2024-01-01T00:00:00Z';");
+    }
+}
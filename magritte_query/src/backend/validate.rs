@@ -0,0 +1,262 @@
+//! Opt-in validation of generated SurrealQL.
+//!
+//! Builders only assemble strings, so a malformed clause is normally not
+//! noticed until the server rejects the query. [`Validate::validate`] runs the
+//! output of any [`StatementBuilder`] through the SurrealDB parser and reports
+//! the position and clause of the first error.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let sql = Query::select::<User>()
+//!     .where_op("age", Operator::Gt, Some(18))?
+//!     .validate()?;
+//! ```
+
+use std::fmt::{self, Display};
+
+use crate::StatementBuilder;
+
+/// Clause keywords in the order they are searched for, longest first so that
+/// `GROUP BY` wins over `GROUP`.
+const CLAUSES: &[&str] = &[
+    "ON DUPLICATE KEY UPDATE",
+    "WITH NOINDEX",
+    "WITH INDEX",
+    "GROUP ALL",
+    "GROUP BY",
+    "ORDER BY",
+    "START AT",
+    "PERMISSIONS",
+    "TEMPFILES",
+    "CHANGEFEED",
+    "PARALLEL",
+    "TIMEOUT",
+    "VERSION",
+    "EXPLAIN",
+    "CONTENT",
+    "COMMENT",
+    "REPLACE",
+    "RETURN",
+    "SELECT",
+    "CREATE",
+    "UPDATE",
+    "UPSERT",
+    "DELETE",
+    "INSERT",
+    "RELATE",
+    "DEFINE",
+    "ALTER",
+    "WHERE",
+    "LIMIT",
+    "SPLIT",
+    "FETCH",
+    "MERGE",
+    "PATCH",
+    "UNSET",
+    "THROW",
+    "FROM",
+    "OMIT",
+    "INTO",
+    "THEN",
+    "ELSE",
+    "WHEN",
+    "SET",
+    "LET",
+    "FOR",
+    "END",
+    "IF",
+];
+
+/// A query rejected by the SurrealDB parser
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// The query that failed to parse
+    pub query: String,
+    /// The parser message
+    pub message: String,
+    /// 1-based line of the error, when reported by the parser
+    pub line: Option<usize>,
+    /// 1-based column of the error, when reported by the parser
+    pub column: Option<usize>,
+    /// The clause containing the error, e.g. `ORDER BY`
+    pub clause: Option<String>,
+}
+
+impl ValidationError {
+    fn from_parse_error(query: &str, error: impl Display) -> Self {
+        let message = error.to_string();
+        let position = parse_position(&message);
+        let clause = position
+            .and_then(|(line, column)| byte_offset(query, line, column))
+            .and_then(|offset| clause_at(query, offset));
+
+        Self {
+            query: query.to_string(),
+            message: message.lines().next().unwrap_or_default().to_string(),
+            line: position.map(|(l, _)| l),
+            column: position.map(|(_, c)| c),
+            clause,
+        }
+    }
+
+    fn from_build_error(error: anyhow::Error) -> Self {
+        Self {
+            query: String::new(),
+            message: error.to_string(),
+            line: None,
+            column: None,
+            clause: None,
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid SurrealQL")?;
+        if let Some(clause) = &self.clause {
+            write!(f, " in {} clause", clause)?;
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at {}:{}", line, column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if !self.query.is_empty() {
+            write!(f, "\n  query: {}", self.query)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Parses `query` with the SurrealDB parser.
+pub fn validate_sql(query: &str) -> Result<(), ValidationError> {
+    surrealdb::sql::parse(query)
+        .map(|_| ())
+        .map_err(|e| ValidationError::from_parse_error(query, e))
+}
+
+/// Validation of builder output against the SurrealDB parser
+pub trait Validate {
+    /// Builds the statement and checks that SurrealDB can parse it,
+    /// returning the query on success.
+    fn validate(&self) -> Result<String, ValidationError>;
+}
+
+impl<S: StatementBuilder> Validate for S {
+    fn validate(&self) -> Result<String, ValidationError> {
+        let query = self.build().map_err(ValidationError::from_build_error)?;
+        validate_sql(&query)?;
+        Ok(query)
+    }
+}
+
+/// Extracts `line:column` from a parser message (` --> [1:23]`).
+fn parse_position(message: &str) -> Option<(usize, usize)> {
+    let start = message.find("--> [")? + 5;
+    let end = start + message[start..].find(']')?;
+    let (line, column) = message[start..end].split_once(':')?;
+    Some((line.trim().parse().ok()?, column.trim().parse().ok()?))
+}
+
+fn byte_offset(query: &str, line: usize, column: usize) -> Option<usize> {
+    let mut offset = 0;
+    for (i, l) in query.split_inclusive('\n').enumerate() {
+        if i + 1 == line {
+            let col = l
+                .char_indices()
+                .nth(column.saturating_sub(1))
+                .map(|(b, _)| b)
+                .unwrap_or(l.len());
+            return Some(offset + col);
+        }
+        offset += l.len();
+    }
+    None
+}
+
+/// Finds the last clause keyword starting at or before `offset`, ignoring
+/// string literals.
+fn clause_at(query: &str, offset: usize) -> Option<String> {
+    let bytes = query.as_bytes();
+    let limit = offset.min(query.len());
+    let mut found: Option<(usize, &str)> = None;
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+
+    while i <= limit && i < bytes.len() {
+        let b = bytes[i];
+        if let Some(q) = quote {
+            if b == b'\\' {
+                i += 1;
+            } else if b == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        if b == b'"' || b == b'\'' {
+            quote = Some(b);
+            i += 1;
+            continue;
+        }
+        let at_boundary = i == 0 || !is_ident(bytes[i - 1]);
+        if at_boundary {
+            if let Some(clause) = CLAUSES.iter().find(|c| {
+                query[i..].starts_with(**c)
+                    && query[i + c.len()..]
+                        .bytes()
+                        .next()
+                        .map_or(true, |n| !is_ident(n))
+            }) {
+                found = Some((i, clause));
+                i += clause.len();
+                continue;
+            }
+        }
+        i += 1;
+    }
+
+    found.map(|(_, c)| c.to_string())
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_query() {
+        assert!(validate_sql("SELECT * FROM users WHERE age > $p0 LIMIT 10;").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_query_points_to_clause() {
+        let err = validate_sql("SELECT * FROM users ORDER BY age ASC WHERE name = 'x';")
+            .unwrap_err();
+        assert_eq!(err.line, Some(1));
+        assert_eq!(err.clause.as_deref(), Some("WHERE"));
+    }
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(
+            parse_position("Parse error: Unexpected token\n --> [1:38]\n  |"),
+            Some((1, 38))
+        );
+        assert_eq!(parse_position("no position"), None);
+    }
+
+    #[test]
+    fn test_clause_at_skips_strings() {
+        let query = "SELECT * FROM users WHERE name = 'ORDER BY' LIMIT 1";
+        assert_eq!(clause_at(query, query.len()).as_deref(), Some("LIMIT"));
+        assert_eq!(clause_at(query, 40).as_deref(), Some("WHERE"));
+    }
+}
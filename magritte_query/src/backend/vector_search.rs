@@ -1,9 +1,22 @@
-use crate::{CanCallFunctions, HasVectorConditions, VectorFunction};
-use magritte_core::{Indexable, VectorCondition, VectorDistance};
-
-/// Extension trait for vector search functionality
+use crate::{
+    CanCallFunctions, HasConditions, HasParams, HasProjections, HasVectorConditions,
+    VectorFunction,
+};
+use magritte_core::operator::Operator;
+use magritte_core::value::SqlValue;
+use magritte_core::{Indexable, KnnSearch, Projection, VectorCondition, VectorDistance};
+use serde_json::Value;
+
+/// Extension trait for vector search functionality.
+///
+/// Query vectors are bound as parameters and every condition is added to the
+/// WHERE clause, so vector searches combine with regular filters.
 pub trait VectorSearchable {
-    /// Perform nearest neighbor search using vector similarity
+    /// Native KNN search, `field <|k|> $vec`, `field <|k, ef|> $vec` or
+    /// `field <|k, DISTANCE|> $vec` depending on `search`
+    fn vector_knn(self, field: &str, vector: Vec<f32>, k: usize, search: KnnSearch) -> Self;
+
+    /// Perform brute force nearest neighbor search, `field <|k, DISTANCE|> $vec`
     fn vector_nearest(
         self,
         field: &str,
@@ -12,7 +25,9 @@ pub trait VectorSearchable {
         operator: VectorDistance,
     ) -> Self;
 
-    /// Perform similarity search with optional threshold
+    /// Perform similarity search with optional threshold. Cosine keeps records
+    /// at least `threshold` similar, other distances keep records at most
+    /// `threshold` away.
     fn vector_similarity(
         self,
         field: &str,
@@ -44,108 +59,70 @@ pub trait VectorSearchable {
     /// Get KNN distance in SELECT (requires prior KNN search)
     fn vector_knn_distance(self) -> Self;
 
+    /// Selects `vector::distance::knn() AS alias`, alongside all fields when
+    /// nothing else is selected
+    fn vector_knn_distance_as(self, alias: &str) -> Self;
+
     /// Define vector index hint
     fn with_vector_index(self, index_name: &str) -> Self;
+}
 
-    fn build_vector_conditions(conditions: &[VectorCondition]) -> String {
-        let mut query = String::new();
+/// Binds `vector` as the next parameter and returns its placeholder
+fn bind_vector<U: HasParams>(statement: &mut U, vector: &[f32]) -> String {
+    let params = statement.params_mut();
+    let name = format!("p{}", params.len());
+    params.push((name.clone(), Value::from(vector.to_vec())));
+    format!("${}", name)
+}
 
-        for condition in conditions {
-            match condition {
-                VectorCondition::Similarity {
-                    field,
-                    vector,
-                    distance: operator,
-                    threshold,
-                } => {
-                    query.push_str(&format!(
-                        " WHERE vector::similarity::{}({}, {}) ",
-                        operator.to_string().to_lowercase(),
-                        field,
-                        vector
-                            .iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<_>>()
-                            .join(",")
-                    ));
-                    if let Some(t) = threshold {
-                        query.push_str(&format!(">= {}", t));
-                    }
-                }
-                VectorCondition::Nearest {
-                    field,
-                    vector,
-                    k,
-                    distance: operator,
-                } => {
-                    query.push_str(&format!(
-                        " ORDER BY vector::similarity::{}({}, {}) DESC LIMIT {}",
-                        operator.to_string().to_lowercase(),
-                        field,
-                        vector
-                            .iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<_>>()
-                            .join(","),
-                        k
-                    ));
-                }
-                VectorCondition::BatchSimilarity { conditions } => {
-                    for (field, vector, operator, threshold) in conditions {
-                        query.push_str(&format!(
-                            " WHERE vector::similarity::{}({}, {}) ",
-                            operator.to_string().to_lowercase(),
-                            field,
-                            vector
-                                .iter()
-                                .map(|x| x.to_string())
-                                .collect::<Vec<_>>()
-                                .join(",")
-                        ));
-                        if let Some(t) = threshold {
-                            query.push_str(&format!(">= {}", t));
-                        }
-                    }
-                }
-                VectorCondition::BatchNearest { conditions } => {
-                    for (field, vector, operator, k) in conditions {
-                        query.push_str(&format!(
-                            " ORDER BY vector::similarity::{}({}, {}) DESC LIMIT {}",
-                            operator.to_string().to_lowercase(),
-                            field,
-                            vector
-                                .iter()
-                                .map(|x| x.to_string())
-                                .collect::<Vec<_>>()
-                                .join(","),
-                            k
-                        ));
-                    }
-                }
-            }
-        }
-        query
+fn vector_function(field: &str, vector: String, distance: VectorDistance) -> VectorFunction {
+    let field = field.to_string();
+    match distance {
+        VectorDistance::Cosine => VectorFunction::SimilarityCosine(field, vector),
+        VectorDistance::Euclidean => VectorFunction::DistanceEuclidean(field, vector),
+        VectorDistance::Manhattan => VectorFunction::DistanceManhattan(field, vector),
+        VectorDistance::Hamming => VectorFunction::DistanceHamming(field, vector),
+        VectorDistance::Chebyshev => VectorFunction::DistanceChebyshev(field, vector),
+        VectorDistance::Minkowski(p) => VectorFunction::DistanceMinkowski(field, vector, p),
     }
 }
 
-impl<U: HasVectorConditions + CanCallFunctions + Indexable> VectorSearchable for U {
-    fn vector_nearest(
-        mut self,
-        field: &str,
-        vector: Vec<f32>,
-        k: usize,
-        operator: VectorDistance,
-    ) -> Self {
+impl<U> VectorSearchable for U
+where
+    U: HasVectorConditions
+        + HasConditions
+        + HasParams
+        + HasProjections
+        + CanCallFunctions
+        + Indexable,
+{
+    fn vector_knn(mut self, field: &str, vector: Vec<f32>, k: usize, search: KnnSearch) -> Self {
+        let param = bind_vector(&mut self, &vector);
+        self.conditions_mut().push((
+            format!("{} {} {}", field, search.operator(k), param),
+            Operator::Raw,
+            SqlValue::Null,
+        ));
         self.get_vector_conditions_mut()
-            .push(VectorCondition::Nearest {
+            .push(VectorCondition::Knn {
                 field: field.to_string(),
                 vector,
                 k,
-                distance: operator,
+                search,
             });
         self
     }
 
+    fn vector_nearest(
+        self,
+        field: &str,
+        vector: Vec<f32>,
+        k: usize,
+        operator: VectorDistance,
+    ) -> Self {
+        self.vector_knn(field, vector, k, KnnSearch::Distance(operator))
+    }
+
     fn vector_similarity(
         mut self,
         field: &str,
@@ -153,6 +130,15 @@ impl<U: HasVectorConditions + CanCallFunctions + Indexable> VectorSearchable for
         operator: VectorDistance,
         threshold: Option<f32>,
     ) -> Self {
+        let param = bind_vector(&mut self, &vector);
+        let function = vector_function(field, param, operator);
+        let condition = match (threshold, operator) {
+            (Some(t), VectorDistance::Cosine) => format!("{} >= {}", function, t),
+            (Some(t), _) => format!("{} <= {}", function, t),
+            (None, _) => function.to_string(),
+        };
+        self.conditions_mut()
+            .push((condition, Operator::Raw, SqlValue::Null));
         self.get_vector_conditions_mut()
             .push(VectorCondition::Similarity {
                 field: field.to_string(),
@@ -167,8 +153,9 @@ impl<U: HasVectorConditions + CanCallFunctions + Indexable> VectorSearchable for
         mut self,
         conditions: Vec<(String, Vec<f32>, VectorDistance, usize)>,
     ) -> Self {
-        self.get_vector_conditions_mut()
-            .push(VectorCondition::BatchNearest { conditions });
+        for (field, vector, operator, k) in conditions {
+            self = self.vector_nearest(&field, vector, k, operator);
+        }
         self
     }
 
@@ -176,96 +163,89 @@ impl<U: HasVectorConditions + CanCallFunctions + Indexable> VectorSearchable for
         mut self,
         conditions: Vec<(String, Vec<f32>, VectorDistance, Option<f32>)>,
     ) -> Self {
-        self.get_vector_conditions_mut()
-            .push(VectorCondition::BatchSimilarity { conditions });
+        for (field, vector, operator, threshold) in conditions {
+            self = self.vector_similarity(&field, vector, operator, threshold);
+        }
         self
     }
 
     fn vector_similarity_score(
-        self,
+        mut self,
         field: &str,
         vector: Vec<f32>,
         operator: VectorDistance,
     ) -> Self {
-        match operator {
-            VectorDistance::Cosine => self.call_function(VectorFunction::SimilarityCosine(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            )),
-            VectorDistance::Euclidean => self.call_function(VectorFunction::DistanceEuclidean(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            )),
-            VectorDistance::Manhattan => self.call_function(VectorFunction::DistanceManhattan(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            )),
-            VectorDistance::Hamming => self.call_function(VectorFunction::DistanceHamming(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            )),
-            VectorDistance::Chebyshev => self.call_function(VectorFunction::DistanceChebyshev(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            )),
-            VectorDistance::Minkowski(p) => self.call_function(VectorFunction::DistanceMinkowski(
-                field.to_string(),
-                format!(
-                    "[{}]",
-                    vector
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-                p,
-            )),
-        }
+        let param = bind_vector(&mut self, &vector);
+        self.call_function(vector_function(field, param, operator))
     }
 
     fn vector_knn_distance(self) -> Self {
         self.call_function(VectorFunction::DistanceKnn)
     }
 
-    fn with_vector_index(mut self, index_name: &str) -> Self {
-        if let Some(indexes) = self.with_index_mut() {
-            indexes.push(index_name.to_string());
+    fn vector_knn_distance_as(mut self, alias: &str) -> Self {
+        let projections = self.projections_mut();
+        if projections.is_empty() {
+            projections.push(Projection::All);
         }
+        projections.push(Projection::RawAs(
+            VectorFunction::DistanceKnn.to_string(),
+            alias.to_string(),
+        ));
         self
     }
+
+    fn with_vector_index(mut self, index_name: &str) -> Self {
+        self.with_index_mut()
+            .get_or_insert_with(Vec::new)
+            .push(index_name.to_string());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Product;
+    use crate::{Query, StatementBuilder, Validate, WhereClause};
+    use serde_json::json;
+
+    #[test]
+    fn test_knn_binds_query_vector() {
+        let query = Query::select::<Product>()
+            .where_op("price", Operator::Lt, Some(100))
+            .unwrap()
+            .vector_knn("embedding", vec![0.1, 0.2, 0.3], 10, KnnSearch::Ef(40))
+            .vector_knn_distance_as("distance");
+        assert_eq!(query.with_params()[1].1, json!([0.1f32, 0.2f32, 0.3f32]));
+        assert_eq!(
+            query.validate().unwrap(),
+            "SELECT *, vector::distance::knn() AS distance FROM products \
+             WHERE price < $p0 AND embedding <|10,40|> $p1;"
+        );
+    }
+
+    #[test]
+    fn test_nearest_parses() {
+        let query = Query::select::<Product>()
+            .vector_nearest("embedding", vec![1.0, 0.0], 5, VectorDistance::Euclidean)
+            .limit(5);
+        query.validate().unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_similarity_joins_the_where_clause() {
+        let sql = Query::select::<Product>()
+            .where_op("quantity", Operator::Gt, Some(0))
+            .unwrap()
+            .vector_similarity("embedding", vec![1.0, 0.0], VectorDistance::Cosine, Some(0.8))
+            .fetch(&["owner"])
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM products WHERE quantity > $p0 \
+             AND vector::similarity::cosine(embedding, $p1) >= 0.8 FETCH owner;"
+        );
+    }
 }
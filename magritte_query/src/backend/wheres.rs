@@ -1,4 +1,5 @@
 use crate::expr::{HasConditions, HasParams};
+use crate::idiom::IntoIdiom;
 use crate::{Callable, SelectStatement};
 use anyhow::anyhow;
 use magritte_core::operator::Operator;
@@ -10,7 +11,7 @@ use tracing::instrument;
 pub trait WhereClause: Sized {
     fn where_op<V: Serialize>(
         self,
-        field: &str,
+        field: impl IntoIdiom,
         op: Operator,
         value: Option<V>,
     ) -> anyhow::Result<Self>;
@@ -18,27 +19,33 @@ pub trait WhereClause: Sized {
     where
         U: RecordType;
     fn where_function<F: Callable>(self, func: F) -> anyhow::Result<Self>;
+    /// Full-text match against a SEARCH index, `field @N@ $p`. The predicate
+    /// reference `N` ties the match to `search::score(N)` and
+    /// `search::highlight(.., N)`.
+    fn where_matches(self, field: &str, text: &str, predicate_ref: Option<u8>)
+        -> anyhow::Result<Self>;
 }
 
 impl<T: HasConditions + HasParams> WhereClause for T {
     /// Add a WHERE condition with an operator
-    #[instrument(skip(self, value))]
+    #[instrument(skip_all, fields(op = ?op))]
     fn where_op<V: Serialize>(
         mut self,
-        field: &str,
+        field: impl IntoIdiom,
         op: Operator,
         value: Option<V>,
     ) -> anyhow::Result<Self> {
+        let field = field.into_idiom().bind(self.params_mut());
         if let Some(value) = value {
             let len = self.params_mut().len();
             let param_name = format!("p{}", len);
             self.params_mut()
                 .push((param_name.clone(), serde_json::to_value(value)?));
             self.conditions_mut()
-                .push((field.to_string(), op, SqlValue::Param(param_name)));
+                .push((field, op, SqlValue::Param(param_name)));
         } else {
             self.conditions_mut()
-                .push((field.to_string(), op, SqlValue::Null));
+                .push((field, op, SqlValue::Null));
         }
         Ok(self)
     }
@@ -49,7 +56,8 @@ impl<T: HasConditions + HasParams> WhereClause for T {
     where
         U: RecordType,
     {
-        let subquery_str = format!("{} IN ({})", field, subquery.build()?);
+        let subquery = subquery.build()?;
+        let subquery_str = format!("{} IN ({})", field, subquery.trim_end_matches(';'));
         self.conditions_mut()
             .push((subquery_str, Operator::Raw, SqlValue::Null));
         Ok(self)
@@ -65,4 +73,15 @@ impl<T: HasConditions + HasParams> WhereClause for T {
             .push((func.to_string(), Operator::Raw, SqlValue::Null));
         Ok(self)
     }
+
+    /// Add a full-text WHERE condition
+    #[instrument(skip(self))]
+    fn where_matches(
+        self,
+        field: &str,
+        text: &str,
+        predicate_ref: Option<u8>,
+    ) -> anyhow::Result<Self> {
+        self.where_op(field, Operator::Matches(predicate_ref), Some(text))
+    }
 }
@@ -28,6 +28,8 @@
 //! - Selected namespace and database before using the statement
 //! - Note: Events are not triggered during data import operations
 
+use crate::backend::params::inline_params;
+use crate::{Block, StatementBuilder};
 use magritte_db::{db, QueryType, SurrealDB};
 use anyhow::{anyhow, bail};
 use std::fmt::Display;
@@ -109,6 +111,16 @@ impl DefineEventStatement {
         self
     }
 
+    /// Sets the THEN action from a [`Block`] of statement builders.
+    ///
+    /// Unlike [`then`](Self::then) the body is used as-is, with bound
+    /// parameters inlined as literals.
+    pub fn then_block(mut self, block: Block) -> anyhow::Result<Self> {
+        let body = block.build_body()?;
+        self.then = Some(inline_params(&body, &block.with_params()));
+        Ok(self)
+    }
+
     /// Sets the OVERWRITE clause
     pub fn overwrite(mut self) -> Self {
         self.overwrite = true;
@@ -401,4 +413,28 @@ mod tests {
             .build();
         assert!(stmt.is_err());
     }
+
+    #[test]
+    fn test_event_with_block() {
+        use crate::IfStatement;
+
+        let stmt = DefineEventStatement::new()
+            .name("stock_check")
+            .table("product")
+            .event_type(EventType::Update)
+            .then_block(
+                Block::new().then(
+                    IfStatement::new("$after.stock < 0").then_block(
+                        Block::new().raw("CREATE alert SET product = $after.id, kind = 'negative_stock'"),
+                    ),
+                ),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            stmt,
+            "DEFINE EVENT stock_check ON TABLE product WHEN $event = \"UPDATE\" THEN { IF $after.stock < 0 THEN { CREATE alert SET product = $after.id, kind = 'negative_stock'; } END; };"
+        );
+    }
 }
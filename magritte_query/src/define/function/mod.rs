@@ -32,6 +32,8 @@
 //! - Authentication as root owner/editor, namespace owner/editor, or database owner/editor
 //! - Selected namespace and database before using the statement
 
+use crate::backend::params::inline_params;
+use crate::{Block, StatementBuilder};
 use anyhow::{anyhow, bail};
 use std::fmt::Display;
 use tracing::{error, info};
@@ -152,6 +154,16 @@ impl DefineFunctionStatement {
         self
     }
 
+    /// Sets the function body from a [`Block`] of statement builders.
+    ///
+    /// Parameters bound by the statements are inlined as literals, since a
+    /// function body is stored and cannot carry bindings.
+    pub fn body(mut self, body: Block) -> anyhow::Result<Self> {
+        let query = body.build_body()?;
+        self.query = Some(inline_params(&query, &body.with_params()));
+        Ok(self)
+    }
+
     /// Sets the function permissions
     pub fn permissions(mut self, permissions: FnPermission) -> Self {
         self.permissions = Some(permissions);
@@ -371,6 +383,31 @@ mod tests {
         assert!(stmt.contains("DEFINE FUNCTION IF NOT EXISTS fn::greet"));
     }
 
+    #[test]
+    fn test_function_with_block_body() {
+        use crate::{IfStatement, ReturnStatement, ThrowStatement};
+
+        let stmt = DefineFunctionStatement::new()
+            .name("check_total")
+            .arg(FunctionArg::new("items", "array"))
+            .body(
+                Block::new()
+                    .let_expr("total", "array::len($items)")
+                    .then(
+                        IfStatement::new("$total = 0")
+                            .then(ThrowStatement::new("no items"))
+                            .else_(ReturnStatement::value(true).unwrap()),
+                    ),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            stmt,
+            "DEFINE FUNCTION fn::check_total($items: array) {\nLET $total = array::len($items); IF $total = 0 THEN { THROW \"no items\"; } ELSE { RETURN true; } END;\n};"
+        );
+    }
+
     #[test]
     fn test_missing_name() {
         let stmt = DefineFunctionStatement::new()
@@ -22,6 +22,7 @@ pub use event::*;
 pub use field::*;
 pub use function::*;
 pub use index::*;
+use crate::{IndexBuilder, StatementBuilder};
 use magritte_core::{EdgeType, TableType};
 pub use namespace::*;
 pub use param::*;
@@ -56,6 +57,11 @@ impl Define {
     pub fn index() -> DefineIndexStatement {
         DefineIndexStatement::new()
     }
+    /// Index definition through [`IndexBuilder`], which only yields the
+    /// statement once it has a name and a table
+    pub fn index_typed() -> IndexBuilder {
+        IndexBuilder::new()
+    }
     pub fn edge<E>() -> DefineEdgeStatement<E>
     where
         E: EdgeType,
@@ -81,3 +87,87 @@ impl Define {
         DefineUserStatement::new()
     }
 }
+
+impl StatementBuilder for DefineAccessStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineAnalyzerStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineConfigStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineDatabaseStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineEventStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineFieldStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineFunctionStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineIndexStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineNamespaceStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineParamStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineTokenStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl StatementBuilder for DefineUserStatement {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl<T: TableType> StatementBuilder for DefineTableStatement<T> {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
+
+impl<E: EdgeType> StatementBuilder for DefineEdgeStatement<E> {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+}
@@ -1,3 +1,4 @@
+use crate::backend::duration::duration_to_sql;
 use crate::define_table::AsSelect;
 use anyhow::bail;
 use magritte_core::{EdgeType, Permission, SchemaType};
@@ -169,9 +170,6 @@ where
         let mut stmt = String::new();
         stmt.push_str("DEFINE TABLE ");
 
-        if self.drop {
-            stmt.push_str("DROP ");
-        }
         if self.overwrite {
             stmt.push_str("OVERWRITE ");
         } else if self.if_not_exists {
@@ -184,15 +182,12 @@ where
             bail!("Table name is required");
         }
 
-        stmt.push_str(" TYPE RELATION");
-
-        if let Some(schema_type) = &self.schema_type {
-            stmt.push(' ');
-            stmt.push_str(schema_type.to_string().as_str());
-        } else {
-            stmt.push_str(" SCHEMALESS ")
+        if self.drop {
+            stmt.push_str(" DROP");
         }
 
+        stmt.push_str(" TYPE RELATION");
+
         if let Some(from) = &self.from {
             stmt.push_str(format!(" FROM {}", from).as_str());
         } else {
@@ -203,14 +198,24 @@ where
         } else {
             bail!("To is required");
         }
+        if self.enforced {
+            stmt.push_str(" ENFORCED");
+        }
+
+        if let Some(schema_type) = &self.schema_type {
+            stmt.push(' ');
+            stmt.push_str(schema_type.to_string().as_str());
+        } else {
+            stmt.push_str(" SCHEMALESS")
+        }
 
         if let Some(as_select) = &self.as_select {
             stmt.push_str(format!(" AS SELECT {}", as_select).as_str());
         }
         if let Some(changefeed) = &self.changefeed {
-            stmt.push_str(format!("CHANGEFEED {}", changefeed.0.as_secs()).as_str());
+            stmt.push_str(format!(" CHANGEFEED {}", duration_to_sql(&changefeed.0)).as_str());
             if changefeed.1 {
-                stmt.push_str(" INCLUDE ORIGINAL ");
+                stmt.push_str(" INCLUDE ORIGINAL");
             }
         }
 
@@ -227,9 +232,6 @@ where
             stmt.push_str(&format!(" COMMENT \"{}\"", comment));
         }
 
-        if self.enforced {
-            stmt.push_str(" ENFORCED");
-        }
 
         stmt.push(';');
         Ok(stmt)
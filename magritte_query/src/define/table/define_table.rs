@@ -1,3 +1,4 @@
+use crate::backend::duration::duration_to_sql;
 use anyhow::{anyhow, bail};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
@@ -187,9 +188,6 @@ where
         let mut stmt = String::new();
         stmt.push_str("DEFINE TABLE ");
 
-        if self.drop {
-            stmt.push_str("DROP ");
-        }
         if self.overwrite {
             stmt.push_str("OVERWRITE ");
         } else if self.if_not_exists {
@@ -202,22 +200,26 @@ where
             bail!("Table name is required");
         }
 
+        if self.drop {
+            stmt.push_str(" DROP");
+        }
+
         stmt.push_str(" TYPE NORMAL");
 
         if let Some(schema_type) = &self.schema_type {
             stmt.push(' ');
             stmt.push_str(schema_type.to_string().as_str());
         } else {
-            stmt.push_str(" SCHEMALESS ")
+            stmt.push_str(" SCHEMALESS")
         }
 
         if let Some(as_select) = &self.as_select {
             stmt.push_str(format!(" AS SELECT {}", as_select).as_str());
         }
         if let Some(changefeed) = &self.changefeed {
-            stmt.push_str(format!("CHANGEFEED {}", changefeed.0.as_secs()).as_str());
+            stmt.push_str(format!(" CHANGEFEED {}", duration_to_sql(&changefeed.0)).as_str());
             if changefeed.1 {
-                stmt.push_str(" INCLUDE ORIGINAL ");
+                stmt.push_str(" INCLUDE ORIGINAL");
             }
         }
 
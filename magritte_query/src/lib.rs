@@ -7,7 +7,12 @@ pub mod func;
 pub mod query;
 pub mod types;
 
+#[cfg(test)]
+pub(crate) mod test_support;
+
 pub use backend::*;
 pub use define::*;
 pub use func::*;
 pub use query::*;
+pub use magritte_db::interceptor::*;
+pub use magritte_db::SurrealDB;
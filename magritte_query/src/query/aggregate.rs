@@ -0,0 +1,388 @@
+//! Typed aggregation queries
+//!
+//! A SELECT with aggregate projections returns rows that are no longer records
+//! of the table, so [`AggregateStatement`] deserialises them into a caller
+//! supplied struct or tuple instead of `T`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! #[derive(Deserialize)]
+//! struct SalesByCountry {
+//!     country: String,
+//!     orders: u64,
+//!     revenue: f64,
+//! }
+//!
+//! let rows: Vec<SalesByCountry> = Query::aggregate::<Order>()
+//!     .key(OrderColumns::Country)
+//!     .count("orders")
+//!     .sum(OrderColumns::Total, "revenue")
+//!     .where_op("status", Operator::Eq, Some("paid"))?
+//!     .execute()
+//!     .await?;
+//!
+//! let totals: Vec<(u64, f64)> = Query::aggregate::<Order>()
+//!     .count("orders")
+//!     .mean(OrderColumns::Total, "average")
+//!     .execute_tuples()
+//!     .await?;
+//! ```
+
+use std::fmt::{self, Display};
+
+use anyhow::{anyhow, bail, Result};
+use magritte_core::operator::Operator;
+use magritte_core::value::SqlValue;
+use magritte_core::{ColumnType, OrderBy, Projection, RecordType};
+use magritte_db::db;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::instrument;
+
+use super::{SelectStatement, StatementBuilder};
+use crate::{HasConditions, HasLetConditions, HasParams};
+
+/// Aggregate function applied over each group
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    /// `count()`
+    Count,
+    /// `count(expr)`, counts rows where the expression is truthy
+    CountIf(String),
+    /// `math::sum(field)`
+    Sum(String),
+    /// `math::mean(field)`
+    Mean(String),
+    /// `math::max(field)`
+    Max(String),
+    /// `math::min(field)`
+    Min(String),
+    /// `array::distinct(field)`
+    Distinct(String),
+    /// Any other aggregate expression
+    Raw(String),
+}
+
+impl Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregate::Count => write!(f, "count()"),
+            Aggregate::CountIf(expr) => write!(f, "count({})", expr),
+            Aggregate::Sum(field) => write!(f, "math::sum({})", field),
+            Aggregate::Mean(field) => write!(f, "math::mean({})", field),
+            Aggregate::Max(field) => write!(f, "math::max({})", field),
+            Aggregate::Min(field) => write!(f, "math::min({})", field),
+            Aggregate::Distinct(field) => write!(f, "array::distinct({})", field),
+            Aggregate::Raw(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+/// SELECT ... GROUP BY returning aggregate rows
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateStatement<T>
+where
+    T: RecordType,
+{
+    select: SelectStatement<T>,
+    keys: Vec<(String, String)>,
+    aggregates: Vec<(Aggregate, String)>,
+}
+
+impl<T> Default for AggregateStatement<T>
+where
+    T: RecordType,
+{
+    fn default() -> Self {
+        Self::from_select(SelectStatement::new())
+    }
+}
+
+impl<T> AggregateStatement<T>
+where
+    T: RecordType,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregates over the targets, conditions and parameters of `select`.
+    /// Its projections, grouping and ordering are replaced.
+    pub fn from_select(mut select: SelectStatement<T>) -> Self {
+        select.selected_fields.clear();
+        select.group_by.clear();
+        select.all = false;
+        select.order_by.clear();
+        select.select_value = false;
+        Self {
+            select,
+            keys: vec![],
+            aggregates: vec![],
+        }
+    }
+
+    /// Groups by a column of `T`. The key is returned under the column name.
+    ///
+    /// Columns of another table do not compile:
+    ///
+    /// ```compile_fail
+    /// use magritte_core::{ColumnType, RecordType};
+    /// use magritte_query::AggregateStatement;
+    ///
+    /// fn by<T: RecordType, C: ColumnType>(column: C) -> AggregateStatement<T> {
+    ///     AggregateStatement::<T>::new().key(column)
+    /// }
+    /// ```
+    pub fn key<C: ColumnType<Table = T>>(self, column: C) -> Self {
+        self.key_field(column.column_name())
+    }
+
+    /// Groups by a field path. Nested paths are returned with `.` replaced
+    /// by `_`, e.g. `address.city` as `address_city`.
+    pub fn key_field(mut self, field: &str) -> Self {
+        let alias = field.replace('.', "_");
+        self.keys.push((field.to_string(), alias));
+        self
+    }
+
+    pub fn aggregate(mut self, aggregate: Aggregate, alias: &str) -> Self {
+        self.aggregates.push((aggregate, alias.to_string()));
+        self
+    }
+
+    /// `count() AS alias`
+    pub fn count(self, alias: &str) -> Self {
+        self.aggregate(Aggregate::Count, alias)
+    }
+
+    /// `count(condition) AS alias`
+    pub fn count_if(self, condition: &str, alias: &str) -> Self {
+        self.aggregate(Aggregate::CountIf(condition.to_string()), alias)
+    }
+
+    /// `math::sum(column) AS alias`
+    pub fn sum<C: ColumnType<Table = T>>(self, column: C, alias: &str) -> Self {
+        let field = column.column_name().to_string();
+        self.aggregate(Aggregate::Sum(field), alias)
+    }
+
+    /// `math::mean(column) AS alias`
+    pub fn mean<C: ColumnType<Table = T>>(self, column: C, alias: &str) -> Self {
+        let field = column.column_name().to_string();
+        self.aggregate(Aggregate::Mean(field), alias)
+    }
+
+    /// `math::max(column) AS alias`
+    pub fn max<C: ColumnType<Table = T>>(self, column: C, alias: &str) -> Self {
+        let field = column.column_name().to_string();
+        self.aggregate(Aggregate::Max(field), alias)
+    }
+
+    /// `math::min(column) AS alias`
+    pub fn min<C: ColumnType<Table = T>>(self, column: C, alias: &str) -> Self {
+        let field = column.column_name().to_string();
+        self.aggregate(Aggregate::Min(field), alias)
+    }
+
+    /// `array::distinct(column) AS alias`
+    pub fn distinct<C: ColumnType<Table = T>>(self, column: C, alias: &str) -> Self {
+        let field = column.column_name().to_string();
+        self.aggregate(Aggregate::Distinct(field), alias)
+    }
+
+    /// Orders by a group key or aggregate alias
+    pub fn order_by(mut self, alias: &str, ascending: bool) -> Self {
+        self.select
+            .order_by
+            .push((OrderBy::Field(alias.to_string()), ascending));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.select.limit = Some(limit);
+        self
+    }
+
+    fn to_select(&self) -> Result<SelectStatement<T>> {
+        if self.aggregates.is_empty() {
+            bail!("An aggregate query needs at least one aggregate function");
+        }
+        let mut select = self.select.clone();
+        for (field, alias) in &self.keys {
+            select.selected_fields.push(if field == alias {
+                Projection::Field(field.clone())
+            } else {
+                Projection::FieldAs(field.clone(), alias.clone())
+            });
+        }
+        for (aggregate, alias) in &self.aggregates {
+            select
+                .selected_fields
+                .push(Projection::RawAs(aggregate.to_string(), alias.clone()));
+        }
+        if self.keys.is_empty() {
+            select.all = true;
+        } else {
+            select.group_by = self.keys.iter().map(|(_, alias)| alias.clone()).collect();
+        }
+        Ok(select)
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.to_select()?.build()
+    }
+
+    /// The SELECT as `execute` sends it, checked against the safety policy
+    /// once its GROUP clause is in place
+    fn prepared(&self) -> Result<(String, Vec<(String, Value)>)> {
+        let mut select = self.to_select()?;
+        let query = select.build_prepared()?;
+        Ok((query, select.parameters))
+    }
+
+    /// Runs the query and deserialises each row into `R` by field name
+    #[instrument(skip_all)]
+    pub async fn execute<R>(self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let (query, params) = self.prepared()?;
+        db().execute(query, params).await
+    }
+
+    /// Runs the query and deserialises each row into a tuple, with the group
+    /// keys first followed by the aggregates, in the order they were added.
+    #[instrument(skip_all)]
+    pub async fn execute_tuples<R>(self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let columns = self.column_aliases();
+        let (query, params) = self.prepared()?;
+        let rows: Vec<Value> = db().execute(query, params).await?;
+        rows.into_iter()
+            .map(|row| Ok(serde_json::from_value(row_to_array(row, &columns)?)?))
+            .collect()
+    }
+
+    fn column_aliases(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|(_, alias)| alias.clone())
+            .chain(self.aggregates.iter().map(|(_, alias)| alias.clone()))
+            .collect()
+    }
+}
+
+/// Reorders an aggregate row into an array following `columns`
+fn row_to_array(row: Value, columns: &[String]) -> Result<Value> {
+    let Value::Object(mut map) = row else {
+        return Err(anyhow!("Expected an object row, got {}", row));
+    };
+    Ok(Value::Array(
+        columns
+            .iter()
+            .map(|c| map.remove(c).unwrap_or(Value::Null))
+            .collect(),
+    ))
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Turns this query into an aggregation over the same rows
+    pub fn aggregate(self) -> AggregateStatement<T> {
+        AggregateStatement::from_select(self)
+    }
+}
+
+impl<T> HasParams for AggregateStatement<T>
+where
+    T: RecordType,
+{
+    fn params(&self) -> &Vec<(String, Value)> {
+        &self.select.parameters
+    }
+
+    fn params_mut(&mut self) -> &mut Vec<(String, Value)> {
+        &mut self.select.parameters
+    }
+}
+
+impl<T> HasConditions for AggregateStatement<T>
+where
+    T: RecordType,
+{
+    fn conditions_mut(&mut self) -> &mut Vec<(String, Operator, SqlValue)> {
+        &mut self.select.conditions
+    }
+}
+
+impl<T> HasLetConditions for AggregateStatement<T>
+where
+    T: RecordType,
+{
+    fn get_lets(&self) -> &Vec<(String, String)> {
+        &self.select.let_statements
+    }
+
+    fn get_lets_mut(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.select.let_statements
+    }
+}
+
+impl<T: RecordType> StatementBuilder for AggregateStatement<T> {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.params().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Order;
+    use crate::{set_safety_policy, Query, SafetyPolicy};
+    use serde_json::json;
+
+    #[test]
+    fn test_aggregate_display() {
+        assert_eq!(Aggregate::Count.to_string(), "count()");
+        assert_eq!(Aggregate::CountIf("age > 18".into()).to_string(), "count(age > 18)");
+        assert_eq!(Aggregate::Sum("total".into()).to_string(), "math::sum(total)");
+        assert_eq!(Aggregate::Mean("total".into()).to_string(), "math::mean(total)");
+        assert_eq!(Aggregate::Max("total".into()).to_string(), "math::max(total)");
+        assert_eq!(
+            Aggregate::Distinct("tags".into()).to_string(),
+            "array::distinct(tags)"
+        );
+    }
+
+    #[test]
+    fn test_row_to_array() {
+        let row = json!({"revenue": 10.5, "country": "NL", "orders": 3});
+        let columns = vec!["country".to_string(), "orders".to_string(), "revenue".to_string()];
+        let tuple: (String, u64, f64) =
+            serde_json::from_value(row_to_array(row, &columns).unwrap()).unwrap();
+        assert_eq!(tuple, ("NL".to_string(), 3, 10.5));
+    }
+
+    #[test]
+    fn test_keyless_aggregate_on_large_table() {
+        set_safety_policy(SafetyPolicy::new().large_tables(&["orders"]));
+        let prepared = AggregateStatement::<Order>::new().count("orders").prepared();
+        let unbounded = Query::select::<Order>().build_prepared();
+        set_safety_policy(SafetyPolicy::new());
+
+        let (query, params) = prepared.unwrap();
+        assert_eq!(query, "SELECT count() AS orders FROM orders GROUP ALL;");
+        assert!(params.is_empty());
+        assert!(unbounded.is_err());
+    }
+}
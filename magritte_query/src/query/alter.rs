@@ -1,3 +1,6 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
 use anyhow::Result;
 use magritte_core::transaction::Transactional;
 use magritte_core::{Permission, SchemaType};
@@ -5,6 +8,47 @@ use magritte_db::db;
 use serde::de::DeserializeOwned;
 use tracing::instrument;
 
+use crate::backend::duration::duration_to_sql;
+
+/// Kind of table set by the `TYPE` clause
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TableKind {
+    /// `TYPE ANY`, normal records and edges. What SurrealDB assumes when the
+    /// clause is left out.
+    #[default]
+    Any,
+    /// `TYPE NORMAL`
+    Normal,
+    /// `TYPE RELATION [FROM a] [TO b] [ENFORCED]`
+    Relation {
+        from: Option<String>,
+        to: Option<String>,
+        enforced: bool,
+    },
+}
+
+impl Display for TableKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableKind::Any => write!(f, "TYPE ANY"),
+            TableKind::Normal => write!(f, "TYPE NORMAL"),
+            TableKind::Relation { from, to, enforced } => {
+                write!(f, "TYPE RELATION")?;
+                if let Some(from) = from {
+                    write!(f, " FROM {}", from)?;
+                }
+                if let Some(to) = to {
+                    write!(f, " TO {}", to)?;
+                }
+                if *enforced {
+                    write!(f, " ENFORCED")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 /// ALTER query builder with allowed method chains
 #[derive(Clone, Debug)]
 pub struct AlterStatement {
@@ -12,8 +56,12 @@ pub struct AlterStatement {
     if_exists: bool,
     drop: bool,
     schema_type: Option<SchemaType>,
+    kind: Option<TableKind>,
     permissions: Vec<Permission>,
-    comment: Option<String>,
+    /// `Some(None)` removes the changefeed
+    changefeed: Option<Option<(Duration, bool)>>,
+    /// `Some(None)` removes the comment
+    comment: Option<Option<String>>,
     in_transaction: bool,
 }
 
@@ -53,19 +101,60 @@ impl AlterStatement {
         self
     }
 
-    /// Add PERMISSIONS clause
+    /// Add PERMISSIONS clause. Conditions are given without `WHERE`, as in
+    /// the DEFINE statements: `Permission::Select("published = true".into())`
+    /// renders `FOR select WHERE published = true`.
     #[instrument(skip(self))]
     pub fn permissions(mut self, perms: Vec<Permission>) -> Self {
         self.permissions = perms;
         self
     }
 
+    /// Set the table TYPE
+    #[instrument(skip(self))]
+    pub fn kind(mut self, kind: TableKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Add CHANGEFEED, keeping changes for `duration`. `include_original`
+    /// also stores the record as it was before each change.
+    #[instrument(skip(self))]
+    pub fn changefeed(mut self, duration: Duration, include_original: bool) -> Self {
+        self.changefeed = Some(Some((duration, include_original)));
+        self
+    }
+
+    /// Remove the changefeed with CHANGEFEED NONE
+    #[instrument(skip(self))]
+    pub fn drop_changefeed(mut self) -> Self {
+        self.changefeed = Some(None);
+        self
+    }
+
     /// Add COMMENT
     #[instrument(skip(self))]
     pub fn comment(mut self, comment: &str) -> Self {
-        self.comment = Some(comment.to_string());
+        self.comment = Some(Some(comment.to_string()));
+        self
+    }
+
+    /// Remove the comment with COMMENT NONE
+    #[instrument(skip(self))]
+    pub fn drop_comment(mut self) -> Self {
+        self.comment = Some(None);
         self
     }
+
+    /// Whether the statement changes anything besides naming the table
+    pub fn has_changes(&self) -> bool {
+        self.drop
+            || self.schema_type.is_some()
+            || self.kind.is_some()
+            || !self.permissions.is_empty()
+            || self.changefeed.is_some()
+            || self.comment.is_some()
+    }
 }
 
 impl AlterStatement {
@@ -76,7 +165,9 @@ impl AlterStatement {
             drop: false,
             if_exists: false,
             schema_type: None,
+            kind: None,
             permissions: Vec::new(),
+            changefeed: None,
             comment: None,
             in_transaction: false,
         }
@@ -111,26 +202,42 @@ impl AlterStatement {
             }
         }
 
+        // Add table type
+        if let Some(kind) = &self.kind {
+            query.push_str(&format!(" {}", kind));
+        }
+
         // Add permissions
         if !self.permissions.is_empty() {
             query.push_str(" PERMISSIONS");
             for perm in &self.permissions {
-                match perm {
-                    Permission::None => query.push_str(" NONE"),
-                    Permission::Full => query.push_str(" FULL"),
-                    Permission::Select(expr) => query.push_str(&format!(" FOR select {}", expr)),
-                    Permission::Create(expr) => query.push_str(&format!(" FOR create {}", expr)),
-                    Permission::Update(expr) => query.push_str(&format!(" FOR update {}", expr)),
-                    Permission::Delete(expr) => query.push_str(&format!(" FOR delete {}", expr)),
+                query.push_str(&perm.to_string());
+            }
+        }
+
+        // Add changefeed
+        match &self.changefeed {
+            Some(Some((duration, include_original))) => {
+                query.push_str(&format!(" CHANGEFEED {}", duration_to_sql(duration)));
+                if *include_original {
+                    query.push_str(" INCLUDE ORIGINAL");
                 }
             }
+            Some(None) => query.push_str(" CHANGEFEED NONE"),
+            None => {}
         }
 
         // Add comment if specified
-        if let Some(comment) = &self.comment {
-            query.push_str(&format!(" COMMENT '{}'", comment));
+        match &self.comment {
+            Some(Some(comment)) => {
+                let escaped = comment.replace('\\', "\\\\").replace('\'', "\\'");
+                query.push_str(&format!(" COMMENT '{}'", escaped));
+            }
+            Some(None) => query.push_str(" COMMENT NONE"),
+            None => {}
         }
 
+        query.push(';');
         Ok(query)
     }
 
@@ -149,3 +256,53 @@ impl Transactional for AlterStatement {
         &mut self.in_transaction
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alter_clauses() {
+        let alter = AlterStatement::new()
+            .table("events")
+            .kind(TableKind::Relation {
+                from: Some("users".into()),
+                to: Some("posts".into()),
+                enforced: true,
+            })
+            .changefeed(Duration::from_secs(3600), true)
+            .comment("It's an edge");
+        assert_eq!(
+            alter.build().unwrap(),
+            "ALTER TABLE events TYPE RELATION FROM users TO posts ENFORCED \
+             CHANGEFEED 3600s INCLUDE ORIGINAL COMMENT 'It\\'s an edge';"
+        );
+
+        let alter = AlterStatement::new()
+            .table("events")
+            .drop_changefeed()
+            .drop_comment();
+        assert_eq!(
+            alter.build().unwrap(),
+            "ALTER TABLE events CHANGEFEED NONE COMMENT NONE;"
+        );
+        assert!(!AlterStatement::new().table("events").has_changes());
+    }
+
+    #[test]
+    fn test_alter_permissions() {
+        let alter = AlterStatement::new().table("posts").permissions(vec![
+            Permission::Select("published = true".into()),
+            Permission::Update("user = $auth.id".into()),
+        ]);
+        assert_eq!(
+            alter.build().unwrap(),
+            "ALTER TABLE posts PERMISSIONS FOR select WHERE published = true \
+             FOR update WHERE user = $auth.id;"
+        );
+        let alter = AlterStatement::new()
+            .table("posts")
+            .permissions(vec![Permission::None]);
+        assert_eq!(alter.build().unwrap(), "ALTER TABLE posts PERMISSIONS NONE;");
+    }
+}
@@ -0,0 +1,545 @@
+//! Changefeeds
+//!
+//! [`ShowChangesStatement`] reads the changefeed of a table enabled with
+//! `#[table(changefeed = "...")]`, and [`ChangefeedConsumer`] follows it,
+//! handing batches of changes to a handler and storing its position in a
+//! checkpoint table once the handler succeeds. A batch whose handler fails, or
+//! that is interrupted before the checkpoint is written, is delivered again, so
+//! handlers must be idempotent.
+//!
+//! Both need `CHANGEFEED` on the table; `SHOW CHANGES` fails on a table
+//! without one.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! ChangefeedConsumer::<Product>::new("search_sync")
+//!     .batch_size(500)
+//!     .run(|batch| async move {
+//!         for change in batch.iter().flat_map(|set| &set.changes) {
+//!             match change {
+//!                 Change::Update { record, .. } => index.upsert(record).await?,
+//!                 Change::Delete { id } => index.remove(id).await?,
+//!                 _ => {}
+//!             }
+//!         }
+//!         Ok(())
+//!     })
+//!     .await?;
+//! ```
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use anyhow::Result;
+use magritte_core::{RecordType, SurrealId};
+use magritte_db::db;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use surrealdb::RecordId;
+use tracing::{instrument, warn};
+
+use super::StatementBuilder;
+use crate::backend::timestamp::datetime_literal;
+
+/// Table the consumers store their checkpoints in
+pub const CHANGEFEED_CHECKPOINT_TABLE: &str = "changefeed_checkpoint";
+
+/// Where `SHOW CHANGES` starts reading
+#[derive(Debug, Clone, PartialEq)]
+pub enum Since {
+    /// `SINCE 42`, inclusive
+    Versionstamp(u64),
+    /// `SINCE d'2024-01-01T00:00:00Z'`
+    Time(String),
+}
+
+impl Default for Since {
+    fn default() -> Self {
+        Since::Versionstamp(0)
+    }
+}
+
+/// `SHOW CHANGES FOR TABLE ... SINCE ... LIMIT ...`
+#[derive(Debug, Clone)]
+pub struct ShowChangesStatement<T>
+where
+    T: RecordType,
+{
+    since: Since,
+    limit: Option<usize>,
+    record: Option<RecordId>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for ShowChangesStatement<T>
+where
+    T: RecordType,
+{
+    fn default() -> Self {
+        Self {
+            since: Since::default(),
+            limit: None,
+            record: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> ShowChangesStatement<T>
+where
+    T: RecordType,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn since(mut self, since: Since) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Changes from `versionstamp` on, inclusive
+    pub fn since_versionstamp(self, versionstamp: u64) -> Self {
+        self.since(Since::Versionstamp(versionstamp))
+    }
+
+    /// Changes from a point in time, e.g. `2024-01-01T00:00:00Z`. Building
+    /// the statement fails unless it is an RFC 3339 datetime.
+    pub fn since_time(self, timestamp: &str) -> Self {
+        self.since(Since::Time(timestamp.to_string()))
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Keeps only the changes of one record, giving its version history.
+    /// `LIMIT` then counts the change sets touching the record: `execute`
+    /// reads the feed page by page until it has found that many or reached
+    /// the end.
+    pub fn for_record(mut self, id: &SurrealId<T>) -> Self {
+        self.record = Some(id.to_record_id().clone());
+        self
+    }
+
+    pub fn build(&self) -> Result<String> {
+        let mut query = format!("SHOW CHANGES FOR TABLE {} SINCE ", T::table_name());
+        match &self.since {
+            Since::Versionstamp(v) => query.push_str(&v.to_string()),
+            Since::Time(t) => query.push_str(&datetime_literal(t.as_str())?),
+        }
+        if let Some(limit) = self.limit {
+            query.push_str(&format!(" LIMIT {}", limit));
+        }
+        query.push(';');
+        Ok(query)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn execute(self) -> Result<Vec<ChangeSet<T>>> {
+        self.read(|query| db().execute(query, vec![])).await
+    }
+
+    /// Runs the statement through `fetch`. With a record filter and a limit,
+    /// pages of `limit` change sets are read until enough of them touch the
+    /// record or the feed ends.
+    async fn read<F, Fut>(&self, mut fetch: F) -> Result<Vec<ChangeSet<T>>>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<Vec<RawChangeSet>>>,
+    {
+        let Some(record) = &self.record else {
+            let rows = fetch(self.build()?).await?;
+            return rows.into_iter().map(ChangeSet::try_from).collect();
+        };
+        let mut page = self.clone();
+        let mut found = Vec::new();
+        loop {
+            let rows = fetch(page.build()?).await?;
+            let last = rows.last().map(|row| row.versionstamp);
+            let exhausted = match page.limit {
+                Some(limit) => rows.len() < limit,
+                None => true,
+            };
+            for mut row in rows {
+                row.changes
+                    .retain(|change| change_record(change).as_ref() == Some(record));
+                if !row.changes.is_empty() {
+                    found.push(ChangeSet::try_from(row)?);
+                }
+            }
+            if let Some(limit) = self.limit {
+                found.truncate(limit);
+                if found.len() == limit {
+                    return Ok(found);
+                }
+            }
+            match last {
+                Some(last) if !exhausted => page.since = Since::Versionstamp(last + 1),
+                _ => return Ok(found),
+            }
+        }
+    }
+}
+
+impl<T> StatementBuilder for ShowChangesStatement<T>
+where
+    T: RecordType,
+{
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+}
+
+/// A single change of a record
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<T> {
+    /// The record after a create or update. With `include_original` the
+    /// patches reverting it to its previous state are included.
+    Update {
+        record: T,
+        patches: Option<Vec<Value>>,
+    },
+    /// The id of a deleted record
+    Delete { id: Value },
+    /// The table definition changed
+    DefineTable(Value),
+    Other(Value),
+}
+
+impl<T: DeserializeOwned> Change<T> {
+    fn from_value(value: Value) -> Result<Self> {
+        let Value::Object(mut change) = value else {
+            return Ok(Change::Other(value));
+        };
+        if let Some(current) = change.remove("current") {
+            let patches = match change.remove("update") {
+                Some(Value::Array(patches)) => Some(patches),
+                _ => None,
+            };
+            return Ok(Change::Update {
+                record: serde_json::from_value(current)?,
+                patches,
+            });
+        }
+        if let Some(record) = change.remove("update") {
+            return Ok(Change::Update {
+                record: serde_json::from_value(record)?,
+                patches: None,
+            });
+        }
+        if let Some(deleted) = change.remove("delete") {
+            let id = deleted.get("id").cloned().unwrap_or(deleted);
+            return Ok(Change::Delete { id });
+        }
+        if let Some(table) = change.remove("define_table") {
+            return Ok(Change::DefineTable(table));
+        }
+        Ok(Change::Other(Value::Object(change)))
+    }
+}
+
+/// Id of the record a raw change is about, decoded like a [`SurrealId`]
+/// field or parsed from its `table:key` form
+fn change_record(change: &Value) -> Option<RecordId> {
+    let id = ["current", "update", "delete"]
+        .iter()
+        .find_map(|key| change.get(key)?.get("id"))?;
+    match id {
+        Value::String(id) => id.parse().ok(),
+        id => RecordId::deserialize(id).ok(),
+    }
+}
+
+/// The changes committed in one transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeSet<T> {
+    pub versionstamp: u64,
+    pub changes: Vec<Change<T>>,
+}
+
+#[derive(Deserialize)]
+struct RawChangeSet {
+    versionstamp: u64,
+    #[serde(default)]
+    changes: Vec<Value>,
+}
+
+impl<T: DeserializeOwned> TryFrom<RawChangeSet> for ChangeSet<T> {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawChangeSet) -> Result<Self> {
+        Ok(Self {
+            versionstamp: raw.versionstamp,
+            changes: raw
+                .changes
+                .into_iter()
+                .map(Change::from_value)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Follows the changefeed of `T`, checkpointing after each handled batch
+#[derive(Debug, Clone)]
+pub struct ChangefeedConsumer<T>
+where
+    T: RecordType,
+{
+    name: String,
+    checkpoint_table: String,
+    batch_size: usize,
+    poll_interval: Duration,
+    start: Since,
+    _marker: PhantomData<T>,
+}
+
+impl<T> ChangefeedConsumer<T>
+where
+    T: RecordType,
+{
+    /// A consumer named `name`. Each name keeps its own checkpoint, so
+    /// several consumers can follow the same table.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            checkpoint_table: CHANGEFEED_CHECKPOINT_TABLE.to_string(),
+            batch_size: 100,
+            poll_interval: Duration::from_secs(1),
+            start: Since::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn checkpoint_table(mut self, table: &str) -> Self {
+        self.checkpoint_table = table.to_string();
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Wait between polls when the feed has no new changes
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Where to start when the consumer has no checkpoint yet
+    pub fn start_from(mut self, since: Since) -> Self {
+        self.start = since;
+        self
+    }
+
+    fn checkpoint_key(&self) -> String {
+        format!("{}_{}", T::table_name(), self.name)
+    }
+
+    /// The next versionstamp to read, if a batch was handled before
+    pub async fn checkpoint(&self) -> Result<Option<u64>> {
+        let rows: Vec<Option<u64>> = db()
+            .execute(
+                "SELECT VALUE next FROM type::thing($table, $key);".to_string(),
+                vec![
+                    ("table".to_string(), json!(self.checkpoint_table)),
+                    ("key".to_string(), json!(self.checkpoint_key())),
+                ],
+            )
+            .await?;
+        Ok(rows.into_iter().flatten().next())
+    }
+
+    /// Stores `next` as the versionstamp to resume from
+    pub async fn commit(&self, next: u64) -> Result<()> {
+        let _: Vec<Value> = db()
+            .execute(
+                "UPSERT type::thing($table, $key) SET next = $next, updated_at = time::now() \
+                 RETURN NONE;"
+                    .to_string(),
+                vec![
+                    ("table".to_string(), json!(self.checkpoint_table)),
+                    ("key".to_string(), json!(self.checkpoint_key())),
+                    ("next".to_string(), json!(next)),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// The next batch after the checkpoint, without moving it
+    pub async fn next_batch(&self) -> Result<Vec<ChangeSet<T>>> {
+        let since = match self.checkpoint().await? {
+            Some(next) => Since::Versionstamp(next),
+            None => self.start.clone(),
+        };
+        ShowChangesStatement::<T>::new()
+            .since(since)
+            .limit(self.batch_size)
+            .execute()
+            .await
+    }
+
+    /// Reads one batch and hands it to `handler`. The checkpoint moves past
+    /// the batch only if the handler succeeds. Returns the number of change
+    /// sets handled.
+    #[instrument(skip_all, fields(consumer = %self.name))]
+    pub async fn poll_once<F, Fut>(&self, handler: &mut F) -> Result<usize>
+    where
+        F: FnMut(Vec<ChangeSet<T>>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let batch = self.next_batch().await?;
+        let Some(last) = batch.last().map(|set| set.versionstamp) else {
+            return Ok(0);
+        };
+        let count = batch.len();
+        handler(batch).await?;
+        self.commit(last + 1).await?;
+        Ok(count)
+    }
+
+    /// Polls forever. A failing batch is logged and retried after the poll
+    /// interval; only errors reading the feed or the checkpoint stop the loop.
+    #[instrument(skip_all, fields(consumer = %self.name))]
+    pub async fn run<F, Fut>(self, mut handler: F) -> Result<()>
+    where
+        F: FnMut(Vec<ChangeSet<T>>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        loop {
+            let batch = self.next_batch().await?;
+            if let Some(last) = batch.last().map(|set| set.versionstamp) {
+                match handler(batch).await {
+                    Ok(()) => {
+                        self.commit(last + 1).await?;
+                        continue;
+                    }
+                    Err(e) => warn!("Changefeed batch failed, retrying: {}", e),
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validate;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Product {
+        name: String,
+    }
+
+    #[test]
+    fn test_change_set_from_rows() {
+        let raw: RawChangeSet = serde_json::from_value(json!({
+            "versionstamp": 65536,
+            "changes": [
+                {"update": {"id": "products:a", "name": "Widget"}},
+                {"current": {"id": "products:b", "name": "Gadget"}, "update": [{"op": "replace"}]},
+                {"delete": {"id": "products:c"}},
+                {"define_table": {"name": "products"}}
+            ]
+        }))
+        .unwrap();
+        let set = ChangeSet::<Product>::try_from(raw).unwrap();
+        assert_eq!(set.versionstamp, 65536);
+        assert_eq!(
+            set.changes[0],
+            Change::Update {
+                record: Product { name: "Widget".into() },
+                patches: None
+            }
+        );
+        assert!(matches!(&set.changes[1], Change::Update { patches: Some(p), .. } if p.len() == 1));
+        assert_eq!(set.changes[2], Change::Delete { id: json!("products:c") });
+        assert!(matches!(set.changes[3], Change::DefineTable(_)));
+        assert_eq!(
+            change_record(&json!({"delete": {"id": "products:c"}})),
+            Some(RecordId::from(("products", "c")))
+        );
+        assert_eq!(
+            change_record(&json!({"update": {"id": "products:⟨a b⟩"}})),
+            Some(RecordId::from(("products", "a b")))
+        );
+        assert_eq!(change_record(&json!({"define_table": {"name": "products"}})), None);
+    }
+
+    #[test]
+    fn test_show_changes_statement() {
+        let stmt = ShowChangesStatement::<crate::test_support::Product>::new()
+            .since_versionstamp(42)
+            .limit(10);
+        assert_eq!(
+            stmt.validate().unwrap(),
+            "SHOW CHANGES FOR TABLE products SINCE 42 LIMIT 10;"
+        );
+        let stmt = ShowChangesStatement::<crate::test_support::Product>::new()
+            .since_time("2024-01-01T00:00:00Z");
+        assert_eq!(
+            stmt.validate().unwrap(),
+            "SHOW CHANGES FOR TABLE products SINCE d'2024-01-01T00:00:00Z';"
+        );
+        assert!(ShowChangesStatement::<crate::test_support::Product>::new()
+            .since_time("2024-01-01'; REMOVE TABLE products; --")
+            .build()
+            .is_err());
+    }
+    #[tokio::test]
+    async fn test_record_versions_in_memory() -> Result<()> {
+        use crate::test_support::Product;
+        use surrealdb::engine::any::connect;
+
+        let db = connect("mem://").await?;
+        db.use_ns("test").use_db("test").await?;
+        db.query("DEFINE TABLE products CHANGEFEED 1h;").await?.check()?;
+        for i in 0..3 {
+            for key in ["a", "b"] {
+                db.query("UPSERT type::thing('products', $key) SET name = $name;")
+                    .bind(("key", key))
+                    .bind(("name", format!("{}{}", key, i)))
+                    .await?
+                    .check()?;
+            }
+        }
+        let fetch = |query: String| {
+            let db = db.clone();
+            async move {
+                let rows: Vec<RawChangeSet> = db.query(query).await?.check()?.take(0)?;
+                Ok::<_, anyhow::Error>(rows)
+            }
+        };
+
+        let all = ShowChangesStatement::<Product>::new().read(fetch).await?;
+        assert!(all.len() >= 6);
+
+        let a = ShowChangesStatement::<Product>::new()
+            .for_record(&SurrealId::new("a"))
+            .limit(2)
+            .read(fetch)
+            .await?;
+        let b = ShowChangesStatement::<Product>::new()
+            .for_record(&SurrealId::new("b"))
+            .read(fetch)
+            .await?;
+        assert_eq!(a.len(), 2);
+        assert_eq!(b.len(), 3);
+        for set in a.iter().chain(&b) {
+            assert_eq!(set.changes.len(), 1);
+            assert!(matches!(set.changes[0], Change::Update { .. }));
+        }
+        assert!(a[0].versionstamp < b[0].versionstamp);
+        assert!(b[0].versionstamp < a[1].versionstamp);
+        Ok(())
+    }
+}
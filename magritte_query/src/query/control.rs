@@ -0,0 +1,572 @@
+//! Control-flow statements
+//!
+//! Builders for `IF ... THEN ... ELSE ... END`, `FOR $x IN ... { }`, `THROW`,
+//! `RETURN`, `BREAK`, `CONTINUE` and `{ ... }` code blocks. They implement
+//! [`StatementBuilder`] so they nest into each other, into
+//! [`TransactionStatement`](super::TransactionStatement) and into function and
+//! event bodies.
+//!
+//! Parameters bound by nested statements are renumbered when the outer
+//! statement is built, so composing several builders never produces clashing
+//! `$pN` names.
+//!
+//! See [SurrealDB IF ELSE Documentation](https://surrealdb.com/docs/surrealql/statements/ifelse)
+//! and [FOR Documentation](https://surrealdb.com/docs/surrealql/statements/for)
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let body = Block::new()
+//!     .let_expr("total", "array::len($items)")
+//!     .then(
+//!         IfStatement::new("$total = 0")
+//!             .then(ThrowStatement::new("no items"))
+//!             .else_(ReturnStatement::new("$total")),
+//!     );
+//! ```
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::StatementBuilder;
+use crate::backend::params::merge_params;
+
+type Rendered = (String, Vec<(String, Value)>);
+
+fn render<S: StatementBuilder>(statement: &S) -> Result<Rendered> {
+    Ok((statement.build()?, statement.with_params()))
+}
+
+/// Strips the trailing `;` from a rendered statement so it can be used as an
+/// expression.
+fn as_expression(query: &str) -> &str {
+    query.trim().trim_end_matches(';').trim_end()
+}
+
+/// A sequence of statements, rendered as `{ a; b; }`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    statements: Vec<Rendered>,
+    error: Option<String>,
+}
+
+impl Block {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a statement to the block
+    pub fn then<S: StatementBuilder>(mut self, statement: S) -> Self {
+        match render(&statement) {
+            Ok(rendered) => self.statements.push(rendered),
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+            }
+        }
+        self
+    }
+
+    /// Appends a raw SurrealQL statement
+    pub fn raw(mut self, query: impl Into<String>) -> Self {
+        self.statements.push((query.into(), vec![]));
+        self
+    }
+
+    /// Binds the result of a statement to `$var`
+    pub fn let_<S: StatementBuilder>(mut self, var: &str, statement: S) -> Self {
+        match render(&statement) {
+            Ok((query, params)) => self.statements.push((
+                format!("LET ${} = ({});", var, as_expression(&query)),
+                params,
+            )),
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+            }
+        }
+        self
+    }
+
+    /// Binds an expression to `$var`
+    pub fn let_expr(mut self, var: &str, expr: impl Into<String>) -> Self {
+        self.statements
+            .push((format!("LET ${} = {};", var, expr.into()), vec![]));
+        self
+    }
+
+    /// Binds a value to `$var` as a query parameter
+    pub fn let_value<V: Serialize>(mut self, var: &str, value: V) -> Result<Self> {
+        self.statements.push((
+            format!("LET ${} = $p0;", var),
+            vec![("p0".to_string(), serde_json::to_value(value)?)],
+        ));
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    fn check(&self) -> Result<()> {
+        if let Some(error) = &self.error {
+            bail!("Invalid statement in block: {}", error);
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> Result<Rendered> {
+        self.check()?;
+        Ok(self.merge())
+    }
+
+    /// Joins the statements and renumbers their parameters, without checking
+    /// them
+    fn merge(&self) -> Rendered {
+        let (queries, params) = merge_params(&self.statements);
+        let body = queries
+            .iter()
+            .map(|q| {
+                let q = q.trim();
+                if q.ends_with(';') || q.ends_with('}') {
+                    q.to_string()
+                } else {
+                    format!("{};", q)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        (body, params)
+    }
+
+    /// Builds the statements without the surrounding braces, for use as the
+    /// body of a function or event.
+    pub fn build_body(&self) -> Result<String> {
+        self.render().map(|(body, _)| body)
+    }
+
+    pub fn build(&self) -> Result<String> {
+        let (body, _) = self.render()?;
+        if body.is_empty() {
+            return Ok("{};".to_string());
+        }
+        Ok(format!("{{ {} }};", body))
+    }
+}
+
+impl StatementBuilder for Block {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.merge().1
+    }
+}
+
+/// `IF @condition THEN { ... } [ ELSE IF @condition THEN { ... } ] [ ELSE { ... } ] END`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfStatement {
+    branches: Vec<(String, Block)>,
+    otherwise: Option<Block>,
+}
+
+impl IfStatement {
+    pub fn new(condition: impl Into<String>) -> Self {
+        Self {
+            branches: vec![(condition.into(), Block::new())],
+            otherwise: None,
+        }
+    }
+
+    /// Appends a statement to the current branch, which is the last `ELSE IF`
+    /// or, once [`else_`](Self::else_) has been called, the `ELSE` branch
+    pub fn then<S: StatementBuilder>(mut self, statement: S) -> Self {
+        match &mut self.otherwise {
+            Some(block) => *block = std::mem::take(block).then(statement),
+            None => {
+                let (_, block) = self.branches.last_mut().expect("IF has a branch");
+                *block = std::mem::take(block).then(statement);
+            }
+        }
+        self
+    }
+
+    /// Replaces the body of the current branch with `block`
+    pub fn then_block(mut self, block: Block) -> Self {
+        match &mut self.otherwise {
+            Some(current) => *current = block,
+            None => self.branches.last_mut().expect("IF has a branch").1 = block,
+        }
+        self
+    }
+
+    /// Starts an `ELSE IF` branch
+    pub fn else_if(mut self, condition: impl Into<String>) -> Self {
+        self.branches.push((condition.into(), Block::new()));
+        self
+    }
+
+    /// Starts the `ELSE` branch with a single statement
+    pub fn else_<S: StatementBuilder>(mut self, statement: S) -> Self {
+        self.otherwise = Some(Block::new().then(statement));
+        self
+    }
+
+    /// Starts the `ELSE` branch with a block
+    pub fn else_block(mut self, block: Block) -> Self {
+        self.otherwise = Some(block);
+        self
+    }
+
+    fn check(&self) -> Result<()> {
+        for (condition, block) in &self.branches {
+            if condition.trim().is_empty() {
+                bail!("IF condition cannot be empty");
+            }
+            if block.is_empty() {
+                bail!("IF branch `{}` has no statements", condition);
+            }
+            block.check()?;
+        }
+        if let Some(block) = &self.otherwise {
+            block.check()?;
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> Result<Rendered> {
+        self.check()?;
+        Ok(self.merge())
+    }
+
+    fn merge(&self) -> Rendered {
+        let mut parts = Vec::new();
+        for (i, (condition, block)) in self.branches.iter().enumerate() {
+            let keyword = if i == 0 { "IF" } else { "ELSE IF" };
+            let (body, params) = block.merge();
+            parts.push((format!("{} {} THEN {{ {} }}", keyword, condition, body), params));
+        }
+        if let Some(block) = &self.otherwise {
+            let (body, params) = block.merge();
+            parts.push((format!("ELSE {{ {} }}", body), params));
+        }
+
+        let (queries, params) = merge_params(&parts);
+        (format!("{} END;", queries.join(" ")), params)
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.render().map(|(q, _)| q)
+    }
+}
+
+impl StatementBuilder for IfStatement {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.merge().1
+    }
+}
+
+/// `FOR $var IN @iterable { ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForStatement {
+    var: String,
+    iterable: Option<Rendered>,
+    body: Block,
+}
+
+impl ForStatement {
+    pub fn new(var: impl Into<String>) -> Self {
+        let var = var.into();
+        Self {
+            var: var.trim_start_matches('$').to_string(),
+            iterable: None,
+            body: Block::new(),
+        }
+    }
+
+    /// Iterates over an expression, e.g. `$items` or `[1, 2, 3]`
+    pub fn in_(mut self, expr: impl Into<String>) -> Self {
+        self.iterable = Some((expr.into(), vec![]));
+        self
+    }
+
+    /// Iterates over the result of a subquery
+    pub fn in_query<S: StatementBuilder>(mut self, statement: S) -> Result<Self> {
+        let (query, params) = render(&statement)?;
+        self.iterable = Some((format!("({})", as_expression(&query)), params));
+        Ok(self)
+    }
+
+    /// Iterates over values bound as a query parameter
+    pub fn in_values<V: Serialize>(mut self, values: Vec<V>) -> Result<Self> {
+        self.iterable = Some((
+            "$p0".to_string(),
+            vec![("p0".to_string(), serde_json::to_value(values)?)],
+        ));
+        Ok(self)
+    }
+
+    /// Appends a statement to the loop body
+    pub fn then<S: StatementBuilder>(mut self, statement: S) -> Self {
+        self.body = self.body.then(statement);
+        self
+    }
+
+    /// Replaces the loop body with `block`
+    pub fn then_block(mut self, block: Block) -> Self {
+        self.body = block;
+        self
+    }
+
+    fn render(&self) -> Result<Rendered> {
+        if self.var.is_empty() {
+            bail!("FOR variable name is required");
+        }
+        let Some(iterable) = &self.iterable else {
+            bail!("FOR iterable is required");
+        };
+        if self.body.is_empty() {
+            bail!("FOR body cannot be empty");
+        }
+        self.body.check()?;
+        let (queries, params) = self.merge(iterable.clone());
+        Ok((
+            format!("FOR ${} IN {} {{ {} }};", self.var, queries[0], queries[1]),
+            params,
+        ))
+    }
+
+    fn merge(&self, iterable: Rendered) -> (Vec<String>, Vec<(String, Value)>) {
+        merge_params(&[iterable, self.body.merge()])
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.render().map(|(q, _)| q)
+    }
+}
+
+impl StatementBuilder for ForStatement {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.merge(self.iterable.clone().unwrap_or_default()).1
+    }
+}
+
+/// `THROW @error`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrowStatement {
+    error: String,
+}
+
+impl ThrowStatement {
+    /// Throws a string message, escaped as a SurrealQL string literal
+    pub fn new(message: impl AsRef<str>) -> Self {
+        Self {
+            error: Value::String(message.as_ref().to_string()).to_string(),
+        }
+    }
+
+    /// Throws the value of an expression, e.g. `"Invalid: " + $name`
+    pub fn expr(expr: impl Into<String>) -> Self {
+        Self { error: expr.into() }
+    }
+
+    pub fn build(&self) -> Result<String> {
+        if self.error.trim().is_empty() {
+            bail!("THROW requires an error");
+        }
+        Ok(format!("THROW {};", self.error))
+    }
+}
+
+impl StatementBuilder for ThrowStatement {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+}
+
+/// `RETURN @value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnStatement {
+    value: Rendered,
+}
+
+impl ReturnStatement {
+    /// Returns the value of an expression
+    pub fn new(expr: impl Into<String>) -> Self {
+        Self {
+            value: (expr.into(), vec![]),
+        }
+    }
+
+    /// Returns the result of a statement
+    pub fn query<S: StatementBuilder>(statement: S) -> Result<Self> {
+        let (query, params) = render(&statement)?;
+        Ok(Self {
+            value: (format!("({})", as_expression(&query)), params),
+        })
+    }
+
+    /// Returns a value bound as a query parameter
+    pub fn value<V: Serialize>(value: V) -> Result<Self> {
+        Ok(Self {
+            value: (
+                "$p0".to_string(),
+                vec![("p0".to_string(), serde_json::to_value(value)?)],
+            ),
+        })
+    }
+
+    pub fn build(&self) -> Result<String> {
+        if self.value.0.trim().is_empty() {
+            return Ok("RETURN;".to_string());
+        }
+        Ok(format!("RETURN {};", self.value.0))
+    }
+}
+
+impl StatementBuilder for ReturnStatement {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.value.1.clone()
+    }
+}
+
+/// `BREAK`, only valid inside a `FOR` loop
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BreakStatement;
+
+impl StatementBuilder for BreakStatement {
+    fn build(&self) -> Result<String> {
+        Ok("BREAK;".to_string())
+    }
+}
+
+/// `CONTINUE`, only valid inside a `FOR` loop
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ContinueStatement;
+
+impl StatementBuilder for ContinueStatement {
+    fn build(&self) -> Result<String> {
+        Ok("CONTINUE;".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_if_else() {
+        let stmt = IfStatement::new("$total = 0")
+            .then(ThrowStatement::new("no items"))
+            .else_if("$total > 100")
+            .then(ReturnStatement::new("\"bulk\""))
+            .else_(ReturnStatement::new("$total"));
+        assert_eq!(
+            stmt.build().unwrap(),
+            "IF $total = 0 THEN { THROW \"no items\"; } ELSE IF $total > 100 THEN { RETURN \"bulk\"; } ELSE { RETURN $total; } END;"
+        );
+    }
+
+    #[test]
+    fn test_if_requires_body() {
+        assert!(IfStatement::new("$x").build().is_err());
+    }
+
+    #[test]
+    fn test_then_after_else_extends_else() {
+        let stmt = IfStatement::new("$x")
+            .then(ReturnStatement::new("1"))
+            .else_(ReturnStatement::new("2"))
+            .then(ReturnStatement::new("3"));
+        assert_eq!(
+            stmt.build().unwrap(),
+            "IF $x THEN { RETURN 1; } ELSE { RETURN 2; RETURN 3; } END;"
+        );
+    }
+
+    #[test]
+    fn test_params_survive_invalid_branch() {
+        let stmt = IfStatement::new("$x")
+            .then(ReturnStatement::value("a").unwrap())
+            .else_if("$y");
+        assert!(stmt.build().is_err());
+        assert_eq!(stmt.with_params(), vec![("p0".to_string(), json!("a"))]);
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let stmt = ForStatement::new("item")
+            .in_("$items")
+            .then(
+                IfStatement::new("$item.qty = 0")
+                    .then(ContinueStatement)
+                    .else_if("$item.qty < 0")
+                    .then(BreakStatement),
+            )
+            .then(Block::new().raw("UPDATE $item.id SET seen = true"));
+        assert_eq!(
+            stmt.build().unwrap(),
+            "FOR $item IN $items { IF $item.qty = 0 THEN { CONTINUE; } ELSE IF $item.qty < 0 THEN { BREAK; } END; { UPDATE $item.id SET seen = true; }; };"
+        );
+    }
+
+    #[test]
+    fn test_nested_params_are_renumbered() {
+        let stmt = ForStatement::new("x")
+            .in_values(vec![1, 2, 3])
+            .unwrap()
+            .then(ReturnStatement::value("done").unwrap());
+        assert_eq!(stmt.build().unwrap(), "FOR $x IN $p0 { RETURN $p1; };");
+        assert_eq!(
+            stmt.with_params(),
+            vec![
+                ("p0".to_string(), json!([1, 2, 3])),
+                ("p1".to_string(), json!("done")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_let() {
+        let block = Block::new()
+            .let_value("limit", 10)
+            .unwrap()
+            .let_expr("now", "time::now()")
+            .then(ReturnStatement::value(true).unwrap());
+        assert_eq!(
+            block.build().unwrap(),
+            "{ LET $limit = $p0; LET $now = time::now(); RETURN $p1; };"
+        );
+        assert_eq!(
+            block.build_body().unwrap(),
+            "LET $limit = $p0; LET $now = time::now(); RETURN $p1;"
+        );
+    }
+
+    #[test]
+    fn test_throw_escapes_message() {
+        assert_eq!(
+            ThrowStatement::new("bad \"input\"").build().unwrap(),
+            "THROW \"bad \\\"input\\\"\";"
+        );
+        assert_eq!(
+            ThrowStatement::expr("\"Invalid: \" + $name").build().unwrap(),
+            "THROW \"Invalid: \" + $name;"
+        );
+    }
+}
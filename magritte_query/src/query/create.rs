@@ -6,11 +6,17 @@ use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::time::Duration;
 
-use crate::{FromTarget, HasReturns};
+use crate::{Fingerprint, FromTarget, HasReturns};
+use crate::backend::duration::duration_to_sql;
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::intercept;
+use crate::query::fetch::{at_most_one, exactly_one};
+use crate::backend::timestamp::{datetime_literal, IntoTimestamp};
 use anyhow::Result;
 use magritte_core::transaction::Transactional;
-use magritte_core::{RangeTarget, RecordType, ReturnType, SurrealId};
-use magritte_db::db;
+use magritte_core::{IdGenerator, RangeTarget, RecordType, ReturnType, SurrealId};
+use magritte_db::{db, StatementKind};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tracing::instrument;
 
@@ -33,7 +39,7 @@ where
     targets: Option<Vec<FromTarget<T>>>,
     only: bool,
     content: Option<Content>,
-    parameters: Vec<(String, serde_json::Value)>,
+    pub(crate) parameters: Vec<(String, serde_json::Value)>,
     parallel: bool,
     timeout: Option<Duration>,
     return_type: Option<ReturnType>,
@@ -50,6 +56,18 @@ where
         self
     }
 
+    /// Creates the record with the key of `id`, whatever its kind
+    pub fn record_id(mut self, id: &SurrealId<T>) -> Self {
+        self.with_id = Some(id.id().to_string());
+        self
+    }
+
+    /// Lets the database generate the id, `CREATE table:ulid()`
+    pub fn generate_id(mut self, generator: IdGenerator) -> Self {
+        self.with_id = Some(generator.to_string());
+        self
+    }
+
     pub fn range(mut self, range_target: RangeTarget) -> Self {
         self.with_range = Some(range_target);
         self
@@ -112,10 +130,11 @@ where
         self
     }
 
-    /// Add VERSION clause
-    pub fn version(mut self, timestamp: &str) -> Self {
-        self.version = Some(format!("d'{}'", timestamp));
-        self
+    /// Add VERSION clause, creating the record at `timestamp`. Fails if
+    /// `timestamp` is a string that is not an RFC 3339 datetime.
+    pub fn version(mut self, timestamp: impl IntoTimestamp) -> Result<Self> {
+        self.version = Some(datetime_literal(timestamp)?);
+        Ok(self)
     }
 
     #[instrument(skip_all)]
@@ -136,20 +155,17 @@ where
         }
     }
     #[instrument(skip_all)]
-    fn build(&self) -> anyhow::Result<String> {
+    pub fn build(&self) -> anyhow::Result<String> {
         let mut query = String::new();
         query.push_str("CREATE ");
         if self.only {
             query.push_str("ONLY ");
-        } else if let Some(targets) = &self.targets {
-            if !targets.is_empty() {
-                for v in targets {
-                    query.push_str(&format!("{},", v.to_string().as_str()));
-                }
-            }
         }
 
-        if let Some(id) = &self.with_id {
+        if let Some(targets) = self.targets.as_ref().filter(|t| !self.only && !t.is_empty()) {
+            let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+            query.push_str(&targets.join(", "));
+        } else if let Some(id) = &self.with_id {
             query.push_str(T::table_name());
             query.push_str(&format!(":{}", id));
         } else if let Some(range) = &self.with_range {
@@ -176,9 +192,6 @@ where
                 }
             }
         }
-        if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
-        }
         if let Some(return_type) = &self.return_type {
             match return_type {
                 ReturnType::All => query.push_str(" RETURN AFTER"),
@@ -192,20 +205,102 @@ where
                 }
             }
         }
-        if let Some(ver) = &self.version {
-            query.push_str(&format!(" VERSION {}", ver));
+        if let Some(timeout) = &self.timeout {
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
         if self.parallel {
             query.push_str(" PARALLEL");
         }
+        if let Some(ver) = &self.version {
+            query.push_str(&format!(" VERSION {}", ver));
+        }
         query.push(';');
         Ok(query)
     }
 
+    /// Applies the registered interceptors, returning the comment they tag
+    /// the statement with
+    pub(crate) fn prepare(&mut self) -> Result<String> {
+        let targets_records = self.with_id.is_some() || self.with_range.is_some();
+        intercept::apply_unfiltered(
+            &mut self.parameters,
+            StatementKind::Create,
+            T::table_name(),
+            targets_records,
+        )
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+
     /// Execute the CREATE query
     #[instrument(skip_all)]
-    async fn execute(self) -> Result<Vec<T>> {
-        db().execute(self.build()?, self.parameters).await
+    pub async fn execute(self) -> Result<Vec<T>> {
+        self.fetch_as().await
+    }
+
+    /// Runs the query and decodes the returned rows into `R`, for RETURN
+    /// projections that do not match the record
+    #[instrument(skip_all, fields(fingerprint = self.fingerprint()))]
+    pub async fn fetch_as<R>(mut self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let query = self.build_prepared()?;
+        if self.only {
+            let row = db().execute_optional(query, self.parameters).await?;
+            return Ok(row.into_iter().collect());
+        }
+        db().execute(query, self.parameters).await
+    }
+
+    /// Returns the only affected row, failing when there are none or several
+    #[instrument(skip_all)]
+    pub async fn fetch_one(self) -> Result<T> {
+        exactly_one(self.fetch_as().await?)
+    }
+
+    /// Returns the affected row if there is one, failing when there are
+    /// several
+    #[instrument(skip_all)]
+    pub async fn fetch_optional(self) -> Result<Option<T>> {
+        at_most_one(self.fetch_as().await?)
+    }
+}
+
+impl<T> Fingerprint for CreateStatement<T>
+where
+    T: RecordType,
+{
+    fn fingerprint(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.with_id.is_some())
+            .part(&self.with_range.is_some())
+            .part(&self.targets.as_ref().map(Vec::len))
+            .part(&self.only)
+            .part(&self.content.as_ref().map(std::mem::discriminant))
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .part(&self.version.is_some())
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.with_id)
+            .part(&self.with_range)
+            .part(&self.targets)
+            .part(&self.only)
+            .part(&self.content)
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .part(&self.version)
+            .finish()
     }
 }
 
@@ -229,3 +324,36 @@ where
         &mut self.in_transaction
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Order, User};
+    use crate::{Query, Validate};
+
+    #[test]
+    fn test_generated_id() {
+        let query = Query::create::<User>()
+            .generate_id(IdGenerator::Ulid)
+            .set("name", "Alice")
+            .unwrap();
+        assert_eq!(query.validate().unwrap(), "CREATE users:ulid() SET name = \"Alice\";");
+    }
+
+    #[test]
+    fn test_array_record_ids() {
+        let reading = SurrealId::<Order>::array(("sensor-1", 42)).unwrap();
+        Query::create::<Order>()
+            .record_id(&reading)
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+        Query::select::<Order>()
+            .range_target(RangeTarget::array(
+                &["'sensor-1'", "d'2024-01-01T00:00:00Z'"],
+                &["'sensor-1'", "time::now()"],
+                true,
+            ))
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+}
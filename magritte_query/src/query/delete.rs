@@ -6,13 +6,21 @@ use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::time::Duration;
 
-use crate::{FromTarget, HasConditions, HasParams, HasReturns, WhereClause};
+use crate::{Fingerprint, FromTarget, HasConditions, HasParams, HasReturns, WhereClause};
+use crate::backend::duration::duration_to_sql;
+use crate::query::fetch::{at_most_one, exactly_one};
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::intercept;
+use crate::backend::policy::{enforce_mutation, MutationShape};
+use crate::backend::query_result::targets_records;
+use crate::backend::strict::check_fields;
 use anyhow::bail;
 use magritte_core::operator::Operator;
 use magritte_core::transaction::Transactional;
 use magritte_core::value::SqlValue;
 use magritte_core::{RangeTarget, RecordType, ReturnType, SurrealId};
-use magritte_db::db;
+use magritte_db::{db, StatementKind};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 use tracing::instrument;
@@ -32,6 +40,7 @@ where
     parallel: bool,
     return_type: Option<ReturnType>,
     in_transaction: bool,
+    allow_full_table: bool,
     _marker: PhantomData<T>,
 }
 impl<T> DeleteStatement<T>
@@ -79,6 +88,13 @@ where
         self
     }
 
+    /// Exempts the statement from the safety policy's guard on deletes
+    /// without conditions
+    pub fn allow_full_table(mut self) -> Self {
+        self.allow_full_table = true;
+        self
+    }
+
     /// Start building an edge deletion
     pub fn edge_of(self, edge: &str) -> EdgeDeleteStatement<T> {
         EdgeDeleteStatement::new(self, edge)
@@ -97,11 +113,19 @@ where
             parallel: false,
             return_type: Default::default(),
             in_transaction: false,
+            allow_full_table: false,
             _marker: Default::default(),
         }
     }
     #[instrument(skip(self))]
     pub fn build(&self) -> anyhow::Result<String> {
+        check_fields(
+            T::table_name(),
+            self.conditions
+                .iter()
+                .filter(|(_, op, _)| *op != Operator::Raw)
+                .map(|(field, _, _)| field.as_str()),
+        )?;
         let mut query = String::new();
         query.push_str("DELETE ");
         if self.only {
@@ -115,21 +139,16 @@ where
                 );
             }
             query.push_str("ONLY ");
-        } else if let Some(targets) = &self.targets {
-            if !targets.is_empty() {
-                for v in targets {
-                    query.push_str(&format!("{},", v.to_string().as_str()));
-                }
-            }
         }
 
-        if let Some(id) = &self.with_id {
-            query.push_str(T::table_name());
-            query.push_str(&format!(":{}", id));
+        if let Some(targets) = self.targets.as_ref().filter(|t| !self.only && !t.is_empty()) {
+            let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+            query.push_str(&targets.join(", "));
+        } else if let Some(id) = &self.with_id {
+            query.push_str(&id.to_string());
         } else if let Some(range) = &self.with_range {
-            query.push('|');
             query.push_str(T::table_name());
-            query.push_str(&format!(":{}|", range));
+            query.push_str(&format!(":{}", range));
         } else {
             query.push_str(T::table_name());
         }
@@ -157,7 +176,7 @@ where
             }
         }
         if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
         if self.parallel {
             query.push_str(" PARALLEL");
@@ -165,11 +184,93 @@ where
         query.push(';');
         Ok(query)
     }
+    /// Checks the safety policy and applies the registered interceptors,
+    /// returning the comment they tag the statement with
+    pub(crate) fn prepare(&mut self) -> anyhow::Result<String> {
+        let targets_records = self.with_id.is_some()
+            || self.with_range.is_some()
+            || targets_records(&self.targets);
+        enforce_mutation(MutationShape {
+            kind: "DELETE",
+            table: T::table_name(),
+            targets_records,
+            has_conditions: !self.conditions.is_empty(),
+            allow_full_table: self.allow_full_table,
+        })?;
+        intercept::apply(self, StatementKind::Delete, T::table_name(), targets_records)
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> anyhow::Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+
     #[instrument(skip_all)]
     pub async fn execute(self) -> anyhow::Result<Vec<T>> {
-        db().execute(self.build()?, self.parameters).await
+        self.fetch_as().await
+    }
+
+    /// Runs the query and decodes the returned rows into `R`, for RETURN
+    /// projections that do not match the record
+    #[instrument(skip_all, fields(fingerprint = self.fingerprint()))]
+    pub async fn fetch_as<R>(mut self) -> anyhow::Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let query = self.build_prepared()?;
+        if self.only {
+            let row = db().execute_optional(query, self.parameters).await?;
+            return Ok(row.into_iter().collect());
+        }
+        db().execute(query, self.parameters).await
+    }
+
+    /// Returns the only affected row, failing when there are none or several
+    #[instrument(skip_all)]
+    pub async fn fetch_one(self) -> anyhow::Result<T> {
+        exactly_one(self.fetch_as().await?)
+    }
+
+    /// Returns the affected row if there is one, failing when there are
+    /// several
+    #[instrument(skip_all)]
+    pub async fn fetch_optional(self) -> anyhow::Result<Option<T>> {
+        at_most_one(self.fetch_as().await?)
     }
 }
+
+impl<T> Fingerprint for DeleteStatement<T>
+where
+    T: RecordType,
+{
+    fn fingerprint(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.with_id.is_some())
+            .part(&self.with_range.is_some())
+            .part(&self.targets.as_ref().map(Vec::len))
+            .part(&self.only)
+            .part(&self.conditions)
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .part(&self.return_type)
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.with_id)
+            .part(&self.with_range)
+            .part(&self.targets)
+            .part(&self.only)
+            .part(&self.conditions)
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .part(&self.return_type)
+            .finish()
+    }
+}
+
 impl<T> HasParams for DeleteStatement<T>
 where
     T: RecordType,
@@ -258,6 +359,29 @@ where
         self.inner = self.inner.parallel();
         self
     }
+    /// Exempts the statement from the safety policy's guard on deletes
+    /// without conditions
+    pub fn allow_full_table(mut self) -> Self {
+        self.inner = self.inner.allow_full_table();
+        self
+    }
+    /// Checks the safety policy. Without a record on either side or a
+    /// condition, the statement removes every edge of its kind.
+    fn check_policy(&self) -> anyhow::Result<()> {
+        enforce_mutation(MutationShape {
+            kind: "DELETE",
+            table: &self.edge,
+            targets_records: self.from_id.is_some() || self.to_id.is_some(),
+            has_conditions: !self.inner.conditions.is_empty(),
+            allow_full_table: self.inner.allow_full_table,
+        })
+    }
+    #[instrument(skip_all)]
+    pub async fn execute(self) -> anyhow::Result<Vec<Value>> {
+        self.check_policy()?;
+        let parameters = self.inner.parameters.clone();
+        db().execute(self.build()?, parameters).await
+    }
     #[instrument(skip(self))]
     pub fn build(self) -> anyhow::Result<String> {
         let mut query = String::new();
@@ -302,7 +426,7 @@ where
 
         // Add TIMEOUT if specified
         if let Some(timeout) = &self.inner.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
 
         // Add PARALLEL if enabled
@@ -0,0 +1,274 @@
+//! Typed EXPLAIN output
+//!
+//! [`SelectStatement::plan`] runs the query with `EXPLAIN FULL` and decodes
+//! the operations SurrealDB reports into a [`QueryPlan`], so tests can check
+//! which indexes a query hits.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let plan = Query::select::<User>()
+//!     .where_op("email", Operator::Eq, Some("alice@example.com"))?
+//!     .plan()
+//!     .await?;
+//! plan.assert_uses_index("email_idx")?;
+//! assert!(!plan.is_full_scan());
+//! ```
+
+use std::fmt::{self, Display};
+
+use anyhow::{bail, Result};
+use magritte_core::RecordType;
+use magritte_db::db;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::instrument;
+
+use super::SelectStatement;
+
+/// One operation of a query plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanStep {
+    /// Scans every record of a table
+    IterateTable { table: String },
+    /// Reads records through an index
+    IterateIndex {
+        table: String,
+        index: String,
+        operator: Option<String>,
+        value: Option<Value>,
+    },
+    /// Reads a single record by id
+    IterateRecord { record: String },
+    /// Reads a range of record ids
+    IterateRange { table: String, range: String },
+    /// Gathers results, e.g. in `Memory`
+    Collector { kind: String },
+    /// Number of records fetched, reported by `EXPLAIN FULL`
+    Fetch { count: u64 },
+    /// The planner could not use an index
+    Fallback { reason: String },
+    Other { operation: String, detail: Value },
+}
+
+#[derive(Deserialize)]
+struct RawStep {
+    operation: String,
+    #[serde(default)]
+    detail: Value,
+}
+
+fn text(detail: &Value, key: &str) -> Option<String> {
+    match detail.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+impl From<RawStep> for PlanStep {
+    fn from(raw: RawStep) -> Self {
+        let detail = &raw.detail;
+        let table = || text(detail, "table").unwrap_or_default();
+        match raw.operation.as_str() {
+            "Iterate Table" => PlanStep::IterateTable { table: table() },
+            op if op.starts_with("Iterate") && op.contains("Index") => {
+                let plan = detail.get("plan").cloned().unwrap_or_default();
+                PlanStep::IterateIndex {
+                    table: table(),
+                    index: text(&plan, "index").unwrap_or_default(),
+                    operator: text(&plan, "operator"),
+                    value: plan.get("value").cloned(),
+                }
+            }
+            "Iterate Thing" | "Iterate Record" => PlanStep::IterateRecord {
+                record: text(detail, "thing")
+                    .or_else(|| text(detail, "record"))
+                    .unwrap_or_default(),
+            },
+            "Iterate Range" => PlanStep::IterateRange {
+                table: table(),
+                range: text(detail, "range").unwrap_or_default(),
+            },
+            "Collector" => PlanStep::Collector {
+                kind: text(detail, "type").unwrap_or_default(),
+            },
+            "Fetch" => PlanStep::Fetch {
+                count: detail.get("count").and_then(Value::as_u64).unwrap_or_default(),
+            },
+            "Fallback" => PlanStep::Fallback {
+                reason: text(detail, "reason").unwrap_or_default(),
+            },
+            _ => PlanStep::Other {
+                operation: raw.operation.clone(),
+                detail: raw.detail.clone(),
+            },
+        }
+    }
+}
+
+impl Display for PlanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IterateTable { table } => write!(f, "Iterate Table {}", table),
+            Self::IterateIndex { table, index, .. } => {
+                write!(f, "Iterate Index {} on {}", index, table)
+            }
+            Self::IterateRecord { record } => write!(f, "Iterate Record {}", record),
+            Self::IterateRange { table, range } => write!(f, "Iterate Range {}:{}", table, range),
+            Self::Collector { kind } => write!(f, "Collector {}", kind),
+            Self::Fetch { count } => write!(f, "Fetch {}", count),
+            Self::Fallback { reason } => write!(f, "Fallback: {}", reason),
+            Self::Other { operation, detail } => write!(f, "{} {}", operation, detail),
+        }
+    }
+}
+
+/// The plan SurrealDB reports for a query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+impl QueryPlan {
+    /// Decodes the rows returned by an `EXPLAIN` query
+    pub fn from_rows(rows: Vec<Value>) -> Result<Self> {
+        let steps = rows
+            .into_iter()
+            .map(|row| serde_json::from_value::<RawStep>(row).map(PlanStep::from))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+
+    /// The iterator steps, one per query target
+    pub fn iterators(&self) -> impl Iterator<Item = &PlanStep> {
+        self.steps.iter().filter(|step| {
+            matches!(
+                step,
+                PlanStep::IterateTable { .. }
+                    | PlanStep::IterateIndex { .. }
+                    | PlanStep::IterateRecord { .. }
+                    | PlanStep::IterateRange { .. }
+            )
+        })
+    }
+
+    /// Names of the indexes the query reads
+    pub fn indexes(&self) -> Vec<&str> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                PlanStep::IterateIndex { index, .. } => Some(index.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn uses_index(&self, index: &str) -> bool {
+        self.indexes().contains(&index)
+    }
+
+    /// Whether any target is scanned in full
+    pub fn is_full_scan(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step, PlanStep::IterateTable { .. }))
+    }
+
+    /// Records fetched, when the plan comes from `EXPLAIN FULL`
+    pub fn fetch_count(&self) -> Option<u64> {
+        self.steps.iter().find_map(|step| match step {
+            PlanStep::Fetch { count } => Some(*count),
+            _ => None,
+        })
+    }
+
+    /// Fails with the whole plan unless the query reads `index`
+    pub fn assert_uses_index(&self, index: &str) -> Result<()> {
+        if !self.uses_index(index) {
+            bail!("Query does not use index {}, plan: {}", index, self);
+        }
+        Ok(())
+    }
+}
+
+impl Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self.steps.iter().map(|s| s.to_string()).collect();
+        write!(f, "[{}]", steps.join(", "))
+    }
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Runs the query with `EXPLAIN FULL`, or the EXPLAIN set with
+    /// [`explain`](Self::explain), and returns its plan instead of its rows.
+    /// The interceptors run first, so the plan covers the conditions they
+    /// add.
+    #[instrument(skip_all)]
+    pub async fn plan(mut self) -> Result<QueryPlan> {
+        if self.explain.is_none() {
+            self.explain = Some(true);
+        }
+        let query = self.build_prepared()?;
+        let rows: Vec<Value> = db().execute(query, self.parameters).await?;
+        QueryPlan::from_rows(rows)
+    }
+
+    /// Fails unless the query reads `index`, returning the plan otherwise
+    pub async fn assert_uses_index(self, index: &str) -> Result<QueryPlan> {
+        let plan = self.plan().await?;
+        plan.assert_uses_index(index)?;
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::Query;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_from_rows() {
+        let plan = QueryPlan::from_rows(vec![
+            json!({
+                "detail": {
+                    "plan": {"index": "email_idx", "operator": "=", "value": "a@b.c"},
+                    "table": "users"
+                },
+                "operation": "Iterate Index"
+            }),
+            json!({"detail": {"type": "Memory"}, "operation": "Collector"}),
+            json!({"detail": {"count": 1}, "operation": "Fetch"}),
+        ])
+        .unwrap();
+        assert_eq!(plan.indexes(), vec!["email_idx"]);
+        assert!(!plan.is_full_scan());
+        assert_eq!(plan.fetch_count(), Some(1));
+        assert_eq!(plan.iterators().count(), 1);
+        assert!(plan.assert_uses_index("name_idx").is_err());
+
+        let scan = QueryPlan::from_rows(vec![
+            json!({"detail": {"table": "users"}, "operation": "Iterate Table"}),
+        ])
+        .unwrap();
+        assert!(scan.is_full_scan());
+        assert_eq!(scan.to_string(), "[Iterate Table users]");
+    }
+
+    #[tokio::test]
+    async fn test_explained_select_does_not_execute() {
+        let err = Query::select::<User>()
+            .explain(true)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("run it with .plan()"));
+    }
+}
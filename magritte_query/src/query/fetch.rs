@@ -0,0 +1,243 @@
+//! Result-shaping terminal methods for SELECT
+//!
+//! `execute` always returns `Vec<T>`. These methods return the shape the query
+//! actually produces: a single record, an optional record, the values of a
+//! `SELECT VALUE`, a count, or a projection decoded into another type. The
+//! CREATE, UPDATE, UPSERT, DELETE and INSERT builders have `fetch_one`,
+//! `fetch_optional` and `fetch_as` for the rows they return.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let user: User = Query::select::<User>()
+//!     .where_id(SurrealId::new("alice"))
+//!     .fetch_one()
+//!     .await?;
+//!
+//! let emails: Vec<String> = Query::select::<User>()
+//!     .select_value()
+//!     .field("email", None)
+//!     .fetch_value()
+//!     .await?;
+//!
+//! let adults = Query::select::<User>()
+//!     .where_op("age", Operator::Gte, Some(18))?
+//!     .fetch_count()
+//!     .await?;
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use magritte_core::{Projection, RecordType};
+use magritte_db::db;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use tracing::instrument;
+
+use super::SelectStatement;
+
+#[derive(Deserialize)]
+struct CountRow {
+    count: u64,
+}
+
+/// Fails when `rows` holds more than one row
+pub(crate) fn at_most_one<R>(mut rows: Vec<R>) -> Result<Option<R>> {
+    if rows.len() > 1 {
+        bail!("Expected at most one row, got {}", rows.len());
+    }
+    Ok(rows.pop())
+}
+
+/// Fails unless `rows` holds exactly one row
+pub(crate) fn exactly_one<R>(rows: Vec<R>) -> Result<R> {
+    at_most_one(rows)?.ok_or_else(|| anyhow!("Expected exactly one row, got none"))
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Returns the only row, failing when there are none or several
+    #[instrument(skip_all)]
+    pub async fn fetch_one(self) -> Result<T> {
+        self.fetch_optional()
+            .await?
+            .ok_or_else(|| anyhow!("Expected exactly one row, got none"))
+    }
+
+    /// Returns the row if there is one, failing when there are several
+    #[instrument(skip_all)]
+    pub async fn fetch_optional(self) -> Result<Option<T>> {
+        self.fetch_optional_as().await
+    }
+
+    /// Returns the values of a `SELECT VALUE` query
+    #[instrument(skip_all)]
+    pub async fn fetch_value<V>(mut self) -> Result<Vec<V>>
+    where
+        V: DeserializeOwned + Send + 'static,
+    {
+        if !self.select_value {
+            bail!("fetch_value requires SELECT VALUE, call .select_value() first");
+        }
+        if self.only {
+            return Ok(self.fetch_optional_as::<V>().await?.into_iter().collect());
+        }
+        db().execute(self.build_prepared()?, self.parameters).await
+    }
+
+    /// Counts the rows matching the query with `count()` and GROUP ALL
+    #[instrument(skip_all)]
+    pub async fn fetch_count(self) -> Result<usize> {
+        let mut query = self.counting();
+        let rows: Vec<CountRow> = db()
+            .execute(query.build_prepared()?, query.parameters)
+            .await?;
+        Ok(rows.first().map_or(0, |row| row.count as usize))
+    }
+
+    /// Whether any row matches the query
+    #[instrument(skip_all)]
+    pub async fn exists(self) -> Result<bool> {
+        let mut query = self.probing();
+        if query.only {
+            return Ok(query.fetch_optional_as::<IgnoredAny>().await?.is_some());
+        }
+        let rows: Vec<IgnoredAny> = db()
+            .execute(query.build_prepared()?, query.parameters)
+            .await?;
+        Ok(!rows.is_empty())
+    }
+
+    /// Returns all rows decoded into `R` instead of `T`, for projections
+    /// that do not match the record
+    #[instrument(skip_all)]
+    pub async fn fetch_as<R>(mut self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        if self.only {
+            return Ok(self.fetch_optional_as::<R>().await?.into_iter().collect());
+        }
+        db().execute(self.build_prepared()?, self.parameters).await
+    }
+
+    async fn fetch_optional_as<R>(self) -> Result<Option<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let mut query = self.single();
+        if query.only {
+            return db()
+                .execute_optional(query.build_prepared()?, query.parameters)
+                .await;
+        }
+        let rows: Vec<R> = db()
+            .execute(query.build_prepared()?, query.parameters)
+            .await?;
+        at_most_one(rows)
+    }
+
+    /// The query limited to what is needed to tell one row from several:
+    /// `LIMIT 1` with ONLY, at most `LIMIT 2` otherwise
+    pub(crate) fn single(mut self) -> Self {
+        if self.only {
+            if self.limit.is_none() {
+                self.limit = Some(1);
+            }
+        } else if self.limit.map_or(true, |limit| limit > 2) {
+            self.limit = Some(2);
+        }
+        self
+    }
+
+    /// `SELECT id ... LIMIT 1` over the same rows
+    pub(crate) fn probing(mut self) -> Self {
+        self.selected_fields = vec![Projection::Field("id".to_string())];
+        self.select_value = false;
+        self.omitted_fields = None;
+        self.fetch_fields.clear();
+        self.limit = Some(1);
+        self
+    }
+
+    /// `SELECT count() AS count ... GROUP ALL` over the same rows
+    pub(crate) fn counting(&self) -> Self {
+        let mut query = self.clone();
+        query.selected_fields = vec![Projection::RawAs("count()".into(), "count".into())];
+        query.select_value = false;
+        query.only = false;
+        query.omitted_fields = None;
+        query.group_by.clear();
+        query.order_by.clear();
+        query.fetch_fields.clear();
+        query.split_fields.clear();
+        query.limit = None;
+        query.start = None;
+        query.all = true;
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, WhereClause};
+    use magritte_core::operator::Operator;
+
+    #[test]
+    fn test_counting() {
+        let query = Query::select::<User>()
+            .fields(&["name"])
+            .where_op("age", Operator::Gte, Some(18))
+            .unwrap()
+            .order_by_field("name", true)
+            .fetch(&["orders"])
+            .limit(10)
+            .counting();
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT count() AS count FROM users WHERE age >= $p0 GROUP ALL;"
+        );
+    }
+
+    #[test]
+    fn test_single_limits() {
+        let sql = |query: SelectStatement<User>| query.single().build().unwrap();
+        assert_eq!(sql(Query::select::<User>()), "SELECT * FROM users LIMIT 2;");
+        assert_eq!(
+            sql(Query::select::<User>().limit(50)),
+            "SELECT * FROM users LIMIT 2;"
+        );
+        assert_eq!(
+            sql(Query::select::<User>().limit(1)),
+            "SELECT * FROM users LIMIT 1;"
+        );
+        assert_eq!(
+            sql(Query::select::<User>().only()),
+            "SELECT * FROM ONLY users LIMIT 1;"
+        );
+    }
+
+    #[test]
+    fn test_probing() {
+        let query = Query::select::<User>()
+            .select_value()
+            .field("email", None)
+            .fetch(&["orders"])
+            .probing();
+        assert_eq!(query.build().unwrap(), "SELECT id FROM users LIMIT 1;");
+    }
+
+    #[test]
+    fn test_row_count_checks() {
+        assert_eq!(at_most_one(Vec::<u8>::new()).unwrap(), None);
+        assert_eq!(at_most_one(vec![1]).unwrap(), Some(1));
+        assert!(at_most_one(vec![1, 2]).is_err());
+        assert!(exactly_one(Vec::<u8>::new()).is_err());
+        assert_eq!(exactly_one(vec![1]).unwrap(), 1);
+    }
+}
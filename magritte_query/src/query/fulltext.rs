@@ -0,0 +1,137 @@
+//! Full-text search projections for SELECT
+//!
+//! Pair [`WhereClause::where_matches`](crate::WhereClause::where_matches) with
+//! a predicate reference to read the relevance score or highlighted text of
+//! each match.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let products = Query::select::<Product>()
+//!     .where_matches("description", "wireless headphones", Some(1))?
+//!     .search_highlight("<b>", "</b>", 1, "snippet")
+//!     .order_by_score(1)
+//!     .limit(10)
+//!     .execute()
+//!     .await?;
+//! ```
+
+use magritte_core::{Projection, RecordType};
+use serde_json::Value;
+
+use super::SelectStatement;
+use crate::SearchFunction;
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    fn push_search_projection(&mut self, function: SearchFunction, alias: &str) {
+        if self.selected_fields.is_empty() {
+            self.selected_fields.push(Projection::All);
+        }
+        self.selected_fields
+            .push(Projection::RawAs(function.to_string(), alias.to_string()));
+    }
+
+    /// Selects `search::score(N) AS alias`
+    pub fn search_score(mut self, predicate_ref: u8, alias: &str) -> Self {
+        self.push_search_projection(SearchFunction::Score(predicate_ref as usize), alias);
+        self
+    }
+
+    /// Selects `search::highlight(prefix, suffix, N) AS alias`
+    pub fn search_highlight(
+        mut self,
+        prefix: &str,
+        suffix: &str,
+        predicate_ref: u8,
+        alias: &str,
+    ) -> Self {
+        let function = SearchFunction::Highlight(
+            Value::from(prefix).to_string(),
+            Value::from(suffix).to_string(),
+            predicate_ref as usize,
+            None,
+        );
+        self.push_search_projection(function, alias);
+        self
+    }
+
+    /// Selects `search::offsets(N) AS alias`
+    pub fn search_offsets(mut self, predicate_ref: u8, alias: &str) -> Self {
+        self.push_search_projection(SearchFunction::Offsets(predicate_ref as usize, None), alias);
+        self
+    }
+
+    /// Orders by relevance, best match first. Selects the score as
+    /// `search_score_N` unless it is already selected under that alias.
+    pub fn order_by_score(mut self, predicate_ref: u8) -> Self {
+        let alias = format!("search_score_{}", predicate_ref);
+        let selected = self
+            .selected_fields
+            .iter()
+            .any(|p| matches!(p, Projection::RawAs(_, a) if *a == alias));
+        if !selected {
+            self = self.search_score(predicate_ref, &alias);
+        }
+        self.order_by_field(&alias, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Product;
+    use crate::{Query, StatementBuilder, WhereClause};
+    use serde_json::json;
+
+    #[test]
+    fn test_matches_with_score_and_highlight() {
+        let query = Query::select::<Product>()
+            .where_matches("description", "wireless headphones", Some(1))
+            .unwrap()
+            .search_highlight("<b>", "</b>", 1, "snippet")
+            .order_by_score(1)
+            .limit(10);
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT *, search::highlight(\"<b>\", \"</b>\", 1) AS snippet, \
+             search::score(1) AS search_score_1 FROM products \
+             WHERE description @1@ $p0 ORDER BY search_score_1 DESC LIMIT 10;"
+        );
+        assert_eq!(
+            query.with_params(),
+            vec![("p0".to_string(), json!("wireless headphones"))]
+        );
+    }
+
+    #[test]
+    fn test_order_by_selected_score() {
+        let sql = Query::select::<Product>()
+            .where_matches("name", "lamp", None)
+            .unwrap()
+            .search_score(2, "search_score_2")
+            .order_by_score(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT *, search::score(2) AS search_score_2 FROM products \
+             WHERE name @@ $p0 ORDER BY search_score_2 DESC;"
+        );
+    }
+
+    #[test]
+    fn test_highlight_quotes_markers() {
+        let function = SearchFunction::Highlight(
+            Value::from("<b>").to_string(),
+            Value::from("</b>").to_string(),
+            1,
+            None,
+        );
+        assert_eq!(function.to_string(), "search::highlight(\"<b>\", \"</b>\", 1)");
+    }
+}
@@ -5,11 +5,16 @@
 use std::fmt::Debug;
 use std::time::Duration;
 
-use crate::{FromTarget, HasParams};
+use crate::{Fingerprint, FromTarget, HasParams};
+use crate::backend::duration::duration_to_sql;
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::intercept;
+use crate::query::fetch::{at_most_one, exactly_one};
 use anyhow::{anyhow, Result};
 use magritte_core::transaction::Transactional;
 use magritte_core::{RecordType, ReturnType, SurrealId};
-use magritte_db::db;
+use magritte_db::{db, StatementKind};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 use tracing::instrument;
@@ -164,26 +169,16 @@ where
         let mut query = String::new();
         query.push_str("INSERT ");
 
-        if self.only {
-            query.push_str("ONLY ");
-        } else if let Some(targets) = &self.targets {
-            if !targets.is_empty() {
-                for v in targets {
-                    query.push_str(&format!("{},", v.to_string().as_str()));
-                }
-            }
-        }
-
-        query.push_str("INTO ");
-        query.push_str(T::table_name());
-
         if self.as_relation {
-            query.push_str(" RELATION ");
+            query.push_str("RELATION ");
         }
         if self.ignore {
             query.push_str("IGNORE ");
         }
 
+        query.push_str("INTO ");
+        query.push_str(T::table_name());
+
         if let Some(content) = &self.content {
             match content {
                 Content::Value(value) => {
@@ -255,7 +250,7 @@ where
 
         // Add TIMEOUT if specified
         if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_millis()));
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
 
         // Add PARALLEL if specified
@@ -267,10 +262,84 @@ where
         Ok(query)
     }
 
+    /// Applies the registered interceptors, returning the comment they tag
+    /// the statement with
+    pub(crate) fn prepare(&mut self) -> Result<String> {
+        intercept::apply_unfiltered(
+            &mut self.parameters,
+            StatementKind::Insert,
+            T::table_name(),
+            self.with_id.is_some(),
+        )
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+
     pub async fn execute(self) -> Result<Vec<T>> {
-        db().execute(self.build()?, self.parameters).await
+        self.fetch_as().await
+    }
+
+    /// Runs the query and decodes the returned rows into `R`, for RETURN
+    /// projections that do not match the record
+    #[instrument(skip_all, fields(fingerprint = self.fingerprint()))]
+    pub async fn fetch_as<R>(mut self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        db().execute(self.build_prepared()?, self.parameters).await
+    }
+
+    /// Returns the only affected row, failing when there are none or several
+    #[instrument(skip_all)]
+    pub async fn fetch_one(self) -> Result<T> {
+        exactly_one(self.fetch_as().await?)
+    }
+
+    /// Returns the affected row if there is one, failing when there are
+    /// several
+    #[instrument(skip_all)]
+    pub async fn fetch_optional(self) -> Result<Option<T>> {
+        at_most_one(self.fetch_as().await?)
+    }
+}
+
+impl<T> Fingerprint for InsertStatement<T>
+where
+    T: RecordType,
+{
+    fn fingerprint(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.targets.as_ref().map(Vec::len))
+            .part(&self.with_id.is_some())
+            .part(&self.only)
+            .part(&self.content.as_ref().map(std::mem::discriminant))
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .part(&self.as_relation)
+            .part(&self.ignore)
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.targets)
+            .part(&self.with_id)
+            .part(&self.only)
+            .part(&self.content)
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .part(&self.as_relation)
+            .part(&self.ignore)
+            .finish()
     }
 }
+
 impl<T> HasParams for InsertStatement<T>
 where
     T: RecordType,
@@ -338,6 +407,13 @@ fn strip_id(value: &serde_json::Value) -> Result<String> {
 }
 fn process_value(value: &serde_json::Value) -> Result<String> {
     match value {
+        serde_json::Value::Array(values) => {
+            let entries = values
+                .iter()
+                .map(process_value)
+                .collect::<Result<Vec<String>>>()?;
+            Ok(format!("[{}]", entries.join(", ")))
+        }
         serde_json::Value::Object(map) => {
             let entries: Vec<String> = map
                 .iter()
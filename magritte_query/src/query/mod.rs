@@ -1,22 +1,40 @@
-use surrealdb::{Response, Surreal};
+use surrealdb::Surreal;
 
+pub mod aggregate;
 pub mod alter;
+pub mod changes;
+pub mod control;
 pub mod create;
 pub mod delete;
+pub mod explain;
+pub mod fetch;
+pub mod fulltext;
 pub mod info;
 pub mod insert;
+pub mod pagination;
 pub mod relate;
 pub mod select;
+pub mod transaction;
+pub mod typestate;
 pub mod update;
 pub mod upsert;
 
+pub use aggregate::*;
 pub use alter::*;
+pub use changes::*;
+pub use control::*;
 pub use create::*;
 pub use delete::*;
+pub use explain::*;
+pub use fetch::*;
+pub use fulltext::*;
 pub use info::*;
 pub use insert::*;
+pub use pagination::*;
 pub use relate::*;
 pub use select::*;
+pub use transaction::*;
+pub use typestate::*;
 use serde_json::Value;
 use surrealdb::engine::any::Any;
 pub use update::*;
@@ -73,6 +91,11 @@ impl Query {
         SelectStatement::new()
     }
 
+    /// Aggregation over a table [`AggregateStatement`]
+    pub fn aggregate<T: RecordType>() -> AggregateStatement<T> {
+        AggregateStatement::new()
+    }
+
     /// INSERT statement [`InsertStatement`]
     pub fn insert<T: RecordType>() -> InsertStatement<T> {
         InsertStatement::new()
@@ -97,6 +120,11 @@ impl Query {
         UpsertStatement::new()
     }
 
+    /// SHOW CHANGES statement [`ShowChangesStatement`]
+    pub fn changes<T: RecordType>() -> ShowChangesStatement<T> {
+        ShowChangesStatement::new()
+    }
+
     pub fn info(db: Surreal<Any>) -> InfoStatement {
         InfoStatement::new(db)
     }
@@ -105,88 +133,30 @@ impl Query {
     pub fn begin() -> TransactionStatement {
         TransactionStatement::new()
     }
-}
 
-#[derive(Debug, Clone, Default)]
-pub struct TransactionStatement {
-    statements: Vec<(String, Vec<(String, Value)>)>,
-}
+    /// Code block [`Block`]
+    pub fn block() -> Block {
+        Block::new()
+    }
 
-impl TransactionStatement {
-    pub fn new() -> Self {
-        Self {
-            statements: vec![("BEGIN TRANSACTION;".to_string(), vec![])],
-        }
-    }
-
-    pub fn raw(mut self, query: &str, params: Vec<(String, Value)>) -> Self {
-        self.statements.push((query.to_string(), params));
-        self
-    }
-
-    pub fn commit(mut self) -> Self {
-        self.statements
-            .push(("COMMIT TRANSACTION;".to_string(), vec![]));
-        self
-    }
-
-    pub fn rollback(mut self) -> Self {
-        self.statements
-            .push(("CANCEL TRANSACTION;".to_string(), vec![]));
-        self
-    }
-
-    pub fn then<S: StatementBuilder>(mut self, statement: S) -> Self {
-        self.statements
-            .push((statement.build().unwrap(), statement.with_params()));
-        self
-    }
-    pub fn build(&self) -> (String, Vec<(String, Value)>) {
-        let mut query_string = String::new();
-        let mut all_params = Vec::new();
-        let mut param_counter = 0;
-
-        for (i, (query, params)) in self.statements.iter().enumerate() {
-            let mut formatted_query = query.trim().to_string();
-            // Ensure proper semicolon usage: add only between statements, not after the last one
-            if i < self.statements.len() - 1 && !formatted_query.ends_with(';') {
-                formatted_query.push(';');
-            }
-
-            // Rename parameters to avoid conflicts across statements
-            let mut renamed_params = Vec::new();
-            for (param_name, value) in params {
-                let new_param_name = format!("p{}", param_counter);
-                formatted_query = formatted_query
-                    .replace(&format!("${}", param_name), &format!("${}", new_param_name));
-                renamed_params.push((new_param_name, value.clone()));
-                param_counter += 1;
-            }
-            all_params.extend(renamed_params);
-
-            if i > 0 {
-                query_string.push(' ');
-            }
-            query_string.push_str(&formatted_query);
-        }
-
-        // Clean up extra delimiters
-        query_string = query_string
-            .replace(";;", ";")
-            .replace("; ;", ";")
-            .trim()
-            .to_string();
-
-        (query_string, all_params)
-    }
-
-    pub async fn execute(self, db: &Surreal<Any>) -> surrealdb::Result<Response> {
-        let (query, params) = self.build();
-        let mut q = db.query(&query);
-        if !params.is_empty() {
-            q = q.bind(params);
-        }
-        q.await?.check()
+    /// IF statement [`IfStatement`]
+    pub fn if_(condition: impl Into<String>) -> IfStatement {
+        IfStatement::new(condition)
+    }
+
+    /// FOR statement [`ForStatement`]
+    pub fn for_(var: impl Into<String>) -> ForStatement {
+        ForStatement::new(var)
+    }
+
+    /// THROW statement [`ThrowStatement`]
+    pub fn throw(message: impl AsRef<str>) -> ThrowStatement {
+        ThrowStatement::new(message)
+    }
+
+    /// RETURN statement [`ReturnStatement`]
+    pub fn return_(expr: impl Into<String>) -> ReturnStatement {
+        ReturnStatement::new(expr)
     }
 }
 
@@ -195,6 +165,21 @@ pub trait StatementBuilder {
     fn with_params(&self) -> Vec<(String, Value)> {
         vec![]
     }
+    /// The statement as `execute` sends it: checked against the safety
+    /// policy and rewritten by the registered interceptors, which can add
+    /// conditions and parameters and prefix it with a comment
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build()
+    }
+    /// The statement as indented, multi-line SurrealQL
+    fn build_pretty(&self) -> anyhow::Result<String> {
+        Ok(crate::format_sql(&self.build()?))
+    }
+    /// The statement with its parameters inlined as SurrealQL literals, ready
+    /// to paste into Surrealist. Values of sensitive fields are redacted.
+    fn to_debug_sql(&self) -> anyhow::Result<String> {
+        Ok(crate::debug_sql(&self.build()?, &self.with_params()))
+    }
 }
 
 impl StatementBuilder for AlterStatement {
@@ -203,6 +188,18 @@ impl StatementBuilder for AlterStatement {
     }
 }
 
+impl<T: RecordType> StatementBuilder for CreateStatement<T> {
+    fn build(&self) -> anyhow::Result<String> {
+        self.build()
+    }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.parameters.clone()
+    }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
+}
+
 impl<T: RecordType> StatementBuilder for SelectStatement<T> {
     fn build(&self) -> anyhow::Result<String> {
         self.build()
@@ -210,6 +207,9 @@ impl<T: RecordType> StatementBuilder for SelectStatement<T> {
     fn with_params(&self) -> Vec<(String, Value)> {
         self.params().clone()
     }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
 
 impl<T: RecordType> StatementBuilder for InsertStatement<T> {
@@ -219,17 +219,32 @@ impl<T: RecordType> StatementBuilder for InsertStatement<T> {
     fn with_params(&self) -> Vec<(String, Value)> {
         self.params().clone()
     }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
 
 impl<T: RecordType> StatementBuilder for UpdateStatement<T> {
     fn build(&self) -> anyhow::Result<String> {
         self.build()
     }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.params().clone()
+    }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
 impl StatementBuilder for RelateStatement {
     fn build(&self) -> anyhow::Result<String> {
         self.build()
     }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.params().clone()
+    }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
 impl<T: RecordType> StatementBuilder for DeleteStatement<T> {
     fn build(&self) -> anyhow::Result<String> {
@@ -239,6 +254,9 @@ impl<T: RecordType> StatementBuilder for DeleteStatement<T> {
     fn with_params(&self) -> Vec<(String, Value)> {
         self.params().clone()
     }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
 
 impl<T: RecordType> StatementBuilder for UpsertStatement<T> {
@@ -249,4 +267,7 @@ impl<T: RecordType> StatementBuilder for UpsertStatement<T> {
     fn with_params(&self) -> Vec<(String, Value)> {
         self.params().clone()
     }
+    fn build_prepared(&mut self) -> anyhow::Result<String> {
+        self.build_prepared()
+    }
 }
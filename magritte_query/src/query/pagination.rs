@@ -0,0 +1,499 @@
+//! Keyset (cursor) pagination for SELECT queries.
+//!
+//! Instead of skipping rows with `START AT`, each page continues from the
+//! ORDER BY key and record id of the last row of the previous page, so the
+//! cost of fetching a page does not grow with its position.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let page = Query::select::<User>()
+//!     .where_op("active", Operator::Eq, Some(true))?
+//!     .order_by_field("created_at", false)
+//!     .paginate(PageRequest::new(20).with_total())
+//!     .await?;
+//!
+//! if let Some(next) = page.next_cursor {
+//!     let next_page = Query::select::<User>()
+//!         .where_op("active", Operator::Eq, Some(true))?
+//!         .order_by_field("created_at", false)
+//!         .paginate(PageRequest::new(20).after(next))
+//!         .await?;
+//! }
+//! ```
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use magritte_core::operator::Operator;
+use magritte_core::value::SqlValue;
+use magritte_core::{OrderBy, Projection, RecordType};
+use magritte_db::db;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::instrument;
+
+use super::SelectStatement;
+
+/// Alias of the projection carrying the sort key values of each row
+const CURSOR_FIELD: &str = "__cursor";
+
+/// Opaque position in a paginated result set.
+///
+/// Encodes the ORDER BY key values and the record id of a row. Cursors are
+/// only valid for a query with the same ORDER BY fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CursorState {
+    keys: Vec<String>,
+    values: Vec<Value>,
+}
+
+impl Cursor {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn encode(state: &CursorState) -> Result<Self> {
+        let json = serde_json::to_vec(state)?;
+        Ok(Cursor(json.iter().map(|b| format!("{:02x}", b)).collect()))
+    }
+
+    fn decode(&self) -> Result<CursorState> {
+        let invalid = || anyhow!("Invalid pagination cursor: {}", self.0);
+        if self.0.len() % 2 != 0 {
+            return Err(invalid());
+        }
+        let bytes = (0..self.0.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(self.0.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let cursor = Cursor(s.to_string());
+        cursor.decode()?;
+        Ok(cursor)
+    }
+}
+
+/// Which way a [`PageRequest`] moves from its cursor
+#[derive(Debug, Clone, PartialEq)]
+enum Direction {
+    After(Cursor),
+    Before(Cursor),
+}
+
+/// Parameters of a single page fetch
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRequest {
+    size: usize,
+    direction: Option<Direction>,
+    with_total: bool,
+}
+
+impl PageRequest {
+    /// Requests the first page with `size` items
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            direction: None,
+            with_total: false,
+        }
+    }
+
+    /// Continues after the row the cursor points to
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        self.direction = Some(Direction::After(cursor));
+        self
+    }
+
+    /// Goes back to the rows preceding the one the cursor points to
+    pub fn before(mut self, cursor: Cursor) -> Self {
+        self.direction = Some(Direction::Before(cursor));
+        self
+    }
+
+    /// Also counts all rows matching the query
+    pub fn with_total(mut self) -> Self {
+        self.with_total = true;
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// A page of results
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the following page, `None` on the last page
+    pub next_cursor: Option<Cursor>,
+    /// Cursor for the preceding page, `None` on the first page
+    pub prev_cursor: Option<Cursor>,
+    /// Total number of matching rows, when requested
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    pub fn has_next(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    pub fn has_prev(&self) -> bool {
+        self.prev_cursor.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct CursorRow<T> {
+    #[serde(flatten)]
+    item: T,
+    #[serde(rename = "__cursor")]
+    cursor: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct CountRow {
+    count: u64,
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Fetches one page of the query using keyset pagination.
+    ///
+    /// The ORDER BY fields, followed by `id` as a tiebreaker, form the page
+    /// key. Any LIMIT or START set on the statement is replaced.
+    #[instrument(skip_all)]
+    pub async fn paginate(self, request: PageRequest) -> Result<Page<T>> {
+        if request.size == 0 {
+            bail!("Page size must be greater than zero");
+        }
+        let keys = self.page_keys()?;
+        let count = request.with_total.then(|| self.counting());
+        let (mut query, backward) = self.page_query(&keys, &request)?;
+
+        let items_fut =
+            db().execute::<CursorRow<T>>(query.build_prepared()?, query.parameters.clone());
+        let (mut rows, total) = match count {
+            Some(mut count) => {
+                let count_fut =
+                    db().execute::<CountRow>(count.build_prepared()?, count.parameters);
+                let (rows, counts) = futures::try_join!(items_fut, count_fut)?;
+                (rows, Some(counts.first().map_or(0, |c| c.count)))
+            }
+            None => (items_fut.await?, None),
+        };
+
+        let has_more = rows.len() > request.size;
+        rows.truncate(request.size);
+        if backward {
+            rows.reverse();
+        }
+
+        let key_names: Vec<String> = keys.iter().map(|(k, _)| k.clone()).collect();
+        let cursor_of = |row: &CursorRow<T>| {
+            Cursor::encode(&CursorState {
+                keys: key_names.clone(),
+                values: row.cursor.clone(),
+            })
+        };
+        let (has_next, has_prev) = if backward {
+            (true, has_more)
+        } else {
+            (has_more, request.direction.is_some())
+        };
+        let next_cursor = match rows.last() {
+            Some(row) if has_next => Some(cursor_of(row)?),
+            _ => None,
+        };
+        let prev_cursor = match rows.first() {
+            Some(row) if has_prev => Some(cursor_of(row)?),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: rows.into_iter().map(|row| row.item).collect(),
+            next_cursor,
+            prev_cursor,
+            total,
+        })
+    }
+
+    /// ORDER BY fields with their direction, ending with `id`
+    fn page_keys(&self) -> Result<Vec<(String, bool)>> {
+        if self.select_value || self.only {
+            bail!("Pagination is not supported with VALUE or ONLY");
+        }
+        if !self.group_by.is_empty() || self.all || !self.split_fields.is_empty() {
+            bail!("Pagination is not supported with GROUP or SPLIT");
+        }
+        if self.explain.is_some() {
+            bail!("Pagination is not supported with EXPLAIN");
+        }
+
+        let mut keys = Vec::with_capacity(self.order_by.len() + 1);
+        for (order, asc) in &self.order_by {
+            match order {
+                OrderBy::Random => bail!("Pagination requires a deterministic ORDER BY"),
+                OrderBy::Field(f) | OrderBy::Collate(f) | OrderBy::Numeric(f) => {
+                    keys.push((f.clone(), *asc))
+                }
+            }
+        }
+        if keys.last().map_or(true, |(k, _)| k != "id") {
+            let asc = keys.last().map_or(true, |(_, asc)| *asc);
+            keys.push(("id".to_string(), asc));
+        }
+        Ok(keys)
+    }
+
+    /// The statement fetching one extra row past the page, and whether it
+    /// runs backwards
+    fn page_query(&self, keys: &[(String, bool)], request: &PageRequest) -> Result<(Self, bool)> {
+        let mut query = self.clone();
+        let (cursor, backward) = match &request.direction {
+            None => (None, false),
+            Some(Direction::After(c)) => (Some(c), false),
+            Some(Direction::Before(c)) => (Some(c), true),
+        };
+
+        if let Some(cursor) = cursor {
+            let state = cursor.decode()?;
+            let names: Vec<&str> = keys.iter().map(|(k, _)| k.as_str()).collect();
+            if state.keys != names || state.values.len() != keys.len() {
+                bail!("Pagination cursor does not match the ORDER BY of this query");
+            }
+            let (condition, params) =
+                keyset_condition(keys, &state.values, backward, query.parameters.len());
+            query.parameters.extend(params);
+            query
+                .conditions
+                .push((condition, Operator::Raw, SqlValue::Null));
+        }
+
+        query.order_by = keys
+            .iter()
+            .map(|(k, asc)| (OrderBy::Field(k.clone()), *asc != backward))
+            .collect();
+        if query.selected_fields.is_empty() {
+            query.selected_fields.push(Projection::All);
+        }
+        let key_exprs: Vec<String> = keys.iter().map(|(k, _)| key_expr(k)).collect();
+        query.selected_fields.push(Projection::RawAs(
+            format!("[{}]", key_exprs.join(", ")),
+            CURSOR_FIELD.to_string(),
+        ));
+        query.limit = Some(request.size + 1);
+        query.start = None;
+
+        Ok((query, backward))
+    }
+}
+
+/// Record ids are compared as strings in the cursor and cast back to records
+fn key_expr(key: &str) -> String {
+    if key == "id" {
+        "<string> id".to_string()
+    } else {
+        key.to_string()
+    }
+}
+
+fn key_value(key: &str, param: &str) -> String {
+    if key == "id" {
+        format!("<record> ${}", param)
+    } else {
+        format!("${}", param)
+    }
+}
+
+/// Builds `(k1 > $a OR (k1 = $a AND k2 > $b) OR ...)`, with the comparison
+/// flipped for descending keys and when paging backwards.
+fn keyset_condition(
+    keys: &[(String, bool)],
+    values: &[Value],
+    backward: bool,
+    first_param: usize,
+) -> (String, Vec<(String, Value)>) {
+    let params: Vec<(String, Value)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (format!("p{}", first_param + i), v.clone()))
+        .collect();
+
+    let branches: Vec<String> = (0..keys.len())
+        .map(|i| {
+            let mut parts: Vec<String> = keys[..i]
+                .iter()
+                .zip(&params)
+                .map(|((k, _), (p, _))| format!("{} = {}", k, key_value(k, p)))
+                .collect();
+            let (key, asc) = &keys[i];
+            let op = if *asc != backward { ">" } else { "<" };
+            parts.push(format!("{} {} {}", key, op, key_value(key, &params[i].0)));
+            if parts.len() > 1 {
+                format!("({})", parts.join(" AND "))
+            } else {
+                parts.remove(0)
+            }
+        })
+        .collect();
+
+    (format!("({})", branches.join(" OR ")), params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Query, WhereClause};
+    use serde_json::json;
+
+    fn by_name() -> SelectStatement<User> {
+        Query::select::<User>()
+            .where_op("email", Operator::Eq, Some("a@b.c"))
+            .unwrap()
+            .order_by_field("name", true)
+    }
+
+    fn alice_cursor() -> Cursor {
+        Cursor::encode(&CursorState {
+            keys: vec!["name".into(), "id".into()],
+            values: vec![json!("Alice"), json!("users:alice")],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let state = CursorState {
+            keys: vec!["name".into(), "id".into()],
+            values: vec![json!("Alice"), json!("users:alice")],
+        };
+        let cursor = Cursor::encode(&state).unwrap();
+        assert!(cursor.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(cursor.decode().unwrap(), state);
+        assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        assert!("zz".parse::<Cursor>().is_err());
+        assert!("abc".parse::<Cursor>().is_err());
+        assert!("7b7d".parse::<Cursor>().is_err());
+    }
+
+    #[test]
+    fn test_keyset_condition_forward() {
+        let keys = vec![("name".to_string(), true), ("id".to_string(), true)];
+        let (condition, params) =
+            keyset_condition(&keys, &[json!("Alice"), json!("users:alice")], false, 2);
+        assert_eq!(
+            condition,
+            "(name > $p2 OR (name = $p2 AND id > <record> $p3))"
+        );
+        assert_eq!(params[0], ("p2".to_string(), json!("Alice")));
+        assert_eq!(params[1], ("p3".to_string(), json!("users:alice")));
+    }
+
+    #[test]
+    fn test_keyset_condition_backward_desc() {
+        let keys = vec![("created_at".to_string(), false), ("id".to_string(), false)];
+        let (condition, _) = keyset_condition(&keys, &[json!(10), json!("users:a")], true, 0);
+        assert_eq!(
+            condition,
+            "(created_at > $p0 OR (created_at = $p0 AND id > <record> $p1))"
+        );
+    }
+
+    #[test]
+    fn test_page_query_first_page() {
+        let select = by_name();
+        let keys = select.page_keys().unwrap();
+        let (query, backward) = select.page_query(&keys, &PageRequest::new(10)).unwrap();
+        assert!(!backward);
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT *, [name, <string> id] AS __cursor FROM users WHERE email = $p0 \
+             ORDER BY name ASC, id ASC LIMIT 11;"
+        );
+        assert_eq!(query.parameters, vec![("p0".to_string(), json!("a@b.c"))]);
+    }
+
+    #[test]
+    fn test_page_query_after_cursor() {
+        let select = by_name();
+        let keys = select.page_keys().unwrap();
+        let request = PageRequest::new(10).after(alice_cursor());
+        let (query, backward) = select.page_query(&keys, &request).unwrap();
+        assert!(!backward);
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT *, [name, <string> id] AS __cursor FROM users WHERE email = $p0 \
+             AND (name > $p1 OR (name = $p1 AND id > <record> $p2)) \
+             ORDER BY name ASC, id ASC LIMIT 11;"
+        );
+        assert_eq!(
+            query.parameters,
+            vec![
+                ("p0".to_string(), json!("a@b.c")),
+                ("p1".to_string(), json!("Alice")),
+                ("p2".to_string(), json!("users:alice")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_page_query_before_cursor() {
+        let select = by_name();
+        let keys = select.page_keys().unwrap();
+        let request = PageRequest::new(10).before(alice_cursor());
+        let (query, backward) = select.page_query(&keys, &request).unwrap();
+        assert!(backward);
+        assert_eq!(
+            query.build().unwrap(),
+            "SELECT *, [name, <string> id] AS __cursor FROM users WHERE email = $p0 \
+             AND (name < $p1 OR (name = $p1 AND id < <record> $p2)) \
+             ORDER BY name DESC, id DESC LIMIT 11;"
+        );
+        assert_eq!(
+            query.parameters,
+            vec![
+                ("p0".to_string(), json!("a@b.c")),
+                ("p1".to_string(), json!("Alice")),
+                ("p2".to_string(), json!("users:alice")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_page_query_rejects_foreign_cursor() {
+        let select = Query::select::<User>().order_by_field("email", true);
+        let keys = select.page_keys().unwrap();
+        let request = PageRequest::new(10).after(alice_cursor());
+        assert!(select.page_query(&keys, &request).is_err());
+    }
+}
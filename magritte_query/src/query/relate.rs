@@ -1,10 +1,14 @@
 use std::time::Duration;
 
-use crate::HasReturns;
+use crate::{Fingerprint, HasParams, HasReturns};
+use crate::backend::duration::duration_to_sql;
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::intercept;
 use anyhow::Result;
 use magritte_core::transaction::Transactional;
 use magritte_core::ReturnType;
-use magritte_db::db;
+use magritte_db::{db, StatementKind};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 
@@ -15,8 +19,9 @@ pub struct RelateStatement {
     to_record: String,
     edge_table: String,
     only: bool,
-    content: Option<Value>,
-    set_fields: Vec<(String, Value)>,
+    content: Option<String>,
+    set_fields: Vec<(String, String)>,
+    parameters: Vec<(String, Value)>,
     return_type: Option<ReturnType>,
     return_fields: Option<Vec<String>>,
     timeout: Option<Duration>,
@@ -33,17 +38,25 @@ impl RelateStatement {
 
     /// Set content for the relation
     pub fn content<V: Serialize>(mut self, content: V) -> anyhow::Result<Self> {
-        self.content = Some(serde_json::to_value(content)?);
+        let param = self.bind(content)?;
+        self.content = Some(param);
         Ok(self)
     }
 
     /// Set a field value
     pub fn set<V: Serialize>(mut self, field: &str, value: V) -> anyhow::Result<Self> {
-        self.set_fields
-            .push((field.to_string(), serde_json::to_value(value)?));
+        let param = self.bind(value)?;
+        self.set_fields.push((field.to_string(), param));
         Ok(self)
     }
 
+    fn bind<V: Serialize>(&mut self, value: V) -> anyhow::Result<String> {
+        let param = format!("p{}", self.parameters.len());
+        self.parameters
+            .push((param.clone(), serde_json::to_value(value)?));
+        Ok(param)
+    }
+
     /// Add timeout duration
     pub fn timeout(mut self, duration: Duration) -> Self {
         self.timeout = Some(duration);
@@ -90,7 +103,7 @@ impl RelateStatement {
 
         // Add CONTENT if present
         if let Some(content) = &self.content {
-            query.push_str(&format!(" CONTENT {}", content));
+            query.push_str(&format!(" CONTENT ${}", content));
         }
 
         // Add SET fields
@@ -99,7 +112,7 @@ impl RelateStatement {
             let fields: Vec<String> = self
                 .set_fields
                 .iter()
-                .map(|(field, value)| format!("{} = {}", field, value))
+                .map(|(field, param)| format!("{} = ${}", field, param))
                 .collect();
             query.push_str(&fields.join(", "));
         }
@@ -121,7 +134,7 @@ impl RelateStatement {
 
         // Add TIMEOUT
         if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
 
         // Add PARALLEL
@@ -133,8 +146,61 @@ impl RelateStatement {
         Ok(query)
     }
 
+    /// Applies the registered interceptors, returning the comment they tag
+    /// the statement with
+    pub(crate) fn prepare(&mut self) -> Result<String> {
+        intercept::apply_unfiltered(
+            &mut self.parameters,
+            StatementKind::Relate,
+            &self.edge_table,
+            true,
+        )
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+
     pub async fn execute(self) -> anyhow::Result<Vec<serde_json::Value>> {
-        db().execute(self.build()?, vec![]).await
+        self.fetch_as().await
+    }
+
+    /// Execute the RELATE query and decode the created edges into `R`
+    pub async fn fetch_as<R>(mut self) -> anyhow::Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        db().execute(self.build_prepared()?, self.parameters).await
+    }
+}
+
+impl Fingerprint for RelateStatement {
+    fn fingerprint(&self) -> u64 {
+        ShapeHasher::new(&self.edge_table)
+            .part(&self.only)
+            .part(&self.content)
+            .part(&self.set_fields)
+            .part(&self.return_type)
+            .part(&self.return_fields)
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        ShapeHasher::new(&self.edge_table)
+            .part(&self.from_record)
+            .part(&self.to_record)
+            .part(&self.only)
+            .part(&self.content)
+            .part(&self.set_fields)
+            .part(&self.return_type)
+            .part(&self.return_fields)
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .finish()
     }
 }
 
@@ -143,6 +209,16 @@ impl HasReturns for RelateStatement {
         &mut self.return_type
     }
 }
+impl HasParams for RelateStatement {
+    fn params(&self) -> &Vec<(String, Value)> {
+        &self.parameters
+    }
+
+    fn params_mut(&mut self) -> &mut Vec<(String, Value)> {
+        &mut self.parameters
+    }
+}
+
 impl Transactional for RelateStatement {
     fn is_transaction(&self) -> bool {
         self.in_transaction
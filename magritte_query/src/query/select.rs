@@ -7,16 +7,24 @@ use std::marker::PhantomData;
 use std::time::Duration;
 
 use crate::{
-    Callable, CanCallFunctions, CountFunction, FromTarget, HasConditions, HasLetConditions,
-    HasParams, HasProjections, HasVectorConditions, VectorSearchable,
+    Callable, CanCallFunctions, CountFunction, Fingerprint, FromTarget, HasConditions,
+    HasLetConditions, HasParams, HasProjections, HasVectorConditions,
 };
+use crate::backend::duration::duration_to_sql;
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::idiom::IntoIdiom;
+use crate::backend::intercept;
+use crate::backend::policy::{enforce_select, SelectShape};
+use crate::backend::query_result::targets_records;
+use crate::backend::strict::{check_fields, is_strict_mode};
+use crate::backend::timestamp::{datetime_literal, IntoTimestamp};
 use anyhow::{bail, Result};
 use magritte_core::operator::Operator;
 use magritte_core::value::SqlValue;
 use magritte_core::{
     Indexable, OrderBy, Projection, RangeTarget, RecordType, SurrealId, VectorCondition,
 };
-use magritte_db::db;
+use magritte_db::{db, StatementKind};
 use serde::Serialize;
 use serde_json::Value;
 use tracing::instrument;
@@ -49,6 +57,7 @@ where
     pub(crate) explain: Option<bool>,
     pub(crate) version: Option<String>,
     pub(crate) let_statements: Vec<(String, String)>,
+    pub(crate) allow_full_table: bool,
     phantom_data: PhantomData<T>,
 }
 
@@ -81,6 +90,7 @@ where
             explain: None,
             version: None,
             let_statements: vec![],
+            allow_full_table: false,
             phantom_data: PhantomData,
         }
     }
@@ -92,14 +102,15 @@ where
     T: RecordType,
 {
     /// Select specific fields, optionally with aliases
-    #[instrument(skip(self))]
-    pub fn field(mut self, expr: &str, alias: Option<&str>) -> Self {
+    #[instrument(skip(self, expr))]
+    pub fn field(mut self, expr: impl IntoIdiom, alias: Option<&str>) -> Self {
+        let expr = expr.into_idiom().bind(&mut self.parameters);
         if let Some(alias) = alias {
             self.selected_fields
-                .push(Projection::FieldAs(expr.to_string(), alias.to_string()));
+                .push(Projection::FieldAs(expr, alias.to_string()));
         } else {
             self.selected_fields
-                .push(Projection::Field(expr.to_string()));
+                .push(Projection::Field(expr));
         }
         self
     }
@@ -197,10 +208,10 @@ where
     }
 
     /// Add ORDER BY clause
-    #[instrument(skip(self))]
-    pub fn order_by_field(mut self, field: &str, ascending: bool) -> Self {
-        self.order_by
-            .push((OrderBy::Field(field.to_string()), ascending));
+    #[instrument(skip(self, field))]
+    pub fn order_by_field(mut self, field: impl IntoIdiom, ascending: bool) -> Self {
+        let field = field.into_idiom().bind(&mut self.parameters);
+        self.order_by.push((OrderBy::Field(field), ascending));
         self
     }
 
@@ -228,9 +239,10 @@ where
     }
 
     /// Add GROUP BY clause
-    #[instrument(skip(self))]
-    pub fn group_by(mut self, field: &str) -> Self {
-        self.group_by.push(field.to_string());
+    #[instrument(skip(self, field))]
+    pub fn group_by(mut self, field: impl IntoIdiom) -> Self {
+        let field = field.into_idiom().bind(&mut self.parameters);
+        self.group_by.push(field);
         self
     }
 
@@ -280,6 +292,12 @@ where
         self
     }
 
+    /// Exempts the statement from the safety policy's LIMIT requirement
+    pub fn allow_full_table(mut self) -> Self {
+        self.allow_full_table = true;
+        self
+    }
+
     /// Simple count, returns 1
     pub fn count(mut self) -> Self {
         self.selected_fields.push(Projection::RawAs(
@@ -334,7 +352,9 @@ where
         self
     }
 
-    /// Enable query explanation
+    /// Enable query explanation. An explained query returns its plan rather
+    /// than records, so it runs through [`plan`](Self::plan), which reads the
+    /// result as a [`QueryPlan`](crate::QueryPlan); `execute` rejects it.
     #[instrument(skip(self))]
     pub fn explain(mut self, full: bool) -> Self {
         self.explain = Some(full);
@@ -370,10 +390,16 @@ where
         self
     }
 
-    /// Add VERSION clause
-    pub fn version(mut self, timestamp: &str) -> Self {
-        self.version = Some(format!("d'{}'", timestamp));
-        self
+    /// Add VERSION clause, reading the records as they were at `timestamp`.
+    /// Fails if `timestamp` is a string that is not an RFC 3339 datetime.
+    pub fn version(mut self, timestamp: impl IntoTimestamp) -> Result<Self> {
+        self.version = Some(datetime_literal(timestamp)?);
+        Ok(self)
+    }
+
+    /// Time-travel read, same as [`SelectStatement::version`]
+    pub fn as_of(self, timestamp: impl IntoTimestamp) -> Result<Self> {
+        self.version(timestamp)
     }
 
     /// Add a range to the target Table
@@ -398,6 +424,15 @@ where
         self
     }
 
+    /// Add a range of any key kind to the target Table, e.g. over composite
+    /// array keys
+    pub fn range_target(mut self, range: RangeTarget) -> Self {
+        self.targets
+            .get_or_insert_with(Vec::new)
+            .push(FromTarget::Range(T::table_name().to_string(), range));
+        self
+    }
+
     /// Filter array values in a field
     ///
     /// # Examples
@@ -438,7 +473,57 @@ where
         Self::default()
     }
 
+    /// Checks the referenced fields against the schema in strict mode.
+    /// ORDER BY and GROUP BY may also name projection aliases.
+    fn check_strict(&self) -> Result<()> {
+        if !is_strict_mode() {
+            return Ok(());
+        }
+        let mut fields: Vec<&str> = Vec::new();
+        let mut aliases: Vec<&str> = Vec::new();
+        for projection in &self.selected_fields {
+            match projection {
+                Projection::Field(field) => fields.push(field),
+                Projection::FieldAs(field, alias) => {
+                    fields.push(field);
+                    aliases.push(alias);
+                }
+                Projection::Fields(list) => fields.extend(list.iter().map(String::as_str)),
+                Projection::FieldsAs(list) => {
+                    for (field, alias) in list {
+                        fields.push(field);
+                        aliases.push(alias);
+                    }
+                }
+                Projection::RawAs(_, alias)
+                | Projection::Subquery(_, Some(alias))
+                | Projection::RelationWildcardAs(alias)
+                | Projection::RelationInverseWildcardAs(alias)
+                | Projection::RelationBidirectionalWildcardAs(alias) => aliases.push(alias),
+                _ => {}
+            }
+        }
+        fields.extend(
+            self.conditions
+                .iter()
+                .filter(|(_, op, _)| *op != Operator::Raw)
+                .map(|(field, _, _)| field.as_str()),
+        );
+        fields.extend(self.omitted_fields.iter().flatten().map(String::as_str));
+        fields.extend(self.split_fields.iter().map(String::as_str));
+        let ordered = self.order_by.iter().filter_map(|(order, _)| match order {
+            OrderBy::Field(field) | OrderBy::Collate(field) | OrderBy::Numeric(field) => {
+                Some(field.as_str())
+            }
+            _ => None,
+        });
+        let grouped = self.group_by.iter().map(String::as_str);
+        fields.extend(ordered.chain(grouped).filter(|field| !aliases.contains(field)));
+        check_fields(T::table_name(), fields)
+    }
+
     pub fn build(&self) -> Result<String> {
+        self.check_strict()?;
         let mut query = String::new();
         let mut params = self.parameters.clone();
         if !self.let_statements.is_empty() {
@@ -451,13 +536,6 @@ where
             query.push(' ')
         }
         query.push_str("SELECT ");
-        // Add EXPLAIN if needed
-        if let Some(full) = self.explain {
-            query.push_str("EXPLAIN ");
-            if full {
-                query.push_str("FULL ");
-            }
-        }
         // Add VALUE if specified
         if self.select_value {
             query.push_str("VALUE ");
@@ -518,11 +596,21 @@ where
             let conditions: Vec<String> = self
                 .conditions
                 .iter()
-                .map(|(field, op, value)| format!("{} {} {}", field, String::from(*op), value))
+                .map(|(field, op, value)| {
+                    format!("{} {} {}", field, String::from(*op), value)
+                        .trim_end()
+                        .to_string()
+                })
                 .collect();
             query.push_str(&conditions.join(" AND "));
         }
 
+        // Add SPLIT
+        if !self.split_fields.is_empty() {
+            query.push_str(" SPLIT ");
+            query.push_str(&self.split_fields.join(", "));
+        }
+
         // Add GROUP BY
         if !self.group_by.is_empty() {
             query.push_str(" GROUP BY ");
@@ -558,12 +646,6 @@ where
             }
         }
 
-        // Add SPLIT
-        if !self.split_fields.is_empty() {
-            query.push_str(" SPLIT ");
-            query.push_str(&self.split_fields.join(", "));
-        }
-
         // Add FETCH
         if !self.fetch_fields.is_empty() {
             query.push_str(" FETCH ");
@@ -576,9 +658,14 @@ where
             }
         }
 
+        // Add VERSION
+        if let Some(ver) = &self.version {
+            query.push_str(&format!(" VERSION {}", ver));
+        }
+
         // Add TIMEOUT
         if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
         }
 
         // Add PARALLEL and TEMPFILES
@@ -589,25 +676,120 @@ where
             query.push_str(" TEMPFILES");
         }
 
-        // Add vector conditions
-        if !self.vector_conditions.is_empty() {
-            query.push_str(&Self::build_vector_conditions(&self.vector_conditions));
-        }
-
-        // Add VERSION
-        if let Some(ver) = &self.version {
-            query.push_str(&format!(" VERSION {}", ver));
+        // Add EXPLAIN
+        if let Some(full) = self.explain {
+            query.push_str(" EXPLAIN");
+            if full {
+                query.push_str(" FULL");
+            }
         }
 
         query.push(';');
         Ok(query)
     }
 
-    #[instrument(skip_all)]
-    pub async fn execute(self) -> Result<Vec<T>> {
-        db().execute(self.build()?, self.parameters).await
+    #[instrument(skip_all, fields(fingerprint = self.fingerprint()))]
+    pub async fn execute(mut self) -> Result<Vec<T>> {
+        if self.explain.is_some() {
+            bail!("An EXPLAIN returns a query plan rather than records, run it with .plan()");
+        }
+        let query = self.build_prepared()?;
+        db().execute(query, self.parameters).await
+    }
+
+    /// Checks the safety policy and applies the registered interceptors,
+    /// returning the comment they tag the statement with
+    pub(crate) fn prepare(&mut self) -> Result<String> {
+        let shape = self.shape();
+        let targets_records = shape.targets_records;
+        enforce_select(shape)?;
+        intercept::apply(self, StatementKind::Select, T::table_name(), targets_records)
+    }
+
+    /// What the safety policy checks
+    pub(crate) fn shape(&self) -> SelectShape<'_> {
+        SelectShape {
+            table: T::table_name(),
+            targets_records: self.with_id.is_some() || targets_records(&self.targets),
+            limit: self.limit,
+            group_all: self.all,
+            fetch_fields: &self.fetch_fields,
+            projections: &self.selected_fields,
+            // A plain EXPLAIN only plans the query, EXPLAIN FULL also runs it
+            allow_full_table: self.allow_full_table || self.explain == Some(false),
+        }
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+}
+
+impl<T> Fingerprint for SelectStatement<T>
+where
+    T: RecordType,
+{
+    fn fingerprint(&self) -> u64 {
+        // Record ids, targets, LIMIT, START, VERSION and the vectors only
+        // count by presence, so queries that differ in those literals share a
+        // fingerprint
+        ShapeHasher::new(T::table_name())
+            .part(&self.let_statements)
+            .part(&self.select_value)
+            .part(&self.selected_fields)
+            .part(&self.omitted_fields)
+            .part(&self.only)
+            .part(&self.targets.as_ref().map(Vec::len))
+            .part(&self.with_id.is_some())
+            .part(&self.with_index)
+            .part(&self.conditions)
+            .part(&self.vector_conditions.len())
+            .part(&self.split_fields)
+            .part(&self.group_by)
+            .part(&self.all)
+            .part(&self.order_by)
+            .part(&self.limit.is_some())
+            .part(&self.start.is_some())
+            .part(&self.fetch_fields)
+            .part(&self.version.is_some())
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .part(&self.tempfiles)
+            .part(&self.explain)
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        // The vectors are bound as parameters, their conditions are rendered
+        // into `conditions`
+        ShapeHasher::new(T::table_name())
+            .part(&self.let_statements)
+            .part(&self.select_value)
+            .part(&self.selected_fields)
+            .part(&self.omitted_fields)
+            .part(&self.only)
+            .part(&self.targets)
+            .part(&self.with_id)
+            .part(&self.with_index)
+            .part(&self.conditions)
+            .part(&self.split_fields)
+            .part(&self.group_by)
+            .part(&self.all)
+            .part(&self.order_by)
+            .part(&self.limit)
+            .part(&self.start)
+            .part(&self.fetch_fields)
+            .part(&self.version)
+            .part(&self.timeout)
+            .part(&self.parallel)
+            .part(&self.tempfiles)
+            .part(&self.explain)
+            .finish()
     }
 }
+
 impl<T> HasVectorConditions for SelectStatement<T>
 where
     T: RecordType,
@@ -0,0 +1,447 @@
+//! Transactions
+//!
+//! [`TransactionStatement`] wraps its steps in `BEGIN TRANSACTION` and
+//! `COMMIT TRANSACTION` (or `CANCEL TRANSACTION`) and sends them as a single
+//! query, so the steps are applied atomically. A step can bind its output to a
+//! `LET` variable that later steps reference, and the output of every step can
+//! be read back from the [`TransactionResult`]. A transaction ended with
+//! [`rollback`](TransactionStatement::rollback) still executes, and its result
+//! reports [`is_cancelled`](TransactionResult::is_cancelled) with no step
+//! output to take.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use magritte_query::*;
+//!
+//! let mut result = Query::begin()
+//!     .let_("order", Query::create::<Order>().only().content(&order)?)
+//!     .then(
+//!         Query::relate()
+//!             .from_record(&user_id.to_string())
+//!             .edge_table("purchased")
+//!             .to_record("$order.id"),
+//!     )
+//!     .commit()
+//!     .execute(db())
+//!     .await?;
+//!
+//! let order: Option<Order> = result.take_one("order")?;
+//! let edges: Vec<Purchased> = result.take(1)?;
+//! ```
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use magritte_db::SurrealDB;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use surrealdb::Response;
+use tracing::instrument;
+
+use super::StatementBuilder;
+use crate::backend::params::merge_params;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    query: String,
+    params: Vec<(String, Value)>,
+    binding: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum End {
+    Commit,
+    Cancel,
+}
+
+/// Builder for `BEGIN TRANSACTION ... COMMIT TRANSACTION`
+#[derive(Debug, Clone, Default)]
+pub struct TransactionStatement {
+    steps: Vec<Step>,
+    end: Option<End>,
+    error: Option<String>,
+}
+
+impl TransactionStatement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds raw SurrealQL as a step
+    pub fn raw(self, query: &str) -> Self {
+        self.raw_with_params(query, vec![])
+    }
+
+    /// Adds raw SurrealQL with its bound parameters as a step
+    pub fn raw_with_params(mut self, query: &str, params: Vec<(String, Value)>) -> Self {
+        self.steps.push(Step {
+            query: query.trim().to_string(),
+            params,
+            binding: None,
+        });
+        self
+    }
+
+    /// Adds a statement as a step. It is prepared like `execute` would, so
+    /// the safety policy and the registered interceptors apply to it.
+    pub fn then<S: StatementBuilder>(mut self, mut statement: S) -> Self {
+        match statement.build_prepared() {
+            Ok(query) => self.raw_with_params(&query, statement.with_params()),
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+                self
+            }
+        }
+    }
+
+    /// Adds a statement as a step and binds its output to `$var`, rendering
+    /// `LET $var = (statement); $var;`
+    pub fn let_<S: StatementBuilder>(mut self, var: &str, mut statement: S) -> Self {
+        let var = var.trim_start_matches('$');
+        if self.steps.iter().any(|s| s.binding.as_deref() == Some(var)) {
+            self.error
+                .get_or_insert(format!("Variable ${} is bound twice", var));
+            return self;
+        }
+        match statement.build_prepared() {
+            Ok(query) => {
+                self.steps.push(Step {
+                    query: query.trim().trim_end_matches(';').to_string(),
+                    params: statement.with_params(),
+                    binding: Some(var.to_string()),
+                });
+                self
+            }
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+                self
+            }
+        }
+    }
+
+    /// Ends the transaction with `COMMIT TRANSACTION`
+    pub fn commit(mut self) -> Self {
+        self.end = Some(End::Commit);
+        self
+    }
+
+    /// Ends the transaction with `CANCEL TRANSACTION`, discarding all steps
+    pub fn rollback(mut self) -> Self {
+        self.end = Some(End::Cancel);
+        self
+    }
+
+    /// Alias of [`rollback`](Self::rollback)
+    pub fn cancel(self) -> Self {
+        self.rollback()
+    }
+
+    /// Renders every step with parameters renumbered across steps, and the
+    /// index in the response of each step's result
+    fn render(&self) -> Result<(Vec<String>, Vec<(String, Value)>, Vec<usize>)> {
+        if let Some(error) = &self.error {
+            bail!("{}", error);
+        }
+        let statements: Vec<(String, Vec<(String, Value)>)> = self
+            .steps
+            .iter()
+            .map(|step| (step.query.clone(), step.params.clone()))
+            .collect();
+        let (queries, params) = merge_params(&statements);
+
+        let mut rendered = Vec::with_capacity(queries.len());
+        let mut indices = Vec::with_capacity(queries.len());
+        let mut next_index = 0;
+        for (step, query) in self.steps.iter().zip(queries) {
+            match &step.binding {
+                Some(var) => {
+                    rendered.push(format!("LET ${} = ({}); ${};", var, query, var));
+                    next_index += 2;
+                }
+                None => {
+                    let query = if query.ends_with(';') {
+                        query
+                    } else {
+                        format!("{};", query)
+                    };
+                    next_index += count_statements(&query).max(1);
+                    rendered.push(query);
+                }
+            }
+            indices.push(next_index - 1);
+        }
+        Ok((rendered, params, indices))
+    }
+
+    pub fn build(&self) -> Result<String> {
+        let (statements, _, _) = self.render()?;
+        let end = match self.end.unwrap_or(End::Commit) {
+            End::Commit => "COMMIT TRANSACTION;",
+            End::Cancel => "CANCEL TRANSACTION;",
+        };
+        let mut query = String::from("BEGIN TRANSACTION;");
+        for statement in statements {
+            query.push(' ');
+            query.push_str(&statement);
+        }
+        query.push(' ');
+        query.push_str(end);
+        Ok(query)
+    }
+
+    /// Runs the transaction through magritte's executor. If any step fails
+    /// the whole transaction is cancelled and the error is returned. A
+    /// transaction ended with [`rollback`](Self::rollback) returns a result
+    /// marked as cancelled instead of the cancellation errors.
+    #[instrument(skip_all)]
+    pub async fn execute(self, db: &SurrealDB) -> Result<TransactionResult> {
+        let (_, params, indices) = self.render()?;
+        let query = self.build()?;
+        let cancelled = self.end == Some(End::Cancel);
+        let response = if cancelled {
+            db.query_unchecked(query, params).await?
+        } else {
+            db.query(query, params).await?
+        };
+        let bindings = self
+            .steps
+            .iter()
+            .zip(&indices)
+            .filter_map(|(step, index)| step.binding.clone().map(|var| (var, *index)))
+            .collect();
+        Ok(TransactionResult {
+            response,
+            indices,
+            bindings,
+            cancelled,
+        })
+    }
+}
+
+impl StatementBuilder for TransactionStatement {
+    fn build(&self) -> Result<String> {
+        self.build()
+    }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.render().map(|(_, params, _)| params).unwrap_or_default()
+    }
+}
+
+/// A step of a transaction, by position or by its `LET` variable
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepRef {
+    Index(usize),
+    Var(String),
+}
+
+impl From<usize> for StepRef {
+    fn from(index: usize) -> Self {
+        StepRef::Index(index)
+    }
+}
+
+impl From<&str> for StepRef {
+    fn from(var: &str) -> Self {
+        StepRef::Var(var.trim_start_matches('$').to_string())
+    }
+}
+
+/// Results of an executed transaction
+#[derive(Debug)]
+pub struct TransactionResult {
+    response: Response,
+    indices: Vec<usize>,
+    bindings: HashMap<String, usize>,
+    cancelled: bool,
+}
+
+impl TransactionResult {
+    fn index(&self, step: StepRef) -> Result<usize> {
+        if self.cancelled {
+            bail!("Transaction was cancelled, its steps have no output");
+        }
+        match step {
+            StepRef::Index(i) => self
+                .indices
+                .get(i)
+                .copied()
+                .ok_or_else(|| anyhow!("Transaction has no step {}", i)),
+            StepRef::Var(var) => self
+                .bindings
+                .get(&var)
+                .copied()
+                .ok_or_else(|| anyhow!("Transaction has no step bound to ${}", var)),
+        }
+    }
+
+    /// Takes the rows returned by a step
+    pub fn take<R>(&mut self, step: impl Into<StepRef>) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned,
+    {
+        let index = self.index(step.into())?;
+        self.response
+            .take(index)
+            .map_err(|e| anyhow!("Failed to process transaction step: {}", e))
+    }
+
+    /// Takes the single row returned by a step, e.g. a `CREATE ONLY`
+    pub fn take_one<R>(&mut self, step: impl Into<StepRef>) -> Result<Option<R>>
+    where
+        R: DeserializeOwned,
+    {
+        let index = self.index(step.into())?;
+        self.response
+            .take(index)
+            .map_err(|e| anyhow!("Failed to process transaction step: {}", e))
+    }
+
+    /// Whether the transaction ended with `CANCEL TRANSACTION`
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Number of steps in the transaction
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Counts top-level statements, ignoring `;` inside strings and blocks
+fn count_statements(query: &str) -> usize {
+    let mut count = 0;
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut pending = false;
+    let mut chars = query.chars();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                pending = true;
+            }
+            '{' | '(' | '[' => {
+                depth += 1;
+                pending = true;
+            }
+            '}' | ')' | ']' => {
+                depth -= 1;
+                pending = true;
+            }
+            ';' if depth == 0 => {
+                if pending {
+                    count += 1;
+                }
+                pending = false;
+            }
+            c if !c.is_whitespace() => pending = true,
+            _ => {}
+        }
+    }
+    count + usize::from(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RelateStatement, ReturnStatement, Validate};
+    use serde_json::json;
+
+    #[test]
+    fn test_let_binding() {
+        let tx = TransactionStatement::new()
+            .let_("total", ReturnStatement::new("10"))
+            .raw("RETURN $total * 2")
+            .commit();
+        assert_eq!(
+            tx.build().unwrap(),
+            "BEGIN TRANSACTION; LET $total = (RETURN 10); $total; RETURN $total * 2; \
+             COMMIT TRANSACTION;"
+        );
+        let (_, _, indices) = tx.render().unwrap();
+        assert_eq!(indices, vec![1, 2]);
+        assert!(TransactionStatement::new()
+            .let_("x", ReturnStatement::new("1"))
+            .let_("$x", ReturnStatement::new("2"))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_let_binding_parses() {
+        let tx = TransactionStatement::new()
+            .let_("alice", ReturnStatement::new("users:alice"))
+            .then(
+                RelateStatement::new()
+                    .from_record("$alice")
+                    .edge_table("follows")
+                    .to_record("users:bob")
+                    .set("since", "2024")
+                    .unwrap(),
+            )
+            .commit();
+        tx.validate().unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_steps_share_parameters() {
+        let tx = TransactionStatement::new()
+            .raw_with_params(
+                "LET $order = (CREATE ONLY orders SET total = $p0)",
+                vec![("p0".into(), json!(10))],
+            )
+            .raw_with_params(
+                "RELATE users:alice->purchased->$order SET qty = $p0",
+                vec![("p0".into(), json!(2))],
+            )
+            .commit();
+        assert_eq!(
+            tx.build().unwrap(),
+            "BEGIN TRANSACTION; LET $order = (CREATE ONLY orders SET total = $p0); \
+             RELATE users:alice->purchased->$order SET qty = $p1; COMMIT TRANSACTION;"
+        );
+        assert_eq!(tx.with_params()[1], ("p1".to_string(), json!(2)));
+    }
+
+    #[test]
+    fn test_params_renumbered_by_name() {
+        let params: Vec<(String, Value)> = (0..11).map(|i| (format!("p{}", i), json!(i))).collect();
+        let tx = TransactionStatement::new()
+            .raw_with_params("RETURN $p0", vec![("p0".into(), json!("x"))])
+            .raw_with_params("RETURN [$p1, $p10]", params);
+        let query = tx.build().unwrap();
+        assert!(query.contains("RETURN [$p2, $p11];"));
+    }
+
+    #[test]
+    fn test_step_indices() {
+        let tx = TransactionStatement::new()
+            .raw("DEFINE TABLE a; DEFINE TABLE b")
+            .raw("RETURN 'a;b'")
+            .rollback();
+        let (_, _, indices) = tx.render().unwrap();
+        assert_eq!(indices, vec![1, 2]);
+        assert!(tx.build().unwrap().ends_with("CANCEL TRANSACTION;"));
+    }
+
+    #[test]
+    fn test_count_statements() {
+        assert_eq!(count_statements("SELECT * FROM a;"), 1);
+        assert_eq!(count_statements("LET $x = 1; SELECT * FROM a WHERE b = ';'"), 2);
+        assert_eq!(count_statements("IF true THEN { RETURN 1; } END;"), 1);
+    }
+}
@@ -0,0 +1,669 @@
+//! Typestate builders
+//!
+//! The fluent builders check some invariants only in `build()`: `ONLY` needs
+//! `LIMIT 1` on SELECT and `RETURN BEFORE` on DELETE, `START` is meaningless
+//! without `LIMIT`, `EXPLAIN` cannot be combined with `FETCH`, and an index
+//! needs a name and a table. The wrappers here track those choices in their
+//! type parameters so invalid combinations fail to compile. Each wraps the
+//! regular builder, which is still available through `into_inner()`.
+//!
+//! ```rust,ignore
+//! let one = Query::select::<User>()
+//!     .typed()
+//!     .where_op("email", Operator::Eq, Some("a@b.c"))?
+//!     .only()          // ONLY ... LIMIT 1, execute() returns Option<User>
+//!     .fetch(&["orders"]);
+//!
+//! Query::select::<User>().typed().start(10);             // error: no LIMIT
+//! Query::select::<User>().typed().explain(true).fetch(&["orders"]); // error
+//! Define::index_typed().name("email_idx").finish();      // error: no table
+//! ```
+
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use anyhow::Result;
+use magritte_core::operator::Operator;
+use magritte_core::value::SqlValue;
+use magritte_core::{Projection, RecordType, ReturnType, SurrealId};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{CreateStatement, DeleteStatement, QueryPlan, SelectStatement, StatementBuilder};
+use crate::define::DefineIndexStatement;
+use crate::{HasConditions, HasParams, HasProjections, HasReturns, IntoIdiom};
+
+/// Returns every matching row
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Many;
+/// `ONLY`, returns a single row
+#[derive(Debug, Clone, Copy, Default)]
+pub struct One;
+/// `EXPLAIN`, returns the query plan
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Explained;
+/// No `LIMIT` yet
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLimit;
+/// `LIMIT` is set, so `START` can be used
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limited;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Many {}
+    impl Sealed for super::One {}
+    impl Sealed for super::Explained {}
+}
+
+/// Modes whose results can `FETCH` related records
+pub trait CanFetch: sealed::Sealed {}
+impl CanFetch for Many {}
+impl CanFetch for One {}
+
+/// Modes that accept a `LIMIT`
+pub trait CanLimit: sealed::Sealed {}
+impl CanLimit for Many {}
+impl CanLimit for Explained {}
+
+/// SELECT whose mode (`M`) and limit (`L`) are checked at compile time
+#[derive(Debug, Clone)]
+pub struct TypedSelect<T, M = Many, L = NoLimit>
+where
+    T: RecordType,
+{
+    inner: SelectStatement<T>,
+    _state: PhantomData<(M, L)>,
+}
+
+impl<T> SelectStatement<T>
+where
+    T: RecordType,
+{
+    /// Switches to the [`TypedSelect`] builder
+    pub fn typed(self) -> TypedSelect<T> {
+        TypedSelect {
+            inner: self,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<T, M, L> TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    fn map(self, f: impl FnOnce(SelectStatement<T>) -> SelectStatement<T>) -> Self {
+        Self {
+            inner: f(self.inner),
+            _state: PhantomData,
+        }
+    }
+
+    fn into_state<M2, L2>(self) -> TypedSelect<T, M2, L2> {
+        TypedSelect {
+            inner: self.inner,
+            _state: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> SelectStatement<T> {
+        self.inner
+    }
+
+    pub fn field(self, expr: impl IntoIdiom, alias: Option<&str>) -> Self {
+        self.map(|s| s.field(expr, alias))
+    }
+
+    pub fn fields(self, fields: &[&str]) -> Self {
+        self.map(|s| s.fields(fields))
+    }
+
+    pub fn omit(self, fields: Vec<&str>) -> Self {
+        self.map(|s| s.omit(fields))
+    }
+
+    pub fn where_id(self, id: SurrealId<T>) -> Self {
+        self.map(|s| s.where_id(id))
+    }
+
+    pub fn order_by_field(self, field: impl IntoIdiom, ascending: bool) -> Self {
+        self.map(|s| s.order_by_field(field, ascending))
+    }
+
+    pub fn group_by(self, field: impl IntoIdiom) -> Self {
+        self.map(|s| s.group_by(field))
+    }
+
+    pub fn timeout(self, duration: Duration) -> Self {
+        self.map(|s| s.timeout(duration))
+    }
+
+    pub fn parallel(self) -> Self {
+        self.map(|s| s.parallel())
+    }
+
+    pub fn allow_full_table(self) -> Self {
+        self.map(|s| s.allow_full_table())
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+}
+
+impl<T, L> TypedSelect<T, Many, L>
+where
+    T: RecordType,
+{
+    /// `SELECT ... FROM ONLY ... LIMIT 1`
+    pub fn only(self) -> TypedSelect<T, One, Limited> {
+        self.map(|s| s.only().limit(1)).into_state()
+    }
+
+    /// `EXPLAIN`, or `EXPLAIN FULL` when `full`
+    pub fn explain(self, full: bool) -> TypedSelect<T, Explained, L> {
+        self.map(|s| s.explain(full)).into_state()
+    }
+
+    pub async fn execute(self) -> Result<Vec<T>> {
+        self.inner.execute().await
+    }
+}
+
+impl<T, M: CanLimit> TypedSelect<T, M, NoLimit>
+where
+    T: RecordType,
+{
+    pub fn limit(self, limit: usize) -> TypedSelect<T, M, Limited> {
+        self.map(|s| s.limit(limit)).into_state()
+    }
+}
+
+impl<T, M> TypedSelect<T, M, Limited>
+where
+    T: RecordType,
+{
+    /// `START` is only available once a `LIMIT` is set:
+    ///
+    /// ```rust
+    /// # use magritte_core::RecordType;
+    /// # use magritte_query::SelectStatement;
+    /// fn page<T: RecordType>(query: SelectStatement<T>) {
+    ///     query.typed().limit(10).start(20);
+    /// }
+    /// ```
+    ///
+    /// ```rust,compile_fail
+    /// # use magritte_core::RecordType;
+    /// # use magritte_query::SelectStatement;
+    /// fn page<T: RecordType>(query: SelectStatement<T>) {
+    ///     query.typed().start(20);
+    /// }
+    /// ```
+    pub fn start(self, start: usize) -> Self {
+        self.map(|s| s.start(&start.to_string()))
+    }
+}
+
+impl<T, M: CanFetch, L> TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    /// `FETCH` is not available on an `EXPLAIN`:
+    ///
+    /// ```rust
+    /// # use magritte_core::RecordType;
+    /// # use magritte_query::SelectStatement;
+    /// fn with_orders<T: RecordType>(query: SelectStatement<T>) {
+    ///     query.typed().fetch(&["orders"]);
+    /// }
+    /// ```
+    ///
+    /// ```rust,compile_fail
+    /// # use magritte_core::RecordType;
+    /// # use magritte_query::SelectStatement;
+    /// fn with_orders<T: RecordType>(query: SelectStatement<T>) {
+    ///     query.typed().explain(true).fetch(&["orders"]);
+    /// }
+    /// ```
+    pub fn fetch(self, fields: &[&str]) -> Self {
+        self.map(|s| s.fetch(fields))
+    }
+}
+
+impl<T> TypedSelect<T, One, Limited>
+where
+    T: RecordType,
+{
+    pub async fn execute(self) -> Result<Option<T>> {
+        Ok(self.inner.execute().await?.into_iter().next())
+    }
+}
+
+impl<T, L> TypedSelect<T, Explained, L>
+where
+    T: RecordType,
+{
+    pub async fn execute(self) -> Result<QueryPlan> {
+        self.inner.plan().await
+    }
+}
+
+impl<T, M, L> From<TypedSelect<T, M, L>> for SelectStatement<T>
+where
+    T: RecordType,
+{
+    fn from(typed: TypedSelect<T, M, L>) -> Self {
+        typed.inner
+    }
+}
+
+impl<T, M, L> HasParams for TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    fn params(&self) -> &Vec<(String, Value)> {
+        self.inner.params()
+    }
+
+    fn params_mut(&mut self) -> &mut Vec<(String, Value)> {
+        self.inner.params_mut()
+    }
+}
+
+impl<T, M, L> HasConditions for TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    fn conditions_mut(&mut self) -> &mut Vec<(String, Operator, SqlValue)> {
+        self.inner.conditions_mut()
+    }
+}
+
+impl<T, M, L> HasProjections for TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    fn projections(&self) -> &Vec<Projection> {
+        self.inner.projections()
+    }
+
+    fn projections_mut(&mut self) -> &mut Vec<Projection> {
+        self.inner.projections_mut()
+    }
+}
+
+impl<T, M, L> StatementBuilder for TypedSelect<T, M, L>
+where
+    T: RecordType,
+{
+    fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        StatementBuilder::with_params(&self.inner)
+    }
+}
+
+/// DELETE whose `ONLY` mode is checked at compile time
+#[derive(Debug, Clone)]
+pub struct TypedDelete<T, M = Many>
+where
+    T: RecordType,
+{
+    inner: DeleteStatement<T>,
+    _state: PhantomData<M>,
+}
+
+impl<T> DeleteStatement<T>
+where
+    T: RecordType,
+{
+    /// Switches to the [`TypedDelete`] builder
+    pub fn typed(self) -> TypedDelete<T> {
+        TypedDelete {
+            inner: self,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<T, M> TypedDelete<T, M>
+where
+    T: RecordType,
+{
+    fn map(self, f: impl FnOnce(DeleteStatement<T>) -> DeleteStatement<T>) -> Self {
+        Self {
+            inner: f(self.inner),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> DeleteStatement<T> {
+        self.inner
+    }
+
+    pub fn where_id(self, id: SurrealId<T>) -> Self {
+        self.map(|s| s.where_id(id))
+    }
+
+    pub fn timeout(self, duration: Duration) -> Self {
+        self.map(|s| s.timeout(duration))
+    }
+
+    pub fn parallel(self) -> Self {
+        self.map(|s| s.parallel())
+    }
+
+    pub fn allow_full_table(self) -> Self {
+        self.map(|s| s.allow_full_table())
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+}
+
+impl<T> TypedDelete<T, Many>
+where
+    T: RecordType,
+{
+    /// `DELETE ONLY ... RETURN BEFORE`, returning the deleted record
+    pub fn only(self) -> TypedDelete<T, One> {
+        TypedDelete {
+            inner: self.inner.only().return_(ReturnType::Before),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn return_(self, return_type: ReturnType) -> Self {
+        self.map(|s| s.return_(return_type))
+    }
+
+    pub fn targets(self, targets: Vec<SurrealId<T>>) -> Result<Self> {
+        Ok(Self {
+            inner: self.inner.targets(targets)?,
+            _state: PhantomData,
+        })
+    }
+
+    pub async fn execute(self) -> Result<Vec<T>> {
+        self.inner.execute().await
+    }
+}
+
+impl<T> TypedDelete<T, One>
+where
+    T: RecordType,
+{
+    pub async fn execute(self) -> Result<Option<T>> {
+        Ok(self.inner.execute().await?.into_iter().next())
+    }
+}
+
+impl<T, M> HasParams for TypedDelete<T, M>
+where
+    T: RecordType,
+{
+    fn params(&self) -> &Vec<(String, Value)> {
+        self.inner.params()
+    }
+
+    fn params_mut(&mut self) -> &mut Vec<(String, Value)> {
+        self.inner.params_mut()
+    }
+}
+
+impl<T, M> HasConditions for TypedDelete<T, M>
+where
+    T: RecordType,
+{
+    fn conditions_mut(&mut self) -> &mut Vec<(String, Operator, SqlValue)> {
+        self.inner.conditions_mut()
+    }
+}
+
+impl<T, M> StatementBuilder for TypedDelete<T, M>
+where
+    T: RecordType,
+{
+    fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        StatementBuilder::with_params(&self.inner)
+    }
+}
+
+/// CREATE whose `ONLY` mode is checked at compile time: an `ONLY` create
+/// takes a single record, so `targets` is not available on it
+#[derive(Debug, Clone)]
+pub struct TypedCreate<T, M = Many>
+where
+    T: RecordType,
+{
+    inner: CreateStatement<T>,
+    _state: PhantomData<M>,
+}
+
+impl<T> CreateStatement<T>
+where
+    T: RecordType,
+{
+    /// Switches to the [`TypedCreate`] builder
+    pub fn typed(self) -> TypedCreate<T> {
+        TypedCreate {
+            inner: self,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<T, M> TypedCreate<T, M>
+where
+    T: RecordType,
+{
+    pub fn into_inner(self) -> CreateStatement<T> {
+        self.inner
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.inner = self.inner.with_id(id);
+        self
+    }
+
+    pub fn content<C: Serialize>(mut self, content: C) -> Result<Self> {
+        self.inner = self.inner.content(content)?;
+        Ok(self)
+    }
+
+    pub fn set<V: Serialize>(mut self, field: &str, value: V) -> Result<Self> {
+        self.inner = self.inner.set(field, value)?;
+        Ok(self)
+    }
+
+    pub fn return_(mut self, return_type: ReturnType) -> Self {
+        self.inner = self.inner.return_(return_type);
+        self
+    }
+
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.inner = self.inner.timeout(duration);
+        self
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+}
+
+impl<T> TypedCreate<T, Many>
+where
+    T: RecordType,
+{
+    /// `CREATE ONLY`, returning a single record
+    pub fn only(self) -> TypedCreate<T, One> {
+        TypedCreate {
+            inner: self.inner.only(),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn targets(mut self, targets: Vec<SurrealId<T>>) -> Result<Self> {
+        self.inner = self.inner.targets(targets)?;
+        Ok(self)
+    }
+
+    pub async fn execute(self) -> Result<Vec<T>> {
+        self.inner.execute().await
+    }
+}
+
+impl<T> TypedCreate<T, One>
+where
+    T: RecordType,
+{
+    pub async fn execute(self) -> Result<Option<T>> {
+        Ok(self.inner.execute().await?.into_iter().next())
+    }
+}
+
+impl<T, M> StatementBuilder for TypedCreate<T, M>
+where
+    T: RecordType,
+{
+    fn build(&self) -> Result<String> {
+        self.inner.build()
+    }
+
+    fn with_params(&self) -> Vec<(String, Value)> {
+        StatementBuilder::with_params(&self.inner)
+    }
+}
+
+/// Index name not set yet
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unnamed;
+/// Index name set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Named;
+/// Index table not set yet
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoTable;
+/// Index table set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OnTable;
+
+/// `DEFINE INDEX` builder that only finishes once it has a name and a table
+#[derive(Debug, Clone, Default)]
+pub struct IndexBuilder<N = Unnamed, Tb = NoTable> {
+    inner: DefineIndexStatement,
+    _state: PhantomData<(N, Tb)>,
+}
+
+impl IndexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<N, Tb> IndexBuilder<N, Tb> {
+    pub fn name(self, name: impl Into<String>) -> IndexBuilder<Named, Tb> {
+        IndexBuilder {
+            inner: self.inner.name(name),
+            _state: PhantomData,
+        }
+    }
+
+    pub fn table(self, table: impl Into<String>) -> IndexBuilder<N, OnTable> {
+        IndexBuilder {
+            inner: self.inner.table(table),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl IndexBuilder<Named, OnTable> {
+    /// The statement, for setting fields, uniqueness or a search/vector
+    /// specification. Only available once both the name and the table are
+    /// set:
+    ///
+    /// ```rust
+    /// # use magritte_query::IndexBuilder;
+    /// IndexBuilder::new().name("email_idx").table("users").finish();
+    /// ```
+    ///
+    /// ```rust,compile_fail
+    /// # use magritte_query::IndexBuilder;
+    /// IndexBuilder::new().name("email_idx").finish();
+    /// ```
+    pub fn finish(self) -> DefineIndexStatement {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::User;
+    use crate::{Define, Query, Validate, WhereClause};
+
+    #[test]
+    fn test_typed_select_only() {
+        let query = Query::select::<User>()
+            .typed()
+            .where_op("email", Operator::Eq, Some("a@b.c"))
+            .unwrap()
+            .only()
+            .fetch(&["orders"]);
+        assert_eq!(
+            query.validate().unwrap(),
+            "SELECT * FROM ONLY users WHERE email = $p0 LIMIT 1 FETCH orders;"
+        );
+        Query::select::<User>()
+            .typed()
+            .limit(10)
+            .start(20)
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+        Query::select::<User>()
+            .typed()
+            .explain(true)
+            .limit(5)
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_typed_mutations_only() {
+        Query::delete::<User>()
+            .typed()
+            .where_id(SurrealId::new("alice"))
+            .only()
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+        Query::create::<User>()
+            .typed()
+            .with_id("bob")
+            .only()
+            .validate()
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    #[test]
+    fn test_index_builder() {
+        let index = Define::index_typed()
+            .name("email_idx")
+            .table("users")
+            .finish()
+            .fields(vec!["email".to_string()])
+            .unique();
+        assert_eq!(
+            index.validate().unwrap(),
+            "DEFINE INDEX email_idx ON users FIELDS email UNIQUE;"
+        );
+    }
+}
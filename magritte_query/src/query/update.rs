@@ -3,13 +3,22 @@
 //! This module contains operations related to updating existing records in
 //! tables.
 
-use crate::{FromTarget, HasConditions, HasParams, HasReturns};
-use anyhow::Result;
+use crate::{Fingerprint, FromTarget, HasAssignments, HasConditions, HasParams, HasReturns};
+use crate::backend::assign::{build_assignments, Assignment};
+use crate::backend::fingerprint::{build_cached, ShapeHasher};
+use crate::backend::intercept;
+use crate::backend::policy::{enforce_mutation, MutationShape};
+use crate::backend::query_result::targets_records;
+use crate::backend::strict::check_fields;
+use crate::backend::duration::duration_to_sql;
+use crate::query::fetch::{at_most_one, exactly_one};
+use anyhow::{bail, Result};
 use magritte_core::operator::Operator;
 use magritte_core::transaction::Transactional;
 use magritte_core::value::SqlValue;
 use magritte_core::{RecordType, ReturnType, SurrealId};
-use magritte_db::db;
+use magritte_db::{db, StatementKind};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 use std::marker::PhantomData;
@@ -34,12 +43,14 @@ where
     with_id: Option<SurrealId<T>>,
     only: bool,
     content: Option<Content>,
+    assignments: Vec<Assignment>,
     conditions: Vec<(String, Operator, SqlValue)>,
     parameters: Vec<(String, serde_json::Value)>,
     parallel: bool,
     timeout: Option<Duration>,
     return_type: Option<ReturnType>,
     in_transaction: bool,
+    allow_full_table: bool,
     _marker: PhantomData<T>,
 }
 
@@ -53,12 +64,14 @@ where
             with_id: None,
             only: false,
             content: None,
+            assignments: vec![],
             conditions: vec![],
             parameters: vec![],
             parallel: false,
             timeout: None,
             return_type: None,
             in_transaction: false,
+            allow_full_table: false,
             _marker: PhantomData,
         }
     }
@@ -68,6 +81,13 @@ impl<T> UpdateStatement<T>
 where
     T: RecordType,
 {
+    /// Exempts the statement from the safety policy's guard on updates
+    /// without conditions
+    pub fn allow_full_table(mut self) -> Self {
+        self.allow_full_table = true;
+        self
+    }
+
     #[instrument(skip_all)]
     pub fn content<C: Serialize>(mut self, content: &C) -> anyhow::Result<Self> {
         self.content = Some(Content::Content(serde_json::to_value(content)?));
@@ -116,14 +136,31 @@ where
         query.push_str("UPDATE ");
         if self.only {
             query.push_str("ONLY ");
-        } else if let Some(targets) = &self.targets {
-            if !targets.is_empty() {
-                for v in targets {
-                    query.push_str(&format!("{},", v.to_string().as_str()));
-                }
-            }
         }
 
+        if let Some(targets) = self.targets.as_ref().filter(|t| !self.only && !t.is_empty()) {
+            let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+            query.push_str(&targets.join(", "));
+        } else if let Some(id) = &self.with_id {
+            query.push_str(&id.to_string());
+        } else {
+            query.push_str(T::table_name());
+        }
+
+        check_fields(
+            T::table_name(),
+            self.assignments.iter().map(Assignment::path).chain(
+                self.conditions
+                    .iter()
+                    .filter(|(_, op, _)| *op != Operator::Raw)
+                    .map(|(field, _, _)| field.as_str()),
+            ),
+        )?;
+        if self.content.is_some() && !self.assignments.is_empty() {
+            bail!("SET/UNSET cannot be combined with CONTENT, MERGE, PATCH or REPLACE");
+        }
+        query.push_str(&build_assignments(&self.assignments));
+
         if let Some(content) = &self.content {
             match content {
                 Content::Content(value) => {
@@ -183,9 +220,6 @@ where
             query.push_str(&conditions.join(" AND "));
         }
 
-        if let Some(timeout) = &self.timeout {
-            query.push_str(&format!(" TIMEOUT {}", timeout.as_secs()));
-        }
         if let Some(return_type) = &self.return_type {
             match return_type {
                 ReturnType::All => query.push_str(" RETURN AFTER"),
@@ -200,6 +234,9 @@ where
             }
         }
 
+        if let Some(timeout) = &self.timeout {
+            query.push_str(&format!(" TIMEOUT {}", duration_to_sql(timeout)));
+        }
         if self.parallel {
             query.push_str(" PARALLEL");
         }
@@ -207,10 +244,92 @@ where
         Ok(query)
     }
 
+    /// Checks the safety policy and applies the registered interceptors,
+    /// returning the comment they tag the statement with
+    pub(crate) fn prepare(&mut self) -> Result<String> {
+        let targets_records = self.with_id.is_some() || targets_records(&self.targets);
+        enforce_mutation(MutationShape {
+            kind: "UPDATE",
+            table: T::table_name(),
+            targets_records,
+            has_conditions: !self.conditions.is_empty(),
+            allow_full_table: self.allow_full_table,
+        })?;
+        intercept::apply(self, StatementKind::Update, T::table_name(), targets_records)
+    }
+
+    /// Prepares the statement for execution and renders it
+    pub(crate) fn build_prepared(&mut self) -> Result<String> {
+        let comment = self.prepare()?;
+        Ok(format!("{}{}", comment, build_cached(self)?))
+    }
+
     pub async fn execute(self) -> Result<Vec<T>> {
-        db().execute(self.build()?, self.parameters).await
+        self.fetch_as().await
+    }
+
+    /// Runs the query and decodes the returned rows into `R`, for RETURN
+    /// projections that do not match the record
+    #[instrument(skip_all, fields(fingerprint = self.fingerprint()))]
+    pub async fn fetch_as<R>(mut self) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        let query = self.build_prepared()?;
+        if self.only {
+            let row = db().execute_optional(query, self.parameters).await?;
+            return Ok(row.into_iter().collect());
+        }
+        db().execute(query, self.parameters).await
+    }
+
+    /// Returns the only affected row, failing when there are none or several
+    #[instrument(skip_all)]
+    pub async fn fetch_one(self) -> Result<T> {
+        exactly_one(self.fetch_as().await?)
+    }
+
+    /// Returns the affected row if there is one, failing when there are
+    /// several
+    #[instrument(skip_all)]
+    pub async fn fetch_optional(self) -> Result<Option<T>> {
+        at_most_one(self.fetch_as().await?)
+    }
+}
+
+impl<T> Fingerprint for UpdateStatement<T>
+where
+    T: RecordType,
+{
+    fn fingerprint(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.targets.as_ref().map(Vec::len))
+            .part(&self.with_id.is_some())
+            .part(&self.only)
+            .part(&self.content.as_ref().map(std::mem::discriminant))
+            .part(&self.assignments)
+            .part(&self.conditions)
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .finish()
+    }
+
+    fn render_key(&self) -> u64 {
+        ShapeHasher::new(T::table_name())
+            .part(&self.targets)
+            .part(&self.with_id)
+            .part(&self.only)
+            .part(&self.content)
+            .part(&self.assignments)
+            .part(&self.conditions)
+            .part(&self.parallel)
+            .part(&self.timeout)
+            .part(&self.return_type)
+            .finish()
     }
 }
+
 impl<T> HasReturns for UpdateStatement<T>
 where
     T: RecordType,
@@ -220,6 +339,23 @@ where
     }
 }
 
+impl<T> HasAssignments for UpdateStatement<T>
+where
+    T: RecordType,
+{
+    fn assignment_table(&self) -> &'static str {
+        T::table_name()
+    }
+
+    fn assignments(&self) -> &Vec<Assignment> {
+        &self.assignments
+    }
+
+    fn assignments_mut(&mut self) -> &mut Vec<Assignment> {
+        &mut self.assignments
+    }
+}
+
 impl<T> HasParams for UpdateStatement<T>
 where
     T: RecordType,
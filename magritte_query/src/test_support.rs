@@ -0,0 +1,38 @@
+//! Record types for the unit tests of the builders
+
+use std::fmt::{self, Display};
+
+use magritte_core::{NamedType, RecordType};
+use serde::{Deserialize, Serialize};
+
+macro_rules! test_record {
+    ($name:ident, $table:literal) => {
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        pub(crate) struct $name {}
+
+        impl NamedType for $name {
+            fn table_name() -> &'static str {
+                $table
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str($table)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                $table
+            }
+        }
+
+        impl RecordType for $name {}
+    };
+}
+
+test_record!(User, "users");
+test_record!(Product, "products");
+test_record!(Order, "orders");
+test_record!(Invoice, "invoices");
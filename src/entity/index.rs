@@ -1,5 +1,6 @@
-use magritte_core::{IndexSpecifics, IndexType, NamedType};
-use magritte_query::{Define, DefineIndexStatement};
+use anyhow::{anyhow, Result};
+use magritte_core::{IndexSpecifics, IndexType, NamedType, RecordType};
+use magritte_query::{Define, DefineIndexStatement, SelectStatement, WhereClause};
 use std::fmt::{Debug, Display};
 
 /// Defines an Index for a Table
@@ -89,6 +90,31 @@ impl IndexDef {
     pub fn comment(&self) -> Option<&str> {
         self.comment.as_deref()
     }
+    pub fn is_search(&self) -> bool {
+        matches!(self.specifics, IndexSpecifics::Search { .. })
+    }
+    /// The analyzer of a SEARCH index
+    pub fn search_analyzer(&self) -> Option<&str> {
+        match &self.specifics {
+            IndexSpecifics::Search { analyzer, .. } => analyzer.as_deref(),
+            _ => None,
+        }
+    }
+    /// The field covered by a SEARCH index, which indexes exactly one field
+    pub fn search_field(&self) -> Option<&str> {
+        if !self.is_search() {
+            return None;
+        }
+        let mut fields = self
+            .fields
+            .iter()
+            .chain(self.columns.iter())
+            .flatten();
+        match (fields.next(), fields.next()) {
+            (Some(field), None) => Some(field.as_str()),
+            _ => None,
+        }
+    }
     pub fn to_statement(&self) -> DefineIndexStatement {
         let mut def = Define::index();
 
@@ -126,3 +152,35 @@ impl IndexDef {
         def
     }
 }
+
+/// Full-text search through a `#[index(search(...))]` definition
+pub trait FullTextSearch: Sized {
+    type Entity: NamedType;
+
+    /// Matches `text` against the field of a SEARCH index, binding it to
+    /// `predicate_ref` for `search::score` and `search::highlight`
+    fn search<I>(self, index: I, text: &str, predicate_ref: u8) -> Result<Self>
+    where
+        I: IndexTrait<EntityName = Self::Entity>;
+}
+
+impl<T> FullTextSearch for SelectStatement<T>
+where
+    T: RecordType,
+{
+    type Entity = T;
+
+    fn search<I>(self, index: I, text: &str, predicate_ref: u8) -> Result<Self>
+    where
+        I: IndexTrait<EntityName = T>,
+    {
+        let def = index.def();
+        let field = def.search_field().ok_or_else(|| {
+            anyhow!(
+                "Index {} is not a SEARCH index on a single field",
+                def.index_name()
+            )
+        })?;
+        self.where_matches(field, text, Some(predicate_ref))
+    }
+}
@@ -8,10 +8,14 @@ pub mod column;
 pub mod edge;
 pub mod event;
 pub mod index;
+pub mod relate;
 pub mod relation;
 pub mod table;
+pub mod traverse;
 
+pub use relate::Relate;
 pub use relation::{LoadStrategy, RelationDef, RelationTrait};
+pub use traverse::{Traversable, Traverse};
 
 pub trait HasColumns {
     fn columns() -> Vec<impl ColumnTrait>;
@@ -0,0 +1,128 @@
+use crate::EdgeTrait;
+use anyhow::{bail, Result};
+use magritte_core::{ReturnType, SurrealId};
+use magritte_query::{HasParams, HasReturns, RelateStatement, StatementBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Typed RELATE builder for an edge.
+///
+/// Both ends are [`SurrealId`]s of the edge's `EntityFrom` and `EntityTo`, so
+/// relating the wrong tables does not compile.
+///
+/// ```rust,ignore
+/// let edges: Vec<Purchased> = Relate::<Purchased>::between(&user_id, &product_id)
+///     .content(&Purchased::new(2))?
+///     .execute()
+///     .await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Relate<E>
+where
+    E: EdgeTrait,
+{
+    statement: RelateStatement,
+    _marker: PhantomData<E>,
+}
+
+impl<E> Relate<E>
+where
+    E: EdgeTrait,
+{
+    /// `RELATE from->edge->to`
+    pub fn between(from: &SurrealId<E::EntityFrom>, to: &SurrealId<E::EntityTo>) -> Self {
+        Self::from_records(from.to_string(), to.to_string())
+    }
+
+    /// `RELATE [a, b]->edge->[c, d]`, creating an edge for every pair
+    pub fn many(from: &[SurrealId<E::EntityFrom>], to: &[SurrealId<E::EntityTo>]) -> Self {
+        Self::from_records(record_list(from), record_list(to))
+    }
+
+    fn from_records(from: String, to: String) -> Self {
+        Self {
+            statement: RelateStatement::new()
+                .from_record(&from)
+                .edge_table(E::table_name())
+                .to_record(&to),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Uses the fields of `edge` as the edge content. `in` and `out` are set
+    /// by RELATE and are dropped from the content.
+    pub fn content(mut self, edge: &E) -> Result<Self> {
+        let mut content = serde_json::to_value(edge)?;
+        match &mut content {
+            Value::Object(map) => {
+                map.remove("in");
+                map.remove("out");
+            }
+            _ => bail!("Edge {} must serialize to an object", E::table_name()),
+        }
+        self.statement = self.statement.content(content)?;
+        Ok(self)
+    }
+
+    /// Sets a single field on the edge
+    pub fn set<V: Serialize>(mut self, field: &str, value: V) -> Result<Self> {
+        self.statement = self.statement.set(field, value)?;
+        Ok(self)
+    }
+
+    /// `RELATE ONLY`, for a single pair of records
+    pub fn only(mut self) -> Self {
+        self.statement = self.statement.only();
+        self
+    }
+
+    pub fn return_(mut self, return_type: ReturnType) -> Self {
+        self.statement = self.statement.return_(return_type);
+        self
+    }
+
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        self.statement = self.statement.timeout(duration);
+        self
+    }
+
+    pub fn parallel(mut self) -> Self {
+        self.statement = self.statement.parallel();
+        self
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.statement.build()
+    }
+
+    /// Creates the edges and returns them as `E`
+    pub async fn execute(self) -> Result<Vec<E>> {
+        self.statement.fetch_as().await
+    }
+
+    pub fn into_statement(self) -> RelateStatement {
+        self.statement
+    }
+}
+
+impl<E> StatementBuilder for Relate<E>
+where
+    E: EdgeTrait,
+{
+    fn build(&self) -> Result<String> {
+        self.statement.build()
+    }
+    fn with_params(&self) -> Vec<(String, Value)> {
+        self.statement.params().clone()
+    }
+    fn build_prepared(&mut self) -> Result<String> {
+        self.statement.build_prepared()
+    }
+}
+
+fn record_list<T: magritte_core::RecordType>(ids: &[SurrealId<T>]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    format!("[{}]", ids.join(", "))
+}
@@ -1,7 +1,7 @@
-use crate::{EdgeTrait, TableTrait};
-use anyhow::Result;
+use crate::{EdgeTrait, Relate, TableTrait};
+use anyhow::{bail, Result};
 use async_trait::async_trait;
-use magritte_core::{HasId, RelationType, SurrealId};
+use magritte_core::{HasId, RecordType, RelationType, SurrealId};
 use magritte_query::{GraphTraversal, Query, RelateStatement, SelectStatement};
 use std::fmt::{Debug, Display};
 
@@ -34,20 +34,20 @@ pub trait RelationTrait: RelationType {
         Self::def()
     }
 
+    /// RELATE between the records with the keys `from_id` and `to_id`
     fn relate(from_id: &str, to_id: &str) -> Result<RelateStatement> {
-        let def = Self::def();
-        let from_record = format!("{}:{}", def.relation_from(), from_id);
-        let to_record = format!("{}:{}", def.relation_to(), to_id);
-
-        let mut stmt = Query::relate()
-            .from_record(&from_record)
-            .to_record(&to_record)
-            .edge_table(&def.via);
+        Self::def().relate(
+            &SurrealId::<Self::Source>::new(from_id),
+            &SurrealId::<Self::Target>::new(to_id),
+        )
+    }
 
-        if let Some(content) = def.content() {
-            stmt = stmt.content(content).map_err(anyhow::Error::from)?;
-        }
-        Ok(stmt)
+    /// Typed RELATE between a source and a target record
+    fn relate_ids(from: &SurrealId<Self::Source>, to: &SurrealId<Self::Target>) -> Relate<Self::Edge>
+    where
+        Self::Edge: EdgeTrait<EntityFrom = Self::Source, EntityTo = Self::Target>,
+    {
+        Relate::between(from, to)
     }
 
     /// Check if this relation should be loaded eagerly
@@ -73,14 +73,27 @@ impl RelationDef {
         }
     }
 
-    /// Create a relate statement for this relation
-    pub fn relate(&self, from_id: &str, to_id: &str) -> Result<RelateStatement> {
-        let from_record = format!("{}:{}", self.relation_from(), from_id);
-        let to_record = format!("{}:{}", self.relation_to(), to_id);
+    /// Create a relate statement for this relation. Fails if the ids are not
+    /// records of the tables the relation connects.
+    pub fn relate<F, T>(&self, from: &SurrealId<F>, to: &SurrealId<T>) -> Result<RelateStatement>
+    where
+        F: RecordType,
+        T: RecordType,
+    {
+        if from.table() != self.relation_from() || to.table() != self.relation_to() {
+            bail!(
+                "Relation {} connects {} to {}, not {} to {}",
+                self.via,
+                self.relation_from(),
+                self.relation_to(),
+                from.table(),
+                to.table()
+            );
+        }
 
         let mut stmt = Query::relate()
-            .from_record(&from_record)
-            .to_record(&to_record)
+            .from_record(&from.to_string())
+            .to_record(&to.to_string())
             .edge_table(&self.via);
 
         if let Some(content) = self.content() {
@@ -109,6 +122,6 @@ impl RelationDef {
 #[macro_export]
 macro_rules! relate {
     ($relation:ty, $from_id:expr, $to_id:expr) => {{
-        <$relation as RelationTrait>::relate_ids($from_id, $to_id)?
+        <$relation as RelationTrait>::relate_ids($from_id, $to_id)
     }};
 }
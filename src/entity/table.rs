@@ -1,9 +1,9 @@
 use crate::entity::{HasColumns, HasEvents, HasIndexes, HasRelations};
 use crate::{ColumnTrait, EventTrait, IndexTrait, RelationTrait};
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use magritte_core::{Permission, RecordRef, Relations, SchemaType, TableType};
 use magritte_query::define::define_table::DefineTableStatement;
-use magritte_query::Define;
+use magritte_query::{AlterStatement, Define, Query, TableKind};
 use std::fmt::{Debug, Display};
 use std::time::Duration;
 
@@ -74,6 +74,7 @@ pub trait TableTrait: TableType + HasColumns {
 pub struct TableDef {
     pub(crate) name: String,
     pub(crate) schema_type: SchemaType,
+    pub(crate) kind: TableKind,
     pub(crate) overwrite: bool,
     pub(crate) if_not_exists: bool,
     pub(crate) permissions: Option<Vec<Permission>>,
@@ -99,6 +100,8 @@ impl TableDef {
         Self {
             name: name.into(),
             schema_type: SchemaType::from(schema_type.into()),
+            // DEFINE TABLE statements built from a TableDef are TYPE NORMAL
+            kind: TableKind::Normal,
             overwrite,
             if_not_exists,
             permissions: permissions
@@ -121,6 +124,9 @@ impl TableDef {
     pub fn schema_type(&self) -> &SchemaType {
         &self.schema_type
     }
+    pub fn kind(&self) -> &TableKind {
+        &self.kind
+    }
     pub fn is_overwrite(&self) -> bool {
         self.overwrite
     }
@@ -178,6 +184,133 @@ impl TableDef {
 
         def
     }
+
+    /// Reads a `DEFINE TABLE` statement, as stored in schema snapshots or
+    /// returned by `INFO FOR DB`
+    pub fn from_define(statement: &str) -> anyhow::Result<Self> {
+        use surrealdb::sql::statements::DefineStatement;
+        use surrealdb::sql::{
+            Kind, Permission as SqlPermission, Statement, TableType as SqlTableType,
+        };
+
+        let query = surrealdb::sql::parse(statement)?;
+        let Some(Statement::Define(DefineStatement::Table(table))) = query.0 .0.into_iter().next()
+        else {
+            bail!("Not a DEFINE TABLE statement: {}", statement);
+        };
+
+        let perms = &table.permissions;
+        let all = [&perms.select, &perms.create, &perms.update, &perms.delete];
+        let permissions = if all.iter().all(|p| matches!(p, SqlPermission::None)) {
+            None
+        } else if all.iter().all(|p| matches!(p, SqlPermission::Full)) {
+            Some(vec![Permission::Full])
+        } else {
+            let kinds: [fn(String) -> Permission; 4] = [
+                Permission::Select,
+                Permission::Create,
+                Permission::Update,
+                Permission::Delete,
+            ];
+            let specific = all.iter().zip(kinds).filter_map(|(perm, kind)| match perm {
+                SqlPermission::Specific(condition) => Some(kind(condition.to_string())),
+                SqlPermission::Full => Some(kind("true".to_string())),
+                _ => None,
+            });
+            Some(specific.collect())
+        };
+
+        let tables = |kind: &Option<Kind>| {
+            kind.as_ref().map(|kind| match kind {
+                Kind::Record(tables) => tables
+                    .iter()
+                    .map(|t| t.0.clone())
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                other => other.to_string(),
+            })
+        };
+        let kind = match &table.kind {
+            SqlTableType::Any => TableKind::Any,
+            SqlTableType::Normal => TableKind::Normal,
+            SqlTableType::Relation(relation) => TableKind::Relation {
+                from: tables(&relation.from),
+                to: tables(&relation.to),
+                enforced: relation.enforced,
+            },
+        };
+
+        Ok(Self {
+            name: table.name.0.clone(),
+            schema_type: if table.full {
+                SchemaType::Schemafull
+            } else {
+                SchemaType::Schemaless
+            },
+            kind,
+            overwrite: table.overwrite,
+            if_not_exists: table.if_not_exists,
+            permissions,
+            drop: table.drop,
+            as_select: table.view.as_ref().map(|view| {
+                let view = view.to_string();
+                view.trim_start_matches("AS SELECT ").to_string()
+            }),
+            changefeed: table.changefeed.map(|cf| (cf.expiry, cf.store_diff)),
+            comment: table.comment.as_ref().map(|c| c.0.clone()),
+        })
+    }
+
+    /// The `ALTER TABLE` turning this definition into `new`, or `None` when
+    /// the table options are the same. Fails for changes ALTER cannot make,
+    /// which need a `DEFINE TABLE OVERWRITE`: a different name or AS SELECT,
+    /// or undoing DROP.
+    pub fn alter_to(&self, new: &TableDef) -> anyhow::Result<Option<AlterStatement>> {
+        if self.name != new.name {
+            bail!("Cannot ALTER table {} into {}", self.name, new.name);
+        }
+        if self.as_select != new.as_select {
+            bail!("Changing the AS SELECT of {} needs DEFINE TABLE OVERWRITE", new.name);
+        }
+        if self.drop && !new.drop {
+            bail!("Undoing DROP on {} needs DEFINE TABLE OVERWRITE", new.name);
+        }
+
+        let mut alter = Query::alter().table(&new.name);
+        if new.drop && !self.drop {
+            alter = alter.drop();
+        }
+        if self.schema_type != new.schema_type {
+            alter = match new.schema_type {
+                SchemaType::Schemafull => alter.schemafull(),
+                SchemaType::Schemaless => alter.schemaless(),
+            };
+        }
+        if self.kind != new.kind {
+            alter = alter.kind(new.kind.clone());
+        }
+        // Tables without PERMISSIONS deny everything, as PERMISSIONS NONE does
+        let effective = |p: &[Permission]| match p {
+            [] => vec![Permission::None],
+            p => p.to_vec(),
+        };
+        if effective(self.permissions()) != effective(new.permissions()) {
+            alter = alter.permissions(effective(new.permissions()));
+        }
+        if self.changefeed != new.changefeed {
+            alter = match new.changefeed {
+                Some((duration, include_original)) => alter.changefeed(duration, include_original),
+                None => alter.drop_changefeed(),
+            };
+        }
+        if self.comment != new.comment {
+            alter = match &new.comment {
+                Some(comment) => alter.comment(comment),
+                None => alter.drop_comment(),
+            };
+        }
+        Ok(alter.has_changes().then_some(alter))
+    }
 }
 
 impl<T> From<DefineTableStatement<T>> for TableDef
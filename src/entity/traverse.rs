@@ -0,0 +1,245 @@
+use crate::{ColumnTrait, RelationTrait, TableTrait};
+use anyhow::{bail, Result};
+use magritte_core::operator::Operator;
+use magritte_core::{ColumnType, HasId, NamedType, RecordType, RelationDirection, SurrealId};
+use magritte_query::{HasParams, Query, SelectStatement, WhereClause};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// Alias of the traversal projection when rows are decoded as tuples
+const RELATED_FIELD: &str = "__related";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Hop {
+    direction: RelationDirection,
+    edge: &'static str,
+    node: &'static str,
+    edge_conditions: Vec<String>,
+    node_conditions: Vec<String>,
+}
+
+impl Hop {
+    fn render(&self) -> String {
+        format!(
+            "{dir}{}{dir}{}",
+            filtered(self.edge, &self.edge_conditions),
+            filtered(self.node, &self.node_conditions),
+            dir = self.direction
+        )
+    }
+}
+
+fn filtered(table: &str, conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        table.to_string()
+    } else {
+        format!("({} WHERE {})", table, conditions.join(" AND "))
+    }
+}
+
+/// Typed graph traversal starting from records of `S` and ending on `T`.
+///
+/// Each hop is a relation generated by `#[derive(Relation)]`; following a
+/// relation whose source is not the current node does not compile.
+///
+/// ```rust,ignore
+/// // users:alice->purchased->product, products over 10 only
+/// let rows: Vec<(User, Vec<Product>)> = alice
+///     .traverse::<UserRelationsPurchasedRelation>()
+///     .where_op("price", Operator::Gt, 10)?
+///     .execute()
+///     .await?;
+///
+/// // Who else bought what alice bought: ->purchased->product<-purchased<-user
+/// let rows: Vec<(User, Vec<User>)> = alice
+///     .traverse::<UserRelationsPurchasedRelation>()
+///     .back::<UserRelationsPurchasedRelation>()
+///     .execute()
+///     .await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Traverse<S, T>
+where
+    S: TableTrait,
+    T: RecordType,
+{
+    source: SelectStatement<S>,
+    hops: Vec<Hop>,
+    _marker: PhantomData<T>,
+}
+
+impl<S> Traverse<S, S>
+where
+    S: TableTrait,
+{
+    /// Starts from a single record
+    pub fn from_id(id: SurrealId<S>) -> Self {
+        Self::from_select(Query::select::<S>().where_id(id))
+    }
+
+    /// Starts from every record of `S` matching `select`
+    pub fn from_select(select: SelectStatement<S>) -> Self {
+        Self {
+            source: select,
+            hops: vec![],
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S, T> Traverse<S, T>
+where
+    S: TableTrait,
+    T: RecordType,
+{
+    /// Follows `R` outwards: `->edge->target`
+    pub fn then<R>(self) -> Traverse<S, R::Target>
+    where
+        R: RelationTrait<Source = T>,
+    {
+        self.hop(
+            RelationDirection::Out,
+            <R::Edge as NamedType>::table_name(),
+            <R::Target as NamedType>::table_name(),
+        )
+    }
+
+    /// Follows `R` backwards: `<-edge<-source`
+    pub fn back<R>(self) -> Traverse<S, R::Source>
+    where
+        R: RelationTrait<Target = T>,
+    {
+        self.hop(
+            RelationDirection::In,
+            <R::Edge as NamedType>::table_name(),
+            <R::Source as NamedType>::table_name(),
+        )
+    }
+
+    fn hop<U: RecordType>(
+        mut self,
+        direction: RelationDirection,
+        edge: &'static str,
+        node: &'static str,
+    ) -> Traverse<S, U> {
+        self.hops.push(Hop {
+            direction,
+            edge,
+            node,
+            edge_conditions: vec![],
+            node_conditions: vec![],
+        });
+        Traverse {
+            source: self.source,
+            hops: self.hops,
+            _marker: PhantomData,
+        }
+    }
+
+    fn bind<V: Serialize>(&mut self, field: &str, op: Operator, value: V) -> Result<String> {
+        let params = self.source.params_mut();
+        let name = format!("p{}", params.len());
+        params.push((name.clone(), serde_json::to_value(value)?));
+        Ok(format!("{} {} ${}", field, String::from(op), name))
+    }
+
+    /// Filters the records reached by the last hop
+    pub fn where_op<V: Serialize>(mut self, field: &str, op: Operator, value: V) -> Result<Self> {
+        if self.hops.is_empty() {
+            self.source = self.source.where_op(field, op, Some(value))?;
+            return Ok(self);
+        }
+        let condition = self.bind(field, op, value)?;
+        if let Some(hop) = self.hops.last_mut() {
+            hop.node_conditions.push(condition);
+        }
+        Ok(self)
+    }
+
+    /// Filters the records reached by the last hop on a column of `T`
+    pub fn where_column<C, V>(self, column: C, op: Operator, value: V) -> Result<Self>
+    where
+        C: ColumnTrait<EntityName = T>,
+        V: Serialize,
+    {
+        self.where_op(column.column_name(), op, value)
+    }
+
+    /// Filters the edges crossed by the last hop
+    pub fn where_edge<V: Serialize>(mut self, field: &str, op: Operator, value: V) -> Result<Self> {
+        if self.hops.is_empty() {
+            bail!("where_edge needs a traversal step");
+        }
+        let condition = self.bind(field, op, value)?;
+        if let Some(hop) = self.hops.last_mut() {
+            hop.edge_conditions.push(condition);
+        }
+        Ok(self)
+    }
+
+    /// The graph path, e.g. `->purchased->products<-purchased<-users`
+    pub fn path(&self) -> String {
+        self.hops.iter().map(Hop::render).collect()
+    }
+
+    /// The SELECT returning each source record with the reached records
+    /// under `alias`
+    pub fn to_select(&self, alias: &str) -> SelectStatement<S> {
+        self.source
+            .clone()
+            .raw("*")
+            .raw_as(&format!("{}.*", self.path()), alias)
+    }
+
+    pub fn build(&self) -> Result<String> {
+        self.to_select(RELATED_FIELD).build()
+    }
+
+    /// Returns each source record with the records reached from it
+    pub async fn execute(self) -> Result<Vec<(S, Vec<T>)>> {
+        let rows: Vec<TraversalRow<S, T>> = self.to_select(RELATED_FIELD).fetch_as().await?;
+        Ok(rows.into_iter().map(|row| (row.source, row.related)).collect())
+    }
+
+    /// Returns the reached records of every source, in order
+    pub async fn targets(self) -> Result<Vec<T>> {
+        Ok(self
+            .execute()
+            .await?
+            .into_iter()
+            .flat_map(|(_, related)| related)
+            .collect())
+    }
+
+    /// Decodes each row into `R`, a struct flattening `S` with the reached
+    /// records in a field named `alias`
+    pub async fn fetch_as<R>(self, alias: &str) -> Result<Vec<R>>
+    where
+        R: DeserializeOwned + Send + 'static,
+    {
+        self.to_select(alias).fetch_as().await
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "S: DeserializeOwned, T: DeserializeOwned")]
+struct TraversalRow<S, T> {
+    #[serde(flatten)]
+    source: S,
+    #[serde(rename = "__related", default = "Vec::new")]
+    related: Vec<T>,
+}
+
+/// Graph traversal from a loaded record
+pub trait Traversable: TableTrait + HasId {
+    /// Starts a traversal from this record along `R`
+    fn traverse<R>(&self) -> Traverse<Self, R::Target>
+    where
+        R: RelationTrait<Source = Self>,
+    {
+        Traverse::from_id(self.id()).then::<R>()
+    }
+}
+
+impl<T: TableTrait + HasId> Traversable for T {}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use magritte_core::{HasId, RecordType, SurrealId};
 use magritte_query::{
-    DeleteStatement, InsertStatement, Query, SelectStatement, UpdateStatement, UpsertStatement,
+    DeleteStatement, InsertStatement, IntoTimestamp, Query, SelectStatement, ShowChangesStatement,
+    UpdateStatement, UpsertStatement,
 };
 use std::fmt::{Debug, Display};
 
@@ -19,6 +20,18 @@ where
         Ok(Query::select().where_id(id))
     }
 
+    /// The record as it was at `at`, on a versioned storage engine
+    fn find_by_id_at(id: SurrealId<T>, at: impl IntoTimestamp) -> Result<SelectStatement<T>> {
+        Query::select().where_id(id).as_of(at)
+    }
+
+    /// Versions of the record recorded in the table changefeed. The table
+    /// needs `CHANGEFEED`, e.g. `#[table(changefeed = "60")]` for an hour of
+    /// history, and only changes within that period are returned.
+    fn versions_of(id: &SurrealId<T>) -> Result<ShowChangesStatement<T>> {
+        Ok(Query::changes().for_record(id))
+    }
+
     fn upsert_by_id(id: SurrealId<T>, entity: T) -> Result<UpsertStatement<T>> {
         Query::upsert()
             .where_id(id)
@@ -44,7 +57,7 @@ where
         Ok(Query::delete().where_id(id))
     }
     fn delete_all() -> anyhow::Result<DeleteStatement<T>> {
-        Ok(Query::delete())
+        Ok(Query::delete().allow_full_table())
     }
 }
 
@@ -72,13 +85,19 @@ where
         Ok(Query::select().only())
     }
     fn upsert(&self) -> anyhow::Result<UpsertStatement<T>> {
-        Query::upsert().content(self).map_err(anyhow::Error::from)
+        Query::upsert()
+            .where_id(self.id())
+            .content(self)
+            .map_err(anyhow::Error::from)
     }
     fn update(&self) -> anyhow::Result<UpdateStatement<T>> {
-        Query::update().content(self).map_err(anyhow::Error::from)
+        Query::update()
+            .where_id(self.id())
+            .content(self)
+            .map_err(anyhow::Error::from)
     }
 
     fn delete(&self) -> anyhow::Result<DeleteStatement<T>> {
-        Ok(Query::delete())
+        Ok(Query::delete().where_id(self.id()))
     }
 }
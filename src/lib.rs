@@ -93,17 +93,20 @@ pub use defs::*;
 pub use entity::column::ColumnTrait;
 pub use entity::edge::EdgeTrait;
 pub use entity::event::EventTrait;
-pub use entity::index::IndexTrait;
+pub use entity::index::{FullTextSearch, IndexTrait};
+pub use entity::relate::Relate;
 pub use entity::relation::RelationTrait;
 pub use entity::table::TableTrait;
+pub use entity::traverse::{Traversable, Traverse};
 pub use entity::HasColumns;
 pub use entity::HasEvents;
 pub use entity::HasIndexes;
 pub use entity::HasRelations;
 pub use magritte_core::{
-    ColumnType, ColumnTypeLite, EdgeType, EventType, FieldType, HasId, IndexSpecifics, IndexType,
-    NamedType, Permission, Record, RecordRef, RecordType, RelationType, Relations, SchemaType,
-    SurrealId, TableType, VectorCondition, VectorDistance, VectorType,
+    ColumnType, ColumnTypeLite, EdgeType, EventType, FieldType, HasId, IdGenerator, IdKey,
+    IndexSpecifics, IndexType, KnnSearch, NamedType, Permission, Record, RecordRef, RecordType,
+    RelationType, Relations, SchemaType, SurrealId, TableType, VectorCondition, VectorDistance,
+    VectorType,
 };
 pub use magritte_macros::EnumIter;
 pub use magritte_macros::*;
@@ -169,3 +172,5 @@ use crate::entity_crud::SurrealCrud;
 
 #[cfg(feature = "with-uuid")]
 pub use uuid::{Uuid, Version};
+#[cfg(feature = "with-geo")]
+pub use geo;
@@ -1,7 +1,12 @@
-use crate::{ColumnTrait, EdgeTrait, EventTrait, HasEvents, HasIndexes, IndexTrait, TableTrait};
+use crate::{
+    ColumnTrait, EdgeRegistration, EdgeTrait, EventTrait, HasEvents, HasIndexes, IndexTrait,
+    TableRegistration, TableTrait,
+};
+use magritte_query::strict::{set_schema_provider, set_strict_mode, with_schema_provider};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::OnceLock;
 use serde::de::DeserializeOwned;
 use tracing::event;
 
@@ -204,3 +209,40 @@ where
 pub fn empty_schema() -> SchemaSnapshot {
     SchemaSnapshot::new()
 }
+
+/// Column names of every registered table and edge, built once
+fn registered_columns() -> &'static HashMap<String, Vec<String>> {
+    static COLUMNS: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+    COLUMNS.get_or_init(|| {
+        let mut columns = HashMap::new();
+        for reg in inventory::iter::<TableRegistration> {
+            if let Ok(table) = (reg.builder)() {
+                columns.insert(table.name, table.fields.into_keys().collect());
+            }
+        }
+        for reg in inventory::iter::<EdgeRegistration> {
+            if let Ok(edge) = (reg.builder)() {
+                columns.insert(edge.name, edge.fields.into_keys().collect());
+            }
+        }
+        columns
+    })
+}
+
+/// Turns on strict mode, checking field names in builders against the
+/// columns of the registered tables and edges. See
+/// [`magritte_query::strict`].
+pub fn enable_strict_mode() {
+    set_schema_provider(registered_table_columns);
+    set_strict_mode(true);
+}
+
+/// Runs `f` with strict mode on for the current thread only, checking
+/// against the registered tables and edges
+pub fn with_strict_mode<R>(f: impl FnOnce() -> R) -> R {
+    with_schema_provider(registered_table_columns, f)
+}
+
+fn registered_table_columns(table: &str) -> Option<Vec<String>> {
+    registered_columns().get(table).cloned()
+}